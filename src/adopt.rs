@@ -0,0 +1,88 @@
+use crate::networking::{public_interface, vlan_interface};
+use crate::regions::RegionInfo;
+use crate::{
+    Interfaces, LinodeClient, LinodeError, A_RECORD, BOOT_TIMEOUT, STATUS_POLL_INTERVAL,
+    STATUS_RUNNING,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdoptReport {
+    pub tags_added: Vec<String>,
+    pub vlan_ip: Option<String>,
+    pub dns_name: Option<String>,
+}
+
+impl LinodeClient {
+    // bring a hand-created instance under the scaler's conventions: fleet
+    // tags, a VLAN interface with a free address, and a sequential A record
+    pub async fn adopt_instance(
+        &self,
+        id: u64,
+        tag: &str,
+        region: &RegionInfo,
+        domain: u64,
+    ) -> Result<AdoptReport, LinodeError> {
+        info!(
+            "Adopting instance ID: {} into tag: {} in region: {}",
+            id, tag, region.code
+        );
+        let instance = self.fetch_instance(id).await?;
+        if instance.region != region.region {
+            return Err(LinodeError::Spec(format!(
+                "instance ID: {} is in {}, not {}",
+                id, instance.region, region.region
+            )));
+        }
+
+        let mut report = AdoptReport::default();
+        let mut tags = instance.tags.clone();
+        for wanted in [tag, region.code] {
+            if !tags.iter().any(|t| t == wanted) {
+                tags.push(wanted.to_owned());
+                report.tags_added.push(wanted.to_owned());
+            }
+        }
+        if !report.tags_added.is_empty() {
+            self.update_instance_tags(id, tags).await?;
+        }
+
+        if self.get_vlan_ip(id, tag).await?.is_none() {
+            let host = self.next_vlan_host(tag, region).await?;
+            let configs = self.get_instance_configurations(id).await?;
+            let config = configs.first().ok_or_else(|| {
+                LinodeError::Spec(format!("instance ID: {} has no config profile", id))
+            })?;
+
+            let mut interfaces = config.interfaces.clone();
+            if interfaces.is_empty() {
+                interfaces.push(public_interface());
+            }
+            interfaces.push(vlan_interface(tag, format!("10.0.0.{}/24", host)));
+            self.set_interfaces(id, config.id, Interfaces { interfaces })
+                .await?;
+
+            self.reboot_instance_with_config(id, Some(config.id))
+                .await?;
+            self.pause(STATUS_POLL_INTERVAL).await;
+            self.wait_for_status(id, STATUS_RUNNING, BOOT_TIMEOUT)
+                .await?;
+            report.vlan_ip = Some(format!("10.0.0.{}", host));
+        }
+
+        let ip = &instance.ipv4[0];
+        let prefix = self.naming().prefix(tag, region.code);
+        let registered = self.fetch_records(domain).await?.into_iter().any(|rec| {
+            rec.record_type == A_RECORD
+                && self.naming().is_slot(&prefix, &rec.name)
+                && &rec.target == ip
+        });
+        if !registered {
+            report.dns_name = Some(self.register_slot(domain, &prefix, ip).await?);
+        }
+
+        info!("Adopted instance ID: {} as {}", id, instance.label);
+        Ok(report)
+    }
+}
@@ -0,0 +1,150 @@
+use crate::firewall::{FirewallAddresses, FirewallRule};
+use crate::{LinodeClient, LinodeError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tracing::info;
+
+fn default_protocol() -> String {
+    "TCP".to_owned()
+}
+
+// An inbound firewall rule whose source addresses follow a tagged fleet,
+// for databases and admin panels that only accept known IPs. The CLI
+// syncs those listed under [[allowlists]] in its config after every scale
+// event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Allowlist {
+    pub tag: String,
+    pub firewall_id: u64,
+    // the rule's label, "{tag}-fleet" by default
+    pub label: Option<String>,
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    // e.g. "5432" or "80,443"; all ports when None
+    pub ports: Option<String>,
+    // also allow the fleet's VLAN addresses
+    #[serde(default)]
+    pub include_vlan: bool,
+}
+
+impl Allowlist {
+    pub fn new(tag: &str, firewall_id: u64) -> Self {
+        Allowlist {
+            tag: tag.to_owned(),
+            firewall_id,
+            label: None,
+            protocol: default_protocol(),
+            ports: None,
+            include_vlan: false,
+        }
+    }
+
+    pub fn rule_label(&self) -> String {
+        self.label
+            .clone()
+            .unwrap_or_else(|| format!("{}-fleet", self.tag))
+    }
+}
+
+// How a sync changed the rule's addresses; created is set when the rule
+// didn't exist yet.
+#[derive(Debug, Default)]
+pub struct AllowlistSync {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub created: bool,
+}
+
+impl AllowlistSync {
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && !self.created
+    }
+}
+
+impl LinodeClient {
+    // every public IPv4 of tag's instances as a /32, plus their VLAN
+    // addresses when include_vlan is set
+    pub async fn fleet_addresses(
+        &self,
+        tag: &str,
+        include_vlan: bool,
+    ) -> Result<BTreeSet<String>, LinodeError> {
+        let mut addresses = BTreeSet::new();
+        for instance in self.get_instances_by_tag(vec![tag]).await? {
+            for ip in &instance.ipv4 {
+                addresses.insert(format!("{}/32", ip));
+            }
+            if include_vlan {
+                if let Some(vlan_ip) = self.get_vlan_ip(instance.id, tag).await? {
+                    let ip = vlan_ip.split('/').next().unwrap_or(&vlan_ip);
+                    addresses.insert(format!("{}/32", ip));
+                }
+            }
+        }
+        Ok(addresses)
+    }
+
+    // Make the allowlist's rule accept exactly the fleet's current
+    // addresses, adding the rule if it is missing. Other rules are left
+    // as they are.
+    pub async fn sync_allowlist(
+        &self,
+        allowlist: &Allowlist,
+    ) -> Result<AllowlistSync, LinodeError> {
+        let label = allowlist.rule_label();
+        let wanted = self
+            .fleet_addresses(&allowlist.tag, allowlist.include_vlan)
+            .await?;
+        let mut rules = self.fetch_firewall_rules(allowlist.firewall_id).await?;
+
+        let mut sync = AllowlistSync::default();
+        let rule = match rules
+            .inbound
+            .iter_mut()
+            .find(|rule| rule.label.as_deref() == Some(label.as_str()))
+        {
+            Some(rule) => rule,
+            None => {
+                sync.created = true;
+                rules.inbound.push(FirewallRule {
+                    action: "ACCEPT".to_owned(),
+                    protocol: allowlist.protocol.clone(),
+                    ports: allowlist.ports.clone(),
+                    addresses: FirewallAddresses::default(),
+                    label: Some(label.clone()),
+                    description: Some(format!("instances tagged {}", allowlist.tag)),
+                });
+                rules.inbound.last_mut().unwrap()
+            }
+        };
+
+        let current = rule
+            .addresses
+            .ipv4
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        sync.added = wanted.difference(&current).cloned().collect();
+        sync.removed = current.difference(&wanted).cloned().collect();
+        if sync.is_unchanged() {
+            info!(
+                "Allowlist {} on firewall ID: {} is up to date",
+                label, allowlist.firewall_id
+            );
+            return Ok(sync);
+        }
+
+        rule.addresses.ipv4 = Some(wanted.into_iter().collect());
+        self.set_firewall_rules(allowlist.firewall_id, &rules)
+            .await?;
+        info!(
+            "Synced allowlist {} on firewall ID: {}: {} added, {} removed",
+            label,
+            allowlist.firewall_id,
+            sync.added.len(),
+            sync.removed.len()
+        );
+        Ok(sync)
+    }
+}
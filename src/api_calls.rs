@@ -0,0 +1,113 @@
+use crate::{LinodeClient, LinodeError};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+const METRIC_NAME: &str = "linode_api_calls_total";
+
+// "/v4//linode/instances/123/configs" as "/v4/linode/instances/:id/configs",
+// so calls to the same endpoint are counted together
+pub(crate) fn endpoint(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if segment.bytes().all(|b| b.is_ascii_digit()) {
+                ":id"
+            } else {
+                segment
+            }
+        })
+        .fold(String::new(), |mut out, segment| {
+            out.push('/');
+            out.push_str(segment);
+            out
+        })
+}
+
+// Every API call the client has sent, retries included, by method and
+// endpoint. With a limit, calls past it fail with TooManyCalls instead of
+// being sent, e.g. to bound a CI job.
+#[derive(Debug, Default)]
+pub struct ApiCalls {
+    counts: Mutex<BTreeMap<(String, String), u64>>,
+    total: AtomicU64,
+    limit: Option<u64>,
+}
+
+impl ApiCalls {
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    // (method, endpoint, calls), busiest endpoint first
+    pub fn by_endpoint(&self) -> Vec<(String, String, u64)> {
+        let mut calls = self
+            .counts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((method, endpoint), count)| (method.clone(), endpoint.clone(), *count))
+            .collect::<Vec<_>>();
+        calls.sort_by_key(|(_, _, count)| std::cmp::Reverse(*count));
+        calls
+    }
+
+    // a counter per endpoint in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP {} API calls sent, retries included.",
+            METRIC_NAME
+        );
+        let _ = writeln!(out, "# TYPE {} counter", METRIC_NAME);
+        for ((method, endpoint), count) in self.counts.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "{}{{method=\"{}\",endpoint=\"{}\"}} {}",
+                METRIC_NAME, method, endpoint, count
+            );
+        }
+        out
+    }
+
+    // checked before each call; retries of a call already under way go
+    // ahead so it isn't cut off halfway
+    pub(crate) fn check(&self) -> Result<(), LinodeError> {
+        match self.limit {
+            Some(limit) if self.total() >= limit => Err(LinodeError::TooManyCalls { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn count(&self, method: &str, path: &str) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        *self
+            .counts
+            .lock()
+            .unwrap()
+            .entry((method.to_owned(), endpoint(path)))
+            .or_default() += 1;
+    }
+}
+
+impl LinodeClient {
+    // refuse to send more than limit API calls over the client's lifetime
+    pub fn with_max_api_calls(mut self, limit: u64) -> Self {
+        self.api_calls.limit = Some(limit);
+        self
+    }
+
+    pub fn api_calls(&self) -> &ApiCalls {
+        &self.api_calls
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{Domain, DomainRecordOptions, LinodeClient, LinodeError};
+use hickory_proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_proto::rr::{Name, RData, Record, RecordType};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::info;
+
+const DNS_PORT: u16 = 53;
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(60);
+
+// What came of importing a zone: the new domain, how many records were
+// created, and every record that was skipped or failed with the reason.
+#[derive(Debug)]
+pub struct ZoneImport {
+    pub domain: Domain,
+    pub created: usize,
+    pub skipped: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+// Pull every record of zone from server ("host" or "host:port") with an
+// AXFR over TCP. The server has to allow transfers to this address.
+pub async fn transfer_zone(server: &str, zone: &str) -> Result<Vec<Record>, LinodeError> {
+    info!("Requesting AXFR of {} from {}", zone, server);
+    tokio::time::timeout(TRANSFER_TIMEOUT, transfer(server, zone))
+        .await
+        .map_err(|_| {
+            LinodeError::Axfr(format!("no complete transfer after {:?}", TRANSFER_TIMEOUT))
+        })?
+}
+
+async fn transfer(server: &str, zone: &str) -> Result<Vec<Record>, LinodeError> {
+    let axfr_err = |e: &dyn std::fmt::Display| LinodeError::Axfr(format!("{}: {}", server, e));
+    let (host, port) = match server.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|e| axfr_err(&e))?),
+        None => (server, DNS_PORT),
+    };
+    let name = Name::from_ascii(zone).map_err(|e| axfr_err(&e))?;
+
+    let mut query = Message::new();
+    query
+        .set_id(rand::random())
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(false)
+        .add_query(Query::query(name, RecordType::AXFR));
+    let query = query.to_vec().map_err(|e| axfr_err(&e))?;
+
+    // DNS over TCP frames every message with a two byte length
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .map_err(|e| axfr_err(&e))?;
+    stream
+        .write_all(&(query.len() as u16).to_be_bytes())
+        .await
+        .map_err(|e| axfr_err(&e))?;
+    stream.write_all(&query).await.map_err(|e| axfr_err(&e))?;
+
+    // the transfer starts and ends with the zone's SOA
+    let mut records: Vec<Record> = Vec::new();
+    let mut soas = 0;
+    while soas < 2 {
+        let len = stream.read_u16().await.map_err(|e| axfr_err(&e))?;
+        let mut buf = vec![0; len as usize];
+        stream
+            .read_exact(&mut buf)
+            .await
+            .map_err(|e| axfr_err(&e))?;
+        let message = Message::from_vec(&buf).map_err(|e| axfr_err(&e))?;
+        if message.response_code() != ResponseCode::NoError {
+            return Err(axfr_err(&format!(
+                "transfer of {} refused: {}",
+                zone,
+                message.response_code()
+            )));
+        }
+        if message.answers().is_empty() {
+            return Err(axfr_err(&"transfer ended without a closing SOA"));
+        }
+        for record in message.answers() {
+            if record.record_type() == RecordType::SOA {
+                soas += 1;
+                // the closing SOA repeats the first one
+                if soas == 2 {
+                    break;
+                }
+            }
+            records.push(record.clone());
+        }
+    }
+    info!(
+        "Received {} records for {} from {}",
+        records.len(),
+        zone,
+        server
+    );
+    Ok(records)
+}
+
+// SOA contact as an email address, hostmaster.example.com. becomes
+// hostmaster@example.com
+pub fn soa_email(records: &[Record]) -> Option<String> {
+    records.iter().find_map(|record| match record.data() {
+        Some(RData::SOA(soa)) => {
+            let rname = fqdn(soa.rname());
+            rname
+                .split_once('.')
+                .map(|(user, host)| format!("{}@{}", user, host))
+        }
+        _ => None,
+    })
+}
+
+// Convert transferred records to Linode record options. SOA and apex NS
+// records are skipped since Linode serves its own, as is anything of a
+// type the API doesn't take; those come back as descriptions.
+pub fn to_record_options(
+    zone: &str,
+    records: &[Record],
+) -> (Vec<DomainRecordOptions>, Vec<String>) {
+    let zone = zone.trim_end_matches('.');
+    let mut options = Vec::new();
+    let mut skipped = Vec::new();
+    for record in records {
+        let name = relative_name(zone, &fqdn(record.name()));
+        let ttl_sec = record.ttl() as i32;
+        let base = |record_type: &str, name: &str, target: String| DomainRecordOptions {
+            record_type: record_type.to_owned(),
+            name: name.to_owned(),
+            target,
+            ttl_sec,
+            ..Default::default()
+        };
+        let converted = match record.data() {
+            Some(RData::A(a)) => Some(base("A", &name, a.0.to_string())),
+            Some(RData::AAAA(aaaa)) => Some(base("AAAA", &name, aaaa.0.to_string())),
+            Some(RData::CNAME(cname)) => Some(base("CNAME", &name, fqdn(&cname.0))),
+            Some(RData::NS(ns)) if !name.is_empty() => Some(base("NS", &name, fqdn(&ns.0))),
+            Some(RData::MX(mx)) => Some(DomainRecordOptions {
+                priority: Some(i32::from(mx.preference())),
+                ..base("MX", &name, fqdn(mx.exchange()))
+            }),
+            Some(RData::TXT(txt)) => {
+                let text = txt
+                    .iter()
+                    .map(|part| String::from_utf8_lossy(part))
+                    .collect::<String>();
+                Some(base("TXT", &name, text))
+            }
+            Some(RData::SRV(srv)) => {
+                // _service._protocol[.name]
+                let mut labels = name.splitn(3, '.');
+                match (labels.next(), labels.next()) {
+                    (Some(service), Some(protocol))
+                        if service.starts_with('_') && protocol.starts_with('_') =>
+                    {
+                        Some(DomainRecordOptions {
+                            service: Some(service.trim_start_matches('_').to_owned()),
+                            protocol: Some(protocol.trim_start_matches('_').to_owned()),
+                            priority: Some(i32::from(srv.priority())),
+                            weight: Some(i32::from(srv.weight())),
+                            port: Some(i32::from(srv.port())),
+                            ..base("SRV", labels.next().unwrap_or(""), fqdn(srv.target()))
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match converted {
+            Some(option) => options.push(option),
+            None => skipped.push(format!("{} {}", record.record_type(), fqdn(record.name()))),
+        }
+    }
+    (options, skipped)
+}
+
+fn fqdn(name: &Name) -> String {
+    name.to_ascii().trim_end_matches('.').to_owned()
+}
+
+// www.example.com in example.com is "www", the apex is ""
+fn relative_name(zone: &str, name: &str) -> String {
+    if name.eq_ignore_ascii_case(zone) {
+        return String::new();
+    }
+    name.strip_suffix(zone)
+        .and_then(|name| name.strip_suffix('.'))
+        .unwrap_or(name)
+        .to_owned()
+}
+
+impl LinodeClient {
+    // migrate a zone onto Linode: transfer it from server, create the
+    // domain and recreate its records there
+    pub async fn import_zone(
+        &self,
+        server: &str,
+        zone: &str,
+        soa_email_override: Option<&str>,
+    ) -> Result<ZoneImport, LinodeError> {
+        let records = transfer_zone(server, zone).await?;
+        let email = soa_email_override
+            .map(str::to_owned)
+            .or_else(|| soa_email(&records))
+            .ok_or_else(|| LinodeError::Axfr(format!("{} has no SOA contact", zone)))?;
+        let (options, skipped) = to_record_options(zone, &records);
+
+        let domain = self.create_domain(zone, &email).await?;
+        let mut created = 0;
+        let mut failed = Vec::new();
+        let descriptions = options
+            .iter()
+            .map(|option| format!("{} {}", option.record_type, option.name))
+            .collect::<Vec<_>>();
+        for (result, description) in self
+            .create_records(domain.id, options)
+            .await
+            .into_iter()
+            .zip(descriptions)
+        {
+            match result {
+                Ok(_) => created += 1,
+                Err(e) => failed.push(format!("{}: {}", description, e)),
+            }
+        }
+        info!(
+            "Imported {} records into domain ID: {} ({} skipped, {} failed)",
+            created,
+            domain.id,
+            skipped.len(),
+            failed.len()
+        );
+        Ok(ZoneImport {
+            domain,
+            created,
+            skipped,
+            failed,
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{BackupSchedule, LinodeClient, LinodeError, LinodeInstance};
+use serde_json::json;
+use tracing::info;
+
+// What setting a tag's backup schedule did to each of its instances, by
+// label. Instances without backups enabled are left alone.
+#[derive(Debug, Default)]
+pub struct BackupScheduleReport {
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub not_enabled: Vec<String>,
+}
+
+impl LinodeClient {
+    pub async fn update_backup_schedule(
+        &self,
+        id: u64,
+        schedule: &BackupSchedule,
+    ) -> Result<LinodeInstance, LinodeError> {
+        info!(
+            "Setting backup schedule {:?} {:?} on instance ID: {}",
+            schedule.day, schedule.window, id
+        );
+        let response = self
+            .send(
+                self.client
+                    .put(format!("{}/linode/instances/{}", self.base_url, id))
+                    .bearer_auth(self.token.expose())
+                    .json(&json!({ "backups": { "schedule": schedule } })),
+            )
+            .await?;
+
+        response.json::<LinodeInstance>().await
+    }
+
+    // put every instance of tag on the same backup day and window
+    pub async fn set_backup_schedule_for_tag(
+        &self,
+        tag: &str,
+        schedule: &BackupSchedule,
+    ) -> Result<BackupScheduleReport, LinodeError> {
+        let mut report = BackupScheduleReport::default();
+        for instance in self.get_instances_by_tag(vec![tag]).await? {
+            if !instance.backups.enabled {
+                report.not_enabled.push(instance.label);
+                continue;
+            }
+            if instance.backups.schedule.as_ref() == Some(schedule) {
+                report.unchanged.push(instance.label);
+                continue;
+            }
+            self.update_backup_schedule(instance.id, schedule).await?;
+            report.updated.push(instance.label);
+        }
+
+        info!(
+            "Backup schedule for tag: {} updated on {} instances, {} unchanged, {} without backups",
+            tag,
+            report.updated.len(),
+            report.unchanged.len(),
+            report.not_enabled.len()
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,156 @@
+use crate::regions::RegionInfo;
+use crate::registry::ServiceRegistry;
+use crate::{LinodeClient, LinodeError, ScaledNode};
+use serde::Deserialize;
+use std::str::FromStr;
+use tracing::info;
+
+// stats come in 5 minute samples, average over the last hour
+const LOAD_SAMPLES: usize = 12;
+
+// Where capacity comes from when scaling down across several regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleDownPolicy {
+    // remove n from every region
+    #[default]
+    PerRegion,
+    // remove n in total, each from the region with the most instances
+    MostInstances,
+    // remove n in total, each from the region whose instances would be
+    // least busy after losing one, judged by average CPU
+    LeastLoad,
+}
+
+impl FromStr for ScaleDownPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "per-region" => Ok(ScaleDownPolicy::PerRegion),
+            "most-instances" => Ok(ScaleDownPolicy::MostInstances),
+            "least-load" => Ok(ScaleDownPolicy::LeastLoad),
+            _ => Err(format!("unknown scale-down policy: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct InstanceStatsResponse {
+    data: InstanceStats,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstanceStats {
+    // [unix millis, percent] pairs
+    cpu: Vec<(f64, f64)>,
+}
+
+// one region's share of a balanced scale-down
+struct RegionLoad<'a> {
+    region: &'a RegionInfo,
+    instances: usize,
+    // summed CPU percent of the region's instances
+    cpu: f64,
+}
+
+impl RegionLoad<'_> {
+    // average CPU per instance once one is gone and its share of the
+    // work has moved to the rest
+    fn cpu_after_removal(&self) -> f64 {
+        match self.instances {
+            0 | 1 => f64::INFINITY,
+            n => self.cpu / (n - 1) as f64,
+        }
+    }
+}
+
+impl LinodeClient {
+    // average CPU percent over the last hour; new instances have no
+    // stats yet and count as idle
+    pub async fn instance_cpu(&self, id: u64) -> Result<f64, LinodeError> {
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}/linode/instances/{}/stats", self.base_url, id))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+        let stats = match response.json::<InstanceStatsResponse>().await {
+            Ok(stats) => stats.data,
+            Err(LinodeError::Validation { .. }) => {
+                info!("No stats yet for instance ID: {}", id);
+                return Ok(0.0);
+            }
+            Err(e) => return Err(e),
+        };
+        let recent = &stats.cpu[stats.cpu.len().saturating_sub(LOAD_SAMPLES)..];
+        if recent.is_empty() {
+            return Ok(0.0);
+        }
+        Ok(recent.iter().map(|(_, cpu)| cpu).sum::<f64>() / recent.len() as f64)
+    }
+
+    // Scale a tag down across regions according to policy. PerRegion
+    // removes n from each region, the other policies n in total, picking
+    // the region again before every removal so the fleet stays spread out.
+    pub async fn scale_down_balanced(
+        &self,
+        registry: &dyn ServiceRegistry,
+        regions: &[&RegionInfo],
+        tag: &str,
+        n: usize,
+        policy: ScaleDownPolicy,
+    ) -> Result<Vec<ScaledNode>, LinodeError> {
+        let mut done = Vec::new();
+        if policy == ScaleDownPolicy::PerRegion {
+            for region in regions {
+                done.extend(self.scale_down_in(registry, region, tag, n).await?);
+            }
+            return Ok(done);
+        }
+
+        let mut loads = Vec::new();
+        for region in regions {
+            let instances = self.get_fleet_instances(tag, region).await?;
+            let mut cpu = 0.0;
+            if policy == ScaleDownPolicy::LeastLoad {
+                for instance in &instances {
+                    cpu += self.instance_cpu(instance.id).await?;
+                }
+            }
+            loads.push(RegionLoad {
+                region,
+                instances: instances.len(),
+                cpu,
+            });
+        }
+
+        while done.len() < n {
+            let candidates = loads.iter_mut().filter(|load| load.instances > 0);
+            let next = match policy {
+                ScaleDownPolicy::LeastLoad => candidates
+                    .min_by(|a, b| a.cpu_after_removal().total_cmp(&b.cpu_after_removal())),
+                // ties go to the region listed first
+                _ => candidates.rev().max_by_key(|load| load.instances),
+            };
+            let Some(load) = next else {
+                info!("No instances with tag: {} left to remove", tag);
+                break;
+            };
+            info!(
+                "Removing capacity from region: {} ({} instances, {:.1}% CPU)",
+                load.region.code, load.instances, load.cpu
+            );
+
+            let removed = self.scale_down_in(registry, load.region, tag, 1).await?;
+            if removed.is_empty() {
+                // nothing here is registered, don't pick it again
+                load.instances = 0;
+                continue;
+            }
+            load.instances -= removed.len().min(load.instances);
+            done.extend(removed);
+        }
+        Ok(done)
+    }
+}
@@ -0,0 +1,564 @@
+// A synchronous LinodeClient for scripts and build tooling that aren't
+// async. Each method runs the async one to completion on a runtime the
+// client owns, so don't call it from inside another async runtime:
+//
+//   let client = BlockingLinodeClient::new(token, pub_key)?;
+//   for instance in client.get_instances_by_tag(vec!["api"])? {
+//       println!("{}", instance.label);
+//   }
+//
+// Generic calls (request, fetch_all, fetch_page) and watch_events go
+// through block_on; settings and other non-async methods through client().
+use crate::adopt::AdoptReport;
+use crate::allowlist::{Allowlist, AllowlistSync};
+use crate::axfr::ZoneImport;
+use crate::backups::BackupScheduleReport;
+use crate::balance::ScaleDownPolicy;
+use crate::domains::DomainSettings;
+use crate::drain::{DrainReplacement, DrainReport};
+use crate::evacuate::{EvacuateOptions, Evacuation};
+use crate::events::AccountEvent;
+use crate::failover::{FailoverOptions, FailoverReport};
+use crate::firewall::{Firewall, FirewallDevice, FirewallRules};
+use crate::fleet::{DiskUsage, FleetMember, HealthCheck};
+use crate::instance_record::InstanceRecord;
+use crate::journal::JournalEntry;
+use crate::lock::ScaleLock;
+use crate::metadata::Peer;
+use crate::nodebalancers::{CertRotation, NodeBalancer, NodeBalancerConfig};
+use crate::placement::RegionPicker;
+use crate::rate_limit::RateLimit;
+use crate::reap::ReapReport;
+use crate::reconcile::ReconcileReport;
+use crate::region_names::RegionNameSync;
+use crate::regions::{Region, RegionGroups, RegionInfo};
+use crate::registry::ServiceRegistry;
+use crate::rename::RenameReport;
+use crate::replace::Replacement;
+use crate::roll::RollStatus;
+use crate::schedule::ScheduleRun;
+use crate::snapshot::{FleetSnapshot, RestoreReport};
+use crate::spec::{FleetDiff, FleetGroupSpec, FleetSpec};
+use crate::srv::SrvService;
+use crate::tags::TagExpr;
+use crate::token::TokenReport;
+use crate::volumes::{Volume, VolumeCreateOptions};
+use crate::zone_health::ZoneHealth;
+use crate::{
+    BackupSchedule, Configuration, CreateInstanceOptions, Disk, Domain, DomainRecord,
+    DomainRecordOptions, Image, Interfaces, LinodeClient, LinodeError, LinodeInstance, LinodeType,
+    ListOptions, ScaleUpOptions, ScaledNode, Upsert,
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+pub struct BlockingLinodeClient {
+    runtime: Runtime,
+    client: LinodeClient,
+}
+
+impl BlockingLinodeClient {
+    pub fn new(
+        token: String,
+        pub_key: String,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(BlockingLinodeClient::from_async(LinodeClient::new(
+            token, pub_key,
+        )?)?)
+    }
+
+    // wrap a client set up with the builder or with_* methods
+    pub fn from_async(client: LinodeClient) -> std::io::Result<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(BlockingLinodeClient { runtime, client })
+    }
+
+    pub fn client(&self) -> &LinodeClient {
+        &self.client
+    }
+
+    pub fn into_async(self) -> LinodeClient {
+        self.client
+    }
+
+    // run any call on the async client, e.g.
+    //   client.block_on(|c| c.fetch_all::<Volume>("volumes", &options))
+    pub fn block_on<'a, F, T>(&'a self, call: impl FnOnce(&'a LinodeClient) -> F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        self.runtime.block_on(call(&self.client))
+    }
+}
+
+// one blocking method per async LinodeClient method, same name and
+// arguments
+macro_rules! blocking {
+    ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)* $(,)?) -> $ret:ty;)*) => {
+        impl BlockingLinodeClient {
+            $(
+                pub fn $name(&self $(, $arg: $ty)*) -> $ret {
+                    self.runtime.block_on(self.client.$name($($arg),*))
+                }
+            )*
+        }
+    };
+}
+
+blocking! {
+    fn adopt_instance(
+        &self,
+        id: u64,
+        tag: &str,
+        region: &RegionInfo,
+        domain: u64,
+    ) -> Result<AdoptReport, LinodeError>;
+
+    fn fleet_addresses(
+        &self,
+        tag: &str,
+        include_vlan: bool,
+    ) -> Result<BTreeSet<String>, LinodeError>;
+    fn sync_allowlist(&self, allowlist: &Allowlist) -> Result<AllowlistSync, LinodeError>;
+
+    fn import_zone(
+        &self,
+        server: &str,
+        zone: &str,
+        soa_email_override: Option<&str>,
+    ) -> Result<ZoneImport, LinodeError>;
+
+    fn update_backup_schedule(
+        &self,
+        id: u64,
+        schedule: &BackupSchedule,
+    ) -> Result<LinodeInstance, LinodeError>;
+    fn set_backup_schedule_for_tag(
+        &self,
+        tag: &str,
+        schedule: &BackupSchedule,
+    ) -> Result<BackupScheduleReport, LinodeError>;
+
+    fn instance_cpu(&self, id: u64) -> Result<f64, LinodeError>;
+    fn scale_down_balanced(
+        &self,
+        registry: &dyn ServiceRegistry,
+        regions: &[&RegionInfo],
+        tag: &str,
+        n: usize,
+        policy: ScaleDownPolicy,
+    ) -> Result<Vec<ScaledNode>, LinodeError>;
+
+    fn projected_monthly_spend(
+        &self,
+        tag: &str,
+        adding: Option<(&str, &RegionInfo)>,
+    ) -> Result<f64, LinodeError>;
+    fn check_budget(
+        &self,
+        tag: &str,
+        instance_type: &str,
+        region: &RegionInfo,
+        cap: f64,
+    ) -> Result<f64, LinodeError>;
+
+    fn fetch_instance_by_label(&self, label: &str) -> Result<LinodeInstance, LinodeError>;
+    fn cordon_instance(&self, domain: u64, label: &str) -> Result<String, LinodeError>;
+    fn cordon(&self, domain: u64, instance: &LinodeInstance) -> Result<String, LinodeError>;
+    fn uncordon_instance(&self, domain: u64, label: &str) -> Result<String, LinodeError>;
+
+    fn fetch_records(&self, domain: u64) -> Result<Vec<DomainRecord>, LinodeError>;
+    fn fetch_records_with(
+        &self,
+        domain: u64,
+        options: &ListOptions,
+    ) -> Result<Vec<DomainRecord>, LinodeError>;
+    fn fetch_domain(&self, domain: u64) -> Result<Domain, LinodeError>;
+    fn fetch_record(&self, domain: u64, id: u64) -> Result<DomainRecord, LinodeError>;
+    fn claim_record(&self, domain: u64, id: u64, target: &str) -> Result<bool, LinodeError>;
+    fn delete_record(&self, domain: u64, id: u64) -> Result<(), LinodeError>;
+    fn update_record_target(&self, domain: u64, id: u64, target: &str) -> Result<(), LinodeError>;
+    fn create_a_record(
+        &self,
+        domain: u64,
+        name: String,
+        target: String,
+    ) -> Result<DomainRecord, LinodeError>;
+    fn create_records(
+        &self,
+        domain: u64,
+        records: Vec<DomainRecordOptions>,
+    ) -> Vec<Result<DomainRecord, LinodeError>>;
+    fn create_records_concurrent(
+        &self,
+        domain: u64,
+        records: Vec<DomainRecordOptions>,
+        concurrency: usize,
+    ) -> Vec<Result<DomainRecord, LinodeError>>;
+    fn reserve_slots(
+        &self,
+        domain: u64,
+        prefix: &str,
+        count: usize,
+    ) -> Result<Vec<DomainRecord>, LinodeError>;
+    fn register_slot(&self, domain: u64, prefix: &str, ip: &str) -> Result<String, LinodeError>;
+    fn upsert_record(
+        &self,
+        domain: u64,
+        name: &str,
+        record_type: &str,
+        target: &str,
+        ttl: i32,
+    ) -> Result<Upsert, LinodeError>;
+
+    fn mirror_record(&self, domain: u64, name: &str, target: &str) -> Result<bool, LinodeError>;
+
+    fn create_domain(&self, domain: &str, soa_email: &str) -> Result<Domain, LinodeError>;
+    fn update_domain_settings(
+        &self,
+        domain: u64,
+        settings: &DomainSettings,
+    ) -> Result<Domain, LinodeError>;
+    fn clone_domain(&self, source: u64, new_domain: &str) -> Result<Domain, LinodeError>;
+    fn clone_domain_with(
+        &self,
+        source: u64,
+        new_domain: &str,
+        targets: &HashMap<String, String>,
+    ) -> Result<Domain, LinodeError>;
+
+    fn drain_region(
+        &self,
+        domain: u64,
+        tag: &str,
+        region: &RegionInfo,
+        replacement: Option<&DrainReplacement<'_>>,
+    ) -> Result<DrainReport, LinodeError>;
+
+    fn evacuate_region(
+        &self,
+        domain: u64,
+        tag: &str,
+        from: &RegionInfo,
+        to: &RegionInfo,
+        options: &EvacuateOptions,
+    ) -> Result<Vec<Evacuation>, LinodeError>;
+
+    fn fetch_events_since(&self, after: u64) -> Result<Vec<AccountEvent>, LinodeError>;
+
+    fn failover_promote(
+        &self,
+        domain: u64,
+        tag: &str,
+        from: &RegionInfo,
+        to: &RegionInfo,
+        options: &FailoverOptions,
+    ) -> Result<FailoverReport, LinodeError>;
+
+    fn fetch_firewalls(&self, options: &ListOptions) -> Result<Vec<Firewall>, LinodeError>;
+    fn create_firewall(
+        &self,
+        label: &str,
+        rules: FirewallRules,
+        tags: Vec<String>,
+    ) -> Result<Firewall, LinodeError>;
+    fn fetch_firewall_rules(&self, id: u64) -> Result<FirewallRules, LinodeError>;
+    fn set_firewall_rules(
+        &self,
+        id: u64,
+        rules: &FirewallRules,
+    ) -> Result<FirewallRules, LinodeError>;
+    fn fetch_firewall_devices(&self, id: u64) -> Result<Vec<FirewallDevice>, LinodeError>;
+    fn attach_firewall(&self, id: u64, linode_id: u64) -> Result<FirewallDevice, LinodeError>;
+
+    fn get_vlan_ip(&self, id: u64, tag: &str) -> Result<Option<String>, LinodeError>;
+    fn fleet_status(
+        &self,
+        tag: &str,
+        domain: u64,
+        health: Option<HealthCheck>,
+    ) -> Result<Vec<FleetMember>, LinodeError>;
+    fn fleet_disks(&self, tag: &str, image: Option<&str>) -> Result<Vec<DiskUsage>, LinodeError>;
+    fn roll_status(&self, tag: &str, domain: u64) -> Result<RollStatus, LinodeError>;
+
+    fn find_instance_by_label(&self, label: &str) -> Result<Option<LinodeInstance>, LinodeError>;
+    fn find_record(
+        &self,
+        domain: u64,
+        options: &DomainRecordOptions,
+    ) -> Result<Option<DomainRecord>, LinodeError>;
+
+    fn write_instance_record(
+        &self,
+        domain: u64,
+        name: &str,
+        record: &InstanceRecord,
+    ) -> Result<(), LinodeError>;
+    fn remove_instance_record(&self, domain: u64, name: &str) -> Result<(), LinodeError>;
+    fn fetch_instance_records(
+        &self,
+        domain: u64,
+    ) -> Result<HashMap<String, InstanceRecord>, LinodeError>;
+
+    fn fetch_instances(&self) -> Result<Vec<LinodeInstance>, LinodeError>;
+    fn fetch_instances_filtered(&self, filter: &Value) -> Result<Vec<LinodeInstance>, LinodeError>;
+    fn fetch_instances_with(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Vec<LinodeInstance>, LinodeError>;
+    fn get_instance_configurations(&self, id: u64) -> Result<Vec<Configuration>, LinodeError>;
+    fn get_instance_configurations_with(
+        &self,
+        id: u64,
+        options: &ListOptions,
+    ) -> Result<Vec<Configuration>, LinodeError>;
+    fn get_instances_by_tag(&self, tags: Vec<&str>) -> Result<Vec<LinodeInstance>, LinodeError>;
+    fn get_instances_by_tag_expr(&self, expr: &TagExpr) -> Result<Vec<LinodeInstance>, LinodeError>;
+    fn get_fleet_instances(
+        &self,
+        tag: &str,
+        region: &RegionInfo,
+    ) -> Result<Vec<LinodeInstance>, LinodeError>;
+    fn destroy_instance(&self, id: u64) -> Result<(), LinodeError>;
+    fn reboot_instance(&self, id: u64) -> Result<(), LinodeError>;
+    fn reboot_instance_with_config(
+        &self,
+        id: u64,
+        config_id: Option<u64>,
+    ) -> Result<(), LinodeError>;
+    fn fetch_instance(&self, id: u64) -> Result<LinodeInstance, LinodeError>;
+    fn update_instance_tags(
+        &self,
+        id: u64,
+        tags: Vec<String>,
+    ) -> Result<LinodeInstance, LinodeError>;
+    fn shutdown_instance(&self, id: u64) -> Result<(), LinodeError>;
+    fn boot_instance(&self, id: u64, config_id: Option<u64>) -> Result<(), LinodeError>;
+    fn wait_for_status(
+        &self,
+        id: u64,
+        status: &str,
+        timeout: Duration,
+    ) -> Result<LinodeInstance, LinodeError>;
+    fn power_cycle_instance(&self, id: u64, config_id: Option<u64>) -> Result<(), LinodeError>;
+    fn create_linode_instance(
+        &self,
+        image: String,
+        tags: Vec<String>,
+        label: String,
+        region: String,
+        instance_type: String,
+    ) -> Result<LinodeInstance, LinodeError>;
+    fn create_instance(&self, create: CreateInstanceOptions) -> Result<LinodeInstance, LinodeError>;
+    fn fetch_regions(&self) -> Result<Vec<Region>, LinodeError>;
+    fn fetch_types(&self) -> Result<Vec<LinodeType>, LinodeError>;
+    fn fetch_images(&self, options: &ListOptions) -> Result<Vec<Image>, LinodeError>;
+    fn create_image(
+        &self,
+        disk_id: u64,
+        label: &str,
+        description: Option<String>,
+    ) -> Result<Image, LinodeError>;
+    fn fetch_image(&self, id: &str) -> Result<Image, LinodeError>;
+    fn replicate_image(&self, id: &str, regions: &[&str]) -> Result<Image, LinodeError>;
+    fn wait_for_image(
+        &self,
+        id: &str,
+        region: Option<&str>,
+        timeout: Duration,
+    ) -> Result<Image, LinodeError>;
+    fn delete_image(&self, id: &str) -> Result<(), LinodeError>;
+    fn fetch_instance_disks(&self, id: u64) -> Result<Vec<Disk>, LinodeError>;
+
+    fn resume(
+        &self,
+        registry: &dyn ServiceRegistry,
+        operation_id: &str,
+        rollback: bool,
+    ) -> Result<JournalEntry, LinodeError>;
+
+    fn acquire_lock(
+        &self,
+        domain: u64,
+        name: &str,
+        ttl: Duration,
+    ) -> Result<ScaleLock, LinodeError>;
+    fn refresh_lock(&self, lock: &mut ScaleLock, ttl: Duration) -> Result<(), LinodeError>;
+    fn release_lock(&self, lock: &ScaleLock) -> Result<(), LinodeError>;
+
+    fn set_interfaces(
+        &self,
+        id: u64,
+        config_id: u64,
+        interfaces: Interfaces,
+    ) -> Result<(), LinodeError>;
+    fn next_vlan_host(&self, tag: &str, region: &RegionInfo) -> Result<u8, LinodeError>;
+
+    fn fetch_nodebalancer(&self, id: u64) -> Result<NodeBalancer, LinodeError>;
+    fn fetch_nodebalancer_configs(&self, id: u64) -> Result<Vec<NodeBalancerConfig>, LinodeError>;
+    fn fetch_nodebalancer_config(
+        &self,
+        id: u64,
+        config_id: u64,
+    ) -> Result<NodeBalancerConfig, LinodeError>;
+    fn rotate_nodebalancer_cert(
+        &self,
+        id: u64,
+        config_id: Option<u64>,
+        cert: &str,
+        key: &str,
+    ) -> Result<Vec<CertRotation>, LinodeError>;
+
+    fn region_picker(&self, weights: &BTreeMap<String, u32>) -> Result<RegionPicker, LinodeError>;
+
+    fn fetch_rate_limit(&self) -> Result<Option<RateLimit>, LinodeError>;
+
+    fn reap(
+        &self,
+        tag: &str,
+        older_than: Duration,
+        images: bool,
+        dry_run: bool,
+    ) -> Result<ReapReport, LinodeError>;
+
+    fn reconcile_dns(&self, domain: u64, tag: &str) -> Result<ReconcileReport, LinodeError>;
+
+    fn reconcile_dns_mirrored(
+        &self,
+        domain: u64,
+        mirrors: &[u64],
+        tag: &str,
+    ) -> Result<Vec<ReconcileReport>, LinodeError>;
+
+    fn sync_region_names(
+        &self,
+        domain: u64,
+        tag: &str,
+        groups: &RegionGroups,
+    ) -> Result<Vec<RegionNameSync>, LinodeError>;
+
+    fn rename_record(&self, domain: u64, id: u64, name: &str) -> Result<(), LinodeError>;
+    fn rename_fleet(
+        &self,
+        from: &str,
+        to: &str,
+        domain: u64,
+        relabel_vlan: bool,
+    ) -> Result<RenameReport, LinodeError>;
+
+    fn replace_instance(
+        &self,
+        domain: u64,
+        label: &str,
+        image: Option<&str>,
+        options: &ScaleUpOptions,
+    ) -> Result<Replacement, LinodeError>;
+
+    fn scale_down(
+        &self,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+        n: usize,
+    ) -> Result<usize, LinodeError>;
+    fn scale_down_in(
+        &self,
+        registry: &dyn ServiceRegistry,
+        region: &RegionInfo,
+        tag: &str,
+        n: usize,
+    ) -> Result<Vec<ScaledNode>, LinodeError>;
+    fn scale_up_one(
+        &self,
+        image_id: &str,
+        instance_type: &str,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+    ) -> Result<ScaledNode, LinodeError>;
+    fn scale_up_one_with(
+        &self,
+        image_id: &str,
+        instance_type: &str,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+        options: &ScaleUpOptions,
+    ) -> Result<ScaledNode, LinodeError>;
+    fn scale_up_one_in(
+        &self,
+        registry: &dyn ServiceRegistry,
+        image_id: &str,
+        instance_type: &str,
+        region: &RegionInfo,
+        tag: &str,
+        options: &ScaleUpOptions,
+    ) -> Result<ScaledNode, LinodeError>;
+    fn fleet_peers(
+        &self,
+        registry: &dyn ServiceRegistry,
+        region: &RegionInfo,
+        tag: &str,
+    ) -> Result<Vec<Peer>, LinodeError>;
+
+    fn run_schedule(
+        &self,
+        domain: u64,
+        group: &FleetGroupSpec,
+        options: &ScaleUpOptions,
+    ) -> Result<ScheduleRun, LinodeError>;
+    fn teardown_fleet(
+        &self,
+        domain: u64,
+        group: &FleetGroupSpec,
+        protect_tag: &str,
+    ) -> Result<Vec<ScaledNode>, LinodeError>;
+    fn rebuild_fleet(
+        &self,
+        domain: u64,
+        group: &FleetGroupSpec,
+        options: &ScaleUpOptions,
+    ) -> Result<Vec<ScaledNode>, LinodeError>;
+
+    fn export_fleet(&self, tag: &str, domain: u64) -> Result<FleetSnapshot, LinodeError>;
+    fn restore_fleet(
+        &self,
+        snapshot: &FleetSnapshot,
+        domain: u64,
+    ) -> Result<RestoreReport, LinodeError>;
+
+    fn diff_fleet(&self, spec: &FleetSpec) -> Result<FleetDiff, LinodeError>;
+
+    fn publish_srv(
+        &self,
+        domain: u64,
+        srv: &SrvService,
+        tag: &str,
+        target: &str,
+    ) -> Result<DomainRecord, LinodeError>;
+    fn remove_srv_targets(&self, domain: u64, target: &str) -> Result<usize, LinodeError>;
+
+    fn verify(&self) -> Result<TokenReport, LinodeError>;
+
+    fn fetch_volumes(&self, options: &ListOptions) -> Result<Vec<Volume>, LinodeError>;
+    fn create_volume(&self, options: &VolumeCreateOptions) -> Result<Volume, LinodeError>;
+    fn attach_volume(
+        &self,
+        id: u64,
+        linode_id: u64,
+        config_id: Option<u64>,
+    ) -> Result<Volume, LinodeError>;
+    fn detach_volume(&self, id: u64) -> Result<(), LinodeError>;
+    fn resize_volume(&self, id: u64, size: u64) -> Result<Volume, LinodeError>;
+
+    fn zone_health(
+        &self,
+        domain: u64,
+        prefixes: &[String],
+        port: Option<u16>,
+        timeout: Duration,
+    ) -> Result<ZoneHealth, LinodeError>;
+}
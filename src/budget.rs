@@ -0,0 +1,70 @@
+use crate::regions::RegionInfo;
+use crate::{LinodeClient, LinodeError, LinodeType};
+use tracing::{error, info};
+
+fn monthly_price(types: &[LinodeType], instance_type: &str, region: &str) -> Option<f64> {
+    types
+        .iter()
+        .find(|t| t.id == instance_type)
+        .map(|t| t.price_in(region).monthly)
+}
+
+impl LinodeClient {
+    // list price per month of every instance carrying tag, plus one more
+    // of (type, region) when adding is set; backups and transfer overage
+    // aren't included
+    pub async fn projected_monthly_spend(
+        &self,
+        tag: &str,
+        adding: Option<(&str, &RegionInfo)>,
+    ) -> Result<f64, LinodeError> {
+        let types = self.fetch_types().await?;
+        let instances = self.get_instances_by_tag(vec![tag]).await?;
+
+        let mut spend = 0.0;
+        for instance in &instances {
+            match monthly_price(&types, &instance.instance_type, &instance.region) {
+                Some(price) => spend += price,
+                None => error!(
+                    "No price for type: {} of instance ID: {}, leaving it out",
+                    instance.instance_type, instance.id
+                ),
+            }
+        }
+        if let Some((instance_type, region)) = adding {
+            spend += monthly_price(&types, instance_type, region.region)
+                .ok_or_else(|| LinodeError::Spec(format!("unknown type: {}", instance_type)))?;
+        }
+        Ok(spend)
+    }
+
+    // refuse a scale-up that would take the tag's projected spend over
+    // cap (per month), returning the projection otherwise
+    pub async fn check_budget(
+        &self,
+        tag: &str,
+        instance_type: &str,
+        region: &RegionInfo,
+        cap: f64,
+    ) -> Result<f64, LinodeError> {
+        let projected = self
+            .projected_monthly_spend(tag, Some((instance_type, region)))
+            .await?;
+        if projected > cap {
+            error!(
+                "Adding a {} in region: {} would bring tag: {} to ${:.2}/month, over the ${:.2} budget",
+                instance_type, region.code, tag, projected, cap
+            );
+            return Err(LinodeError::OverBudget {
+                tag: tag.to_owned(),
+                projected,
+                cap,
+            });
+        }
+        info!(
+            "Projected spend for tag: {} is ${:.2}/month of ${:.2}",
+            tag, projected, cap
+        );
+        Ok(projected)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::api_calls::ApiCalls;
+use crate::api_version::ApiVersion;
+use crate::concurrency::RequestLimiter;
+use crate::naming::NamingScheme;
+use crate::retry::RetryPolicy;
+use crate::secret::SecretString;
+use crate::shutdown::ShutdownHandle;
+use crate::timings::StepMetrics;
+use crate::token_source::{TokenSource, TOKEN_REFRESH};
+use crate::transport::LinodeTransport;
+use crate::{LinodeClient, API_HOST, DEFAULT_PAGE_SIZE};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use reqwest::Client;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Settings fixed when the HTTP client is made, e.g.
+//
+//   LinodeClient::builder(token, pub_key)
+//       .base_url("http://127.0.0.1:8080/v4/")
+//       .timeout(Duration::from_secs(30))
+//       .proxy("http://proxy.corp:3128")
+//       .build()?
+//
+// Everything else is set afterwards with the client's with_* methods.
+// Timeouts and proxies are left to the browser on wasm32, and don't apply
+// to a custom transport.
+#[derive(Clone)]
+pub struct LinodeClientBuilder {
+    token: SecretString,
+    pub_key: String,
+    base_url: String,
+    api_version: Option<ApiVersion>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+    page_size: u32,
+    transport: Option<Arc<dyn LinodeTransport>>,
+    token_source: Option<Arc<dyn TokenSource>>,
+}
+
+impl LinodeClientBuilder {
+    pub fn new(token: String, pub_key: String) -> Self {
+        LinodeClientBuilder {
+            token: SecretString::new(token),
+            pub_key,
+            base_url: API_HOST.to_owned(),
+            api_version: None,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            proxy: None,
+            page_size: DEFAULT_PAGE_SIZE,
+            transport: None,
+            token_source: None,
+        }
+    }
+
+    // API root, e.g. a test server; paths are appended as-is so keep the
+    // trailing slash
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_owned();
+        self
+    }
+
+    // API version every call goes to, applied to the base_url whichever
+    // is set first; v4 by default
+    pub fn api_version(mut self, version: ApiVersion) -> Self {
+        self.api_version = Some(version);
+        self
+    }
+
+    // limit on each whole request, from connecting to reading the body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    // send every request through proxy, e.g. "http://proxy.corp:3128";
+    // without one the HTTPS_PROXY and HTTP_PROXY variables apply
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_owned());
+        self
+    }
+
+    // page_size for list calls whose ListOptions don't set one, at most
+    // 500
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.clamp(25, DEFAULT_PAGE_SIZE);
+        self
+    }
+
+    // send requests through transport instead of the reqwest client,
+    // e.g. a FnTransport answering from memory in tests
+    pub fn transport<T: LinodeTransport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    // read the token from source instead, see TokenSource
+    pub fn token_source<S: TokenSource + 'static>(mut self, source: S) -> Self {
+        self.token_source = Some(Arc::new(source));
+        self
+    }
+
+    pub fn build(self) -> Result<LinodeClient, Box<dyn std::error::Error + Send + Sync>> {
+        let mut decoded_pub_key: Vec<u8> = STANDARD.decode(&self.pub_key)?;
+
+        // Strip any newline or whitespace characters from the end
+        while let Some(&last_byte) = decoded_pub_key.last() {
+            if last_byte == b'\n' || last_byte == b'\r' || last_byte == b' ' {
+                decoded_pub_key.pop();
+            } else {
+                break;
+            }
+        }
+
+        let mut client = Client::builder();
+        if let Some(user_agent) = &self.user_agent {
+            client = client.user_agent(user_agent);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(timeout) = self.timeout {
+                client = client.timeout(timeout);
+            }
+            if let Some(timeout) = self.connect_timeout {
+                client = client.connect_timeout(timeout);
+            }
+            if let Some(proxy) = &self.proxy {
+                client = client.proxy(reqwest::Proxy::all(proxy)?);
+            }
+        }
+
+        let client = client.build()?;
+        let transport = self.transport.unwrap_or_else(|| Arc::new(client.clone()));
+
+        Ok(LinodeClient {
+            token: self.token,
+            base_url: match self.api_version {
+                Some(version) => version.rebase(&self.base_url),
+                None => self.base_url,
+            },
+            pub_key: String::from_utf8_lossy(&decoded_pub_key).into_owned(),
+            client,
+            transport,
+            page_size: self.page_size,
+            verify_writes: false,
+            record_cache: None,
+            response_cache: None,
+            retry_policy: RetryPolicy::default(),
+            create_retry_policy: RetryPolicy::creates(),
+            trace_http: false,
+            cassette: None,
+            state_cache: None,
+            journal: None,
+            rate_limit: Mutex::new(None),
+            rate_limit_pacing: true,
+            step_metrics: StepMetrics::default(),
+            api_calls: ApiCalls::default(),
+            naming: NamingScheme::default(),
+            middleware: Vec::new(),
+            shutdown: ShutdownHandle::default(),
+            token_source: self.token_source,
+            token_refresh: TOKEN_REFRESH,
+            current_token: Mutex::new(None),
+            limiter: RequestLimiter::default(),
+        })
+    }
+}
+
+impl LinodeClient {
+    pub fn builder(token: String, pub_key: String) -> LinodeClientBuilder {
+        LinodeClientBuilder::new(token, pub_key)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::api_calls::endpoint;
+use crate::transport::{json_response, LinodeTransport};
+use crate::{LinodeClient, LinodeError};
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use reqwest::{Method, Request, Response};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+// An orchestration step chaos can fail, by the API calls it makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChaosStep {
+    // POST linode/instances
+    Create,
+    // PUT linode/instances/:id/configs/:id
+    SetInterfaces,
+    // POST linode/instances/:id/reboot or boot
+    Reboot,
+    // any write to domains/:id/records
+    Dns,
+    // DELETE linode/instances/:id
+    Delete,
+}
+
+impl ChaosStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChaosStep::Create => "create",
+            ChaosStep::SetInterfaces => "set_interfaces",
+            ChaosStep::Reboot => "reboot",
+            ChaosStep::Dns => "dns",
+            ChaosStep::Delete => "delete",
+        }
+    }
+
+    fn of(method: &Method, endpoint: &str) -> Option<Self> {
+        let segments = endpoint
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .skip_while(|segment| !matches!(*segment, "linode" | "domains"))
+            .collect::<Vec<_>>();
+        match (method.as_str(), segments.as_slice()) {
+            ("POST", ["linode", "instances"]) => Some(ChaosStep::Create),
+            ("PUT", ["linode", "instances", _, "configs", _]) => Some(ChaosStep::SetInterfaces),
+            ("POST", ["linode", "instances", _, "reboot" | "boot"]) => Some(ChaosStep::Reboot),
+            ("DELETE", ["linode", "instances", _]) => Some(ChaosStep::Delete),
+            ("POST" | "PUT" | "DELETE", ["domains", _, "records", ..]) => Some(ChaosStep::Dns),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ChaosStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ChaosStep {
+    type Err = LinodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(ChaosStep::Create),
+            "set_interfaces" => Ok(ChaosStep::SetInterfaces),
+            "reboot" => Ok(ChaosStep::Reboot),
+            "dns" => Ok(ChaosStep::Dns),
+            "delete" => Ok(ChaosStep::Delete),
+            _ => Err(LinodeError::Spec(format!("unknown chaos step: {}", s))),
+        }
+    }
+}
+
+// Fails the API calls of named steps with a probability each, answering
+// them with a 500 instead of sending them, so rollback, resume and
+// reconcile run in CI against a mock transport:
+//
+//   let chaos = Chaos::new(42).fail(ChaosStep::SetInterfaces, 1.0);
+//   let client = client.with_chaos(chaos.clone());
+//   assert!(client.scale_up_one(...).await.is_err());
+//   assert!(chaos.injected() > 0);
+//
+// Each attempt rolls separately, so retries can get past a probability
+// below 1. The same seed fails the same calls.
+#[derive(Clone)]
+pub struct Chaos {
+    faults: BTreeMap<ChaosStep, f64>,
+    rng: Arc<Mutex<StdRng>>,
+    injected: Arc<AtomicU64>,
+}
+
+impl Chaos {
+    pub fn new(seed: u64) -> Self {
+        Chaos {
+            faults: BTreeMap::new(),
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+            injected: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    // fail step's calls with probability, 0 to 1
+    pub fn fail(mut self, step: ChaosStep, probability: f64) -> Self {
+        self.faults.insert(step, probability.clamp(0.0, 1.0));
+        self
+    }
+
+    // steps and probabilities as "create=0.5,dns=0.1"; a step without
+    // one always fails
+    pub fn parse(spec: &str, seed: u64) -> Result<Self, LinodeError> {
+        let mut chaos = Chaos::new(seed);
+        for fault in spec.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let (step, probability) = match fault.split_once('=') {
+                Some((step, probability)) => (
+                    step,
+                    probability.parse::<f64>().map_err(|_| {
+                        LinodeError::Spec(format!("invalid chaos probability: {}", fault))
+                    })?,
+                ),
+                None => (fault, 1.0),
+            };
+            chaos = chaos.fail(step.parse()?, probability);
+        }
+        Ok(chaos)
+    }
+
+    // calls failed so far
+    pub fn injected(&self) -> u64 {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    fn roll(&self, request: &Request) -> Option<ChaosStep> {
+        let step = ChaosStep::of(request.method(), &endpoint(request.url().path()))?;
+        let probability = *self.faults.get(&step)?;
+        let hit = self.rng.lock().unwrap().gen_bool(probability);
+        hit.then_some(step)
+    }
+}
+
+struct ChaosTransport {
+    inner: Arc<dyn LinodeTransport>,
+    chaos: Chaos,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl LinodeTransport for ChaosTransport {
+    async fn execute(&self, request: Request) -> Result<Response, reqwest::Error> {
+        let Some(step) = self.chaos.roll(&request) else {
+            return self.inner.execute(request).await;
+        };
+        self.chaos.injected.fetch_add(1, Ordering::Relaxed);
+        error!(
+            "Chaos: failing {} {} at step {}",
+            request.method(),
+            request.url().path(),
+            step
+        );
+        Ok(json_response(
+            500,
+            &format!(
+                r#"{{"errors":[{{"reason":"chaos: injected failure at {}"}}]}}"#,
+                step
+            ),
+        ))
+    }
+}
+
+impl LinodeClient {
+    // fail calls as chaos says, in front of the transport set so far
+    pub fn with_chaos(mut self, chaos: Chaos) -> Self {
+        self.transport = Arc::new(ChaosTransport {
+            inner: self.transport,
+            chaos,
+        });
+        self
+    }
+}
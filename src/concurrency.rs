@@ -0,0 +1,55 @@
+use crate::LinodeClient;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+// requests a client sends at once unless with_max_in_flight says otherwise
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 32;
+
+// Caps how many requests are waiting on the API at once, however many
+// tasks share the client; the rest queue for a permit. A permit is held
+// for one attempt, not across retry backoff.
+#[derive(Debug)]
+pub(crate) struct RequestLimiter {
+    semaphore: Semaphore,
+    max: usize,
+}
+
+impl RequestLimiter {
+    pub(crate) fn new(max: usize) -> Self {
+        let max = max.max(1);
+        RequestLimiter {
+            semaphore: Semaphore::new(max),
+            max,
+        }
+    }
+
+    pub(crate) async fn acquire(&self) -> SemaphorePermit<'_> {
+        // the semaphore is never closed
+        self.semaphore
+            .acquire()
+            .await
+            .expect("request limiter is never closed")
+    }
+}
+
+impl Default for RequestLimiter {
+    fn default() -> Self {
+        RequestLimiter::new(DEFAULT_MAX_IN_FLIGHT)
+    }
+}
+
+impl LinodeClient {
+    // send at most max requests at once, at least one
+    pub fn with_max_in_flight(mut self, max: usize) -> Self {
+        self.limiter = RequestLimiter::new(max);
+        self
+    }
+
+    pub fn max_in_flight(&self) -> usize {
+        self.limiter.max
+    }
+
+    // requests sent and not yet answered
+    pub fn requests_in_flight(&self) -> usize {
+        self.limiter.max - self.limiter.semaphore.available_permits()
+    }
+}
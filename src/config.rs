@@ -0,0 +1,413 @@
+use crate::allowlist::Allowlist;
+use crate::naming::{NamingScheme, DEFAULT_TEMPLATE};
+use crate::secret::SecretString;
+use crate::token_source::FileToken;
+use crate::{LinodeClient, LinodeClientBuilder, LinodeError, ScaledNode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{error, info};
+
+// CLI settings, read from --config or ~/.config/linode/config.toml:
+//
+//   # the account used when none is named
+//   default_account = "staging"
+//
+//   [accounts.staging]
+//   token_file = "/run/secrets/linode-staging"
+//   pub_key = "c3NoLWVkMjU1MTkg..."
+//   region = "us-ord"
+//   domain_id = 1234
+//
+//   [accounts.prod]
+//   token = "..."
+//   pub_key = "c3NoLWVkMjU1MTkg..."
+//   region = "us-iad"
+//   domain_id = 5678
+//
+//   [hooks]
+//   on_scale_up = "cmdb-register \"$LINODE_LABEL\" \"$LINODE_IPV4\""
+//   on_scale_down = "cmdb-remove \"$LINODE_LABEL\""
+//   on_failure = "page-oncall \"$LINODE_ERROR\""
+//
+//   [profiles.api-prod]
+//   tag = "api"
+//   image = "private/1234"
+//   type = "g6-dedicated-2"
+//   subnet = "10.8.0.0/24"
+//   firewall_id = 4242
+//   user_data_template = "/etc/linode/api.yaml.j2"
+//   health_port = 443
+//   fallback_regions = ["us-ord", "@eu"]
+//
+//   [[allowlists]]
+//   tag = "api"
+//   firewall_id = 5151
+//   ports = "5432"
+//
+//   # where `scale up` without --region puts each instance
+//   [region_weights]
+//   us-iad = 3
+//   us-ord = 1
+//
+//   # how scale slots are named, for zones with their own convention
+//   [naming]
+//   template = "{tag}-{region}-{n}"
+//   width = 2
+//   environment = "prod"
+//
+//   # zones a tag's slots are mirrored into besides --domain-id
+//   [domains]
+//   api = [1234, 5678]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Config {
+    pub default_account: Option<String>,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, Account>,
+    #[serde(default)]
+    pub hooks: Hooks,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub region_weights: BTreeMap<String, u32>,
+    // firewall rules synced to their fleet's IPs after every scale event
+    #[serde(default)]
+    pub allowlists: Vec<Allowlist>,
+    #[serde(default)]
+    pub naming: Naming,
+    #[serde(default)]
+    pub domains: BTreeMap<String, Vec<u64>>,
+}
+
+impl Config {
+    // the domains besides domain that tag's slots are mirrored into,
+    // extra first
+    pub fn mirror_domains(&self, tag: &str, domain: u64, extra: &[u64]) -> Vec<u64> {
+        let mut mirrors = Vec::new();
+        for id in extra
+            .iter()
+            .chain(self.domains.get(tag).into_iter().flatten())
+        {
+            if *id != domain && !mirrors.contains(id) {
+                mirrors.push(*id);
+            }
+        }
+        mirrors
+    }
+}
+
+impl Config {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LinodeError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| LinodeError::Spec(format!("{}: {}", path.display(), e)))?;
+        toml::from_str(&source).map_err(|e| LinodeError::Spec(format!("{}: {}", path.display(), e)))
+    }
+
+    // an explicit path must exist, the default location is optional
+    pub fn load(path: Option<&Path>) -> Result<Self, LinodeError> {
+        match path {
+            Some(path) => Config::from_file(path),
+            None => match default_path().filter(|path| path.exists()) {
+                Some(path) => Config::from_file(path),
+                None => Ok(Config::default()),
+            },
+        }
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("linode").join("config.toml"))
+}
+
+// Scale-up settings for a tag, used by `scale up --profile` so every
+// operator provisions the fleet the same way. Flags override each field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub tag: String,
+    pub image: Option<String>,
+    #[serde(rename = "type")]
+    pub instance_type: Option<String>,
+    // VLAN /24 for the fleet
+    pub subnet: Option<String>,
+    pub firewall_id: Option<u64>,
+    pub user_data_template: Option<PathBuf>,
+    // TCP port that must accept connections before a node gets DNS
+    pub health_port: Option<u16>,
+    // regions or @groups to retry in, in order, when one is out of capacity
+    #[serde(default)]
+    pub fallback_regions: Vec<String>,
+}
+
+impl Config {
+    pub fn profile(&self, name: &str) -> Result<&Profile, LinodeError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| LinodeError::Spec(format!("no profile named {} in the config", name)))
+    }
+}
+
+// DNS slot naming, see NamingScheme.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Naming {
+    pub template: Option<String>,
+    // digits the slot number is zero padded to
+    #[serde(default)]
+    pub width: usize,
+    // put in front of every slot prefix, e.g. "prod"
+    pub environment: Option<String>,
+}
+
+impl Naming {
+    pub fn scheme(&self) -> Result<NamingScheme, LinodeError> {
+        Ok(
+            NamingScheme::new(self.template.as_deref().unwrap_or(DEFAULT_TEMPLATE))?
+                .with_width(self.width)
+                .with_environment(self.environment.clone()),
+        )
+    }
+}
+
+// A Linode account to run against, e.g. staging and prod, so switching
+// between them is a name instead of a set of environment variables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Account {
+    pub token: Option<SecretString>,
+    // read instead of token, and re-read as it rotates
+    pub token_file: Option<PathBuf>,
+    // base64 SSH public key put on new instances
+    pub pub_key: Option<String>,
+    // used when a command isn't given one
+    pub region: Option<String>,
+    pub domain_id: Option<u64>,
+    // API root, e.g. a mock server
+    pub api_url: Option<String>,
+}
+
+impl Account {
+    // a builder with the account's token, pub key and API root, for
+    // further settings
+    pub fn builder(&self) -> Result<LinodeClientBuilder, LinodeError> {
+        let pub_key = self
+            .pub_key
+            .clone()
+            .ok_or_else(|| LinodeError::Spec("the account has no pub_key".to_owned()))?;
+        let mut builder = match (&self.token_file, &self.token) {
+            (Some(path), _) => {
+                LinodeClient::builder(String::new(), pub_key).token_source(FileToken::new(path))
+            }
+            (None, Some(token)) => LinodeClient::builder(token.expose().to_owned(), pub_key),
+            (None, None) => {
+                return Err(LinodeError::Spec(
+                    "the account has no token or token_file".to_owned(),
+                ))
+            }
+        };
+        if let Some(api_url) = &self.api_url {
+            builder = builder.base_url(api_url);
+        }
+        Ok(builder)
+    }
+
+    pub fn client(&self) -> Result<LinodeClient, LinodeError> {
+        self.builder()?
+            .build()
+            .map_err(|e| LinodeError::Spec(format!("failed to build the client: {}", e)))
+    }
+}
+
+impl Config {
+    // the account called name, or default_account when None; None if
+    // neither is given
+    pub fn account(&self, name: Option<&str>) -> Result<Option<&Account>, LinodeError> {
+        let Some(name) = name.or(self.default_account.as_deref()) else {
+            return Ok(None);
+        };
+        self.accounts
+            .get(name)
+            .map(Some)
+            .ok_or_else(|| LinodeError::Spec(format!("no account named {} in the config", name)))
+    }
+
+    // a client for the account called name (or the default account),
+    // naming slots by the config's [naming]
+    pub fn client(&self, name: Option<&str>) -> Result<LinodeClient, LinodeError> {
+        let account = self.account(name)?.ok_or_else(|| {
+            LinodeError::Spec("no account named and no default_account".to_owned())
+        })?;
+        Ok(account.client()?.with_naming(self.naming.scheme()?))
+    }
+}
+
+impl LinodeClient {
+    // a client for an account in ~/.config/linode/config.toml, e.g.
+    // LinodeClient::for_account(Some("prod"))
+    pub fn for_account(name: Option<&str>) -> Result<Self, LinodeError> {
+        Config::load(None)?.client(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    ScaleUp,
+    ScaleDown,
+    Failure,
+}
+
+impl HookEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::ScaleUp => "scale_up",
+            HookEvent::ScaleDown => "scale_down",
+            HookEvent::Failure => "failure",
+        }
+    }
+}
+
+// Shell commands run by `sh -c` after scale events, with the details in
+// LINODE_* environment variables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Hooks {
+    pub on_scale_up: Option<String>,
+    pub on_scale_down: Option<String>,
+    pub on_failure: Option<String>,
+}
+
+impl Hooks {
+    fn command(&self, event: HookEvent) -> Option<&str> {
+        match event {
+            HookEvent::ScaleUp => self.on_scale_up.as_deref(),
+            HookEvent::ScaleDown => self.on_scale_down.as_deref(),
+            HookEvent::Failure => self.on_failure.as_deref(),
+        }
+    }
+
+    pub async fn node_event(&self, event: HookEvent, tag: &str, node: &ScaledNode) {
+        let mut env = vec![
+            ("LINODE_TAG", tag.to_owned()),
+            ("LINODE_REGION", node.region.clone()),
+            ("LINODE_INSTANCE_ID", node.id.to_string()),
+            ("LINODE_LABEL", node.label.clone()),
+            ("LINODE_OPERATION_ID", node.operation_id.clone()),
+        ];
+        if let Some(ip) = &node.ipv4 {
+            env.push(("LINODE_IPV4", ip.clone()));
+        }
+        if let Some(name) = &node.name {
+            env.push(("LINODE_DNS_NAME", name.clone()));
+        }
+        self.run(event, &env).await;
+    }
+
+    pub async fn failure(&self, action: &str, tag: &str, region: &str, err: &str) {
+        let env = vec![
+            ("LINODE_ACTION", action.to_owned()),
+            ("LINODE_TAG", tag.to_owned()),
+            ("LINODE_REGION", region.to_owned()),
+            ("LINODE_ERROR", err.to_owned()),
+        ];
+        self.run(HookEvent::Failure, &env).await;
+    }
+
+    // a failing hook is logged, never fails the scale operation itself
+    pub async fn run(&self, event: HookEvent, env: &[(&str, String)]) {
+        let Some(command) = self.command(event) else {
+            return;
+        };
+        info!("Running {} hook: {}", event.as_str(), command);
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("LINODE_HOOK", event.as_str())
+            .envs(env.iter().map(|(k, v)| (k, v)))
+            .status()
+            .await;
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => error!("{} hook exited with {}", event.as_str(), status),
+            Err(e) => error!("Failed to run {} hook: {}", event.as_str(), e),
+        }
+    }
+}
+
+// Defaults shared with linode-cli, read from ~/.config/linode-cli:
+//
+//   [DEFAULT]
+//   default-user = alice
+//
+//   [alice]
+//   token = ...
+//   region = us-east
+//   type = g6-standard-2
+//
+// Flags and environment variables take precedence over these.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinodeCliConfig {
+    pub token: Option<SecretString>,
+    pub region: Option<String>,
+    pub instance_type: Option<String>,
+}
+
+impl LinodeCliConfig {
+    // settings for user, or for the file's default-user when None
+    pub fn parse(source: &str, user: Option<&str>) -> Self {
+        let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                sections.push((name.trim().to_owned(), Vec::new()));
+            } else if let (Some((key, value)), Some((_, entries))) =
+                (line.split_once('='), sections.last_mut())
+            {
+                entries.push((key.trim().to_owned(), value.trim().to_owned()));
+            }
+        }
+        let lookup = |section: &str, key: &str| {
+            sections
+                .iter()
+                .filter(|(name, _)| name == section)
+                .flat_map(|(_, entries)| entries)
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .filter(|v| !v.is_empty())
+        };
+
+        let Some(user) = user
+            .map(str::to_owned)
+            .or_else(|| lookup("DEFAULT", "default-user"))
+        else {
+            return LinodeCliConfig::default();
+        };
+        LinodeCliConfig {
+            token: lookup(&user, "token").map(SecretString::new),
+            region: lookup(&user, "region"),
+            instance_type: lookup(&user, "type"),
+        }
+    }
+
+    // empty when the file doesn't exist or can't be read
+    pub fn load(user: Option<&str>) -> Self {
+        let Some(path) = linode_cli_path().filter(|path| path.exists()) else {
+            return LinodeCliConfig::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(source) => {
+                info!("Reading linode-cli defaults from {}", path.display());
+                LinodeCliConfig::parse(&source, user)
+            }
+            Err(e) => {
+                error!("Failed to read {}: {}", path.display(), e);
+                LinodeCliConfig::default()
+            }
+        }
+    }
+}
+
+pub fn linode_cli_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("linode-cli"))
+}
@@ -0,0 +1,132 @@
+use crate::regions::find_region;
+use crate::registry::{LinodeDnsRegistry, ServiceRegistry};
+use crate::{LinodeClient, LinodeError, LinodeInstance, A_RECORD, LOCALHOST};
+use tracing::info;
+
+// instance tag remembering the slot a cordoned node was pulled from
+pub const CORDON_TAG_PREFIX: &str = "cordoned:";
+
+pub fn cordoned_slot(instance: &LinodeInstance) -> Option<&str> {
+    instance
+        .tags
+        .iter()
+        .find_map(|tag| tag.strip_prefix(CORDON_TAG_PREFIX))
+}
+
+impl LinodeClient {
+    pub async fn fetch_instance_by_label(
+        &self,
+        label: &str,
+    ) -> Result<LinodeInstance, LinodeError> {
+        self.find_instance_by_label(label)
+            .await?
+            .ok_or_else(|| LinodeError::Spec(format!("no instance labelled {}", label)))
+    }
+
+    // Pull an instance out of traffic without touching it: its slot is
+    // parked on the sentinel target like on scale-down, but the instance
+    // keeps running and is tagged with the slot it held. Returns the slot
+    // name.
+    pub async fn cordon_instance(&self, domain: u64, label: &str) -> Result<String, LinodeError> {
+        let instance = self.fetch_instance_by_label(label).await?;
+        self.cordon(domain, &instance).await
+    }
+
+    pub async fn cordon(
+        &self,
+        domain: u64,
+        instance: &LinodeInstance,
+    ) -> Result<String, LinodeError> {
+        let label = &instance.label;
+        if let Some(slot) = cordoned_slot(instance) {
+            info!("Instance {} is already cordoned from {}", label, slot);
+            return Ok(slot.to_owned());
+        }
+
+        // the slot is in the fleet of whichever of its tags names one
+        let region = find_region(&instance.region).ok_or_else(|| {
+            LinodeError::Spec(format!(
+                "instance {} is in unknown region: {}",
+                label, instance.region
+            ))
+        })?;
+        let registry = LinodeDnsRegistry::new(self, domain);
+        let mut slot = None;
+        for tag in &instance.tags {
+            let prefix = self.naming().prefix(tag, region.code);
+            slot = registry.deregister(&prefix, instance).await?;
+            if slot.is_some() {
+                break;
+            }
+        }
+        let slot = slot.ok_or_else(|| {
+            LinodeError::Spec(format!(
+                "instance {} has no DNS slot in domain ID: {}",
+                label, domain
+            ))
+        })?;
+
+        let mut tags = instance.tags.clone();
+        tags.push(format!("{}{}", CORDON_TAG_PREFIX, slot));
+        self.update_instance_tags(instance.id, tags).await?;
+        info!("Cordoned instance {} from slot: {}", label, slot);
+        Ok(slot)
+    }
+
+    // Put a cordoned instance back into traffic, in the slot it was pulled
+    // from if that is still free, otherwise in the next free slot of the
+    // same fleet. Returns the slot name.
+    pub async fn uncordon_instance(&self, domain: u64, label: &str) -> Result<String, LinodeError> {
+        let instance = self.fetch_instance_by_label(label).await?;
+        let original = cordoned_slot(&instance)
+            .ok_or_else(|| LinodeError::Spec(format!("instance {} is not cordoned", label)))?
+            .to_owned();
+        let address = instance
+            .ipv4
+            .first()
+            .ok_or_else(|| LinodeError::Spec(format!("instance {} has no public IPv4", label)))?;
+
+        let records = self.fetch_records(domain).await?;
+        let free = records.iter().find(|rec| {
+            rec.record_type == A_RECORD && rec.name == original && rec.target == LOCALHOST
+        });
+        let reclaimed = match free {
+            Some(rec) => self.claim_record(domain, rec.id, address).await?,
+            None => false,
+        };
+        let slot = if reclaimed {
+            original.clone()
+        } else {
+            let prefix = find_region(&instance.region)
+                .and_then(|region| {
+                    let tag = self
+                        .naming()
+                        .fleet_of(&original, &instance.tags, region.code)?;
+                    Some(self.naming().prefix(tag, region.code))
+                })
+                .ok_or_else(|| {
+                    LinodeError::Spec(format!(
+                        "slot {} belongs to no fleet of {}",
+                        original, label
+                    ))
+                })?;
+            info!(
+                "Slot: {} was taken while {} was cordoned, registering under {}",
+                original, label, prefix
+            );
+            LinodeDnsRegistry::new(self, domain)
+                .register(&prefix, &instance)
+                .await?
+        };
+
+        let tags = instance
+            .tags
+            .iter()
+            .filter(|tag| !tag.starts_with(CORDON_TAG_PREFIX))
+            .cloned()
+            .collect();
+        self.update_instance_tags(instance.id, tags).await?;
+        info!("Uncordoned instance {} into slot: {}", label, slot);
+        Ok(slot)
+    }
+}
@@ -0,0 +1,786 @@
+use crate::idempotency::record_key;
+use crate::naming::NamingScheme;
+use crate::{
+    LinodeClient, LinodeError, ListOptions, A_RECORD, BULK_CONCURRENCY, LOCALHOST, TXT_RECORD,
+};
+use futures::{stream, Stream, StreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use tracing::{error, info};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Domain {
+    pub id: u64,
+    pub domain: String,
+    #[serde(rename = "type")]
+    pub domain_type: String,
+    pub status: String,
+    pub soa_email: Option<String>,
+    pub tags: Vec<String>,
+    // SOA timers in seconds, 0 means the API default
+    #[serde(default)]
+    pub refresh_sec: u32,
+    #[serde(default)]
+    pub retry_sec: u32,
+    #[serde(default)]
+    pub expire_sec: u32,
+    #[serde(default)]
+    pub ttl_sec: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DomainRecordOptions {
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub name: String,
+    pub target: String,
+    pub ttl_sec: i32,
+    // SRV only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<i32>,
+}
+
+impl DomainRecordOptions {
+    pub fn a(name: &str, target: &str) -> Self {
+        DomainRecordOptions {
+            record_type: A_RECORD.to_owned(),
+            name: name.to_owned(),
+            target: target.to_owned(),
+            ttl_sec: 30,
+            ..Default::default()
+        }
+    }
+
+    pub fn txt(name: &str, target: &str) -> Self {
+        DomainRecordOptions {
+            record_type: TXT_RECORD.to_owned(),
+            name: name.to_owned(),
+            target: target.to_owned(),
+            ttl_sec: 30,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DomainRecordUpdateOptions {
+    target: String,
+    ttl_sec: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainRecord {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub name: String,
+    pub target: String,
+    pub priority: Option<i32>,
+    pub weight: Option<i32>,
+    pub port: Option<i32>,
+    pub service: Option<String>,
+    pub protocol: Option<String>,
+    pub ttl_sec: i32,
+    // when the record was last changed, e.g. "2024-05-01T12:00:00" UTC
+    #[serde(default)]
+    pub updated: Option<String>,
+}
+
+// What upsert_record did to bring a record in line.
+#[derive(Debug, Clone)]
+pub enum Upsert {
+    Created(DomainRecord),
+    Updated(DomainRecord),
+    Unchanged(DomainRecord),
+}
+
+impl Upsert {
+    pub fn record(&self) -> &DomainRecord {
+        match self {
+            Upsert::Created(rec) | Upsert::Updated(rec) | Upsert::Unchanged(rec) => rec,
+        }
+    }
+
+    pub fn into_record(self) -> DomainRecord {
+        match self {
+            Upsert::Created(rec) | Upsert::Updated(rec) | Upsert::Unchanged(rec) => rec,
+        }
+    }
+
+    pub fn is_changed(&self) -> bool {
+        !matches!(self, Upsert::Unchanged(_))
+    }
+}
+
+impl DomainRecord {
+    fn extract_prefix_and_number(&self) -> (&str, Option<i32>) {
+        let re = Regex::new(r"^(.*?)-(\d+)$").unwrap();
+        if let Some(caps) = re.captures(&self.name) {
+            let prefix = caps.get(1).map_or("", |m| m.as_str());
+            let number = caps.get(2).and_then(|m| m.as_str().parse::<i32>().ok());
+            (prefix, number)
+        } else {
+            (&self.name, None)
+        }
+    }
+}
+
+// Domain record and slot calls, from LinodeClient::dns().
+#[derive(Clone, Copy)]
+pub struct Dns<'a> {
+    client: &'a LinodeClient,
+}
+
+impl LinodeClient {
+    pub fn dns(&self) -> Dns<'_> {
+        Dns { client: self }
+    }
+
+    pub fn invalidate_record_cache(&self, domain: u64) {
+        if let Some(cache) = &self.record_cache {
+            cache.lock().unwrap().remove(&domain);
+        }
+    }
+
+    pub(crate) fn cache_records<F>(&self, domain: u64, f: F)
+    where
+        F: FnOnce(&mut Vec<DomainRecord>),
+    {
+        if let Some(cache) = &self.record_cache {
+            if let Some(records) = cache.lock().unwrap().get_mut(&domain) {
+                f(records);
+                sort_records(records);
+            }
+        }
+    }
+
+    pub(crate) fn cache_record(&self, domain: u64, record: &DomainRecord) {
+        self.cache_records(domain, |records| {
+            records.retain(|rec| rec.id != record.id);
+            records.push(record.clone());
+        });
+    }
+
+    pub async fn fetch_records(&self, domain: u64) -> Result<Vec<DomainRecord>, LinodeError> {
+        if let Some(cache) = &self.record_cache {
+            if let Some(records) = cache.lock().unwrap().get(&domain) {
+                info!("Using cached records for domain ID: {}", domain);
+                return Ok(records.clone());
+            }
+        }
+
+        let records = self
+            .fetch_records_with(domain, &ListOptions::default())
+            .await?;
+        if let Some(cache) = &self.record_cache {
+            cache.lock().unwrap().insert(domain, records.clone());
+        }
+        Ok(records)
+    }
+
+    pub async fn fetch_records_with(
+        &self,
+        domain: u64,
+        options: &ListOptions,
+    ) -> Result<Vec<DomainRecord>, LinodeError> {
+        info!("Fetching domain records for domain ID: {}", domain);
+        let path = format!("domains/{}/records", domain);
+        let mut records = self
+            .cached_list(
+                &path,
+                options,
+                self.fetch_all::<DomainRecord>(&path, options),
+            )
+            .await?;
+        info!(
+            "Fetched {} records for domain ID: {}",
+            records.len(),
+            domain
+        );
+
+        if options.order_by.is_none() {
+            sort_records(&mut records);
+        }
+
+        Ok(records)
+    }
+
+    // Every record of domain, fetched a page at a time as the stream is
+    // read. Records come in API order and bypass the record cache.
+    pub fn fetch_records_stream(
+        &self,
+        domain: u64,
+    ) -> impl Stream<Item = Result<DomainRecord, LinodeError>> + '_ {
+        self.paginate::<DomainRecord>(
+            &format!("domains/{}/records", domain),
+            &ListOptions::default(),
+        )
+        .into_stream()
+    }
+
+    pub async fn fetch_domain(&self, domain: u64) -> Result<Domain, LinodeError> {
+        info!("Fetching domain ID: {}", domain);
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}/domains/{}", self.base_url, domain))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+
+        response.json::<Domain>().await
+    }
+
+    pub async fn fetch_record(&self, domain: u64, id: u64) -> Result<DomainRecord, LinodeError> {
+        info!("Fetching record ID: {} in domain ID: {}", id, domain);
+        let response = self
+            .send(
+                self.client
+                    .get(format!(
+                        "{}/domains/{}/records/{}",
+                        self.base_url, domain, id
+                    ))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+
+        let record = response.json::<DomainRecord>().await?;
+        self.cache_record(domain, &record);
+        Ok(record)
+    }
+
+    // point a free slot at target, then read it back to make sure a
+    // concurrent scaler didn't claim the same slot in the meantime
+    pub async fn claim_record(
+        &self,
+        domain: u64,
+        id: u64,
+        target: &str,
+    ) -> Result<bool, LinodeError> {
+        // the slot may have been taken since our (possibly cached) listing
+        let current = self.fetch_record(domain, id).await?;
+        if current.target != LOCALHOST {
+            info!(
+                "Record ID: {} is no longer free, target: {}",
+                id, current.target
+            );
+            return Ok(false);
+        }
+
+        self.put_record_target(domain, id, target).await?;
+
+        let record = self.fetch_record(domain, id).await?;
+        if record.target == target {
+            info!("Claimed record ID: {} for target: {}", id, target);
+            Ok(true)
+        } else {
+            info!(
+                "Record ID: {} was claimed by another target: {}",
+                id, record.target
+            );
+            Ok(false)
+        }
+    }
+
+    pub async fn delete_record(&self, domain: u64, id: u64) -> Result<(), LinodeError> {
+        info!("Deleting record with ID: {} in domain ID: {}", id, domain);
+        self.send(
+            self.client
+                .delete(format!(
+                    "{}/domains/{}/records/{}",
+                    self.base_url, domain, id
+                ))
+                .bearer_auth(self.token.expose()),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        self.cache_records(domain, |records| records.retain(|rec| rec.id != id));
+        info!("Record ID: {} deleted successfully", id);
+        Ok(())
+    }
+
+    pub async fn update_record_target(
+        &self,
+        domain: u64,
+        id: u64,
+        target: &str,
+    ) -> Result<(), LinodeError> {
+        self.put_record_target(domain, id, target).await?;
+
+        if self.verify_writes {
+            let record = self.fetch_record(domain, id).await?;
+            verify_record_field(domain, id, "target", target, &record.target)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn put_record_target(
+        &self,
+        domain: u64,
+        id: u64,
+        target: &str,
+    ) -> Result<(), LinodeError> {
+        self.put_record(domain, id, target, 30).await
+    }
+
+    async fn put_record(
+        &self,
+        domain: u64,
+        id: u64,
+        target: &str,
+        ttl: i32,
+    ) -> Result<(), LinodeError> {
+        info!(
+            "Updating record ID: {} in domain ID: {} with new target: {}",
+            id, domain, target
+        );
+        let options = DomainRecordUpdateOptions {
+            target: target.to_owned(),
+            ttl_sec: ttl,
+        };
+        self.send(
+            self.client
+                .put(format!(
+                    "{}/domains/{}/records/{}",
+                    self.base_url, domain, id
+                ))
+                .bearer_auth(self.token.expose())
+                .json(&options),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        self.cache_records(domain, |records| {
+            if let Some(rec) = records.iter_mut().find(|rec| rec.id == id) {
+                rec.target = target.to_owned();
+                rec.ttl_sec = ttl;
+            }
+        });
+        info!(
+            "Record ID: {} updated successfully to target: {}",
+            id, target
+        );
+        Ok(())
+    }
+
+    pub async fn create_a_record(
+        &self,
+        domain: u64,
+        name: String,
+        target: String,
+    ) -> Result<DomainRecord, LinodeError> {
+        info!(
+            "Creating new A record in domain ID: {} with name: {} and target: {}",
+            domain, name, target
+        );
+        let options = DomainRecordOptions::a(&name, &target);
+        let record = self.post_record(domain, &options).await?;
+        info!(
+            "A record created successfully with name: {} in domain ID: {}",
+            name, domain
+        );
+
+        if self.verify_writes {
+            let stored = self.fetch_record(domain, record.id).await?;
+            verify_record_field(domain, stored.id, "type", A_RECORD, &stored.record_type)?;
+            verify_record_field(domain, stored.id, "name", &name, &stored.name)?;
+            verify_record_field(domain, stored.id, "target", &target, &stored.target)?;
+            return Ok(stored);
+        }
+
+        Ok(record)
+    }
+
+    // Make the record_type record called name point at target with ttl:
+    // update the first one found, or create it if there's none. The
+    // create is idempotent, so a retried POST can't leave a duplicate.
+    pub async fn upsert_record(
+        &self,
+        domain: u64,
+        name: &str,
+        record_type: &str,
+        target: &str,
+        ttl: i32,
+    ) -> Result<Upsert, LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        let Some(existing) = records
+            .into_iter()
+            .find(|rec| rec.record_type == record_type && rec.name == name)
+        else {
+            let options = DomainRecordOptions {
+                record_type: record_type.to_owned(),
+                name: name.to_owned(),
+                target: target.to_owned(),
+                ttl_sec: ttl,
+                ..Default::default()
+            };
+            let record = self.post_record(domain, &options).await?;
+            if self.verify_writes {
+                let stored = self.fetch_record(domain, record.id).await?;
+                verify_record_field(domain, stored.id, "target", target, &stored.target)?;
+                return Ok(Upsert::Created(stored));
+            }
+            return Ok(Upsert::Created(record));
+        };
+        if existing.target == target && existing.ttl_sec == ttl {
+            return Ok(Upsert::Unchanged(existing));
+        }
+
+        self.put_record(domain, existing.id, target, ttl).await?;
+        if self.verify_writes {
+            let stored = self.fetch_record(domain, existing.id).await?;
+            verify_record_field(domain, stored.id, "target", target, &stored.target)?;
+            return Ok(Upsert::Updated(stored));
+        }
+        Ok(Upsert::Updated(DomainRecord {
+            target: target.to_owned(),
+            ttl_sec: ttl,
+            ..existing
+        }))
+    }
+
+    pub(crate) async fn post_record(
+        &self,
+        domain: u64,
+        options: &DomainRecordOptions,
+    ) -> Result<DomainRecord, LinodeError> {
+        let record = self
+            .create_idempotent(
+                &record_key(options),
+                || async {
+                    self.send(
+                        self.client
+                            .post(format!("{}/domains/{}/records", self.base_url, domain))
+                            .bearer_auth(self.token.expose())
+                            .json(options),
+                    )
+                    .await?
+                    .json::<DomainRecord>()
+                    .await
+                },
+                || self.find_record(domain, options),
+            )
+            .await?;
+        self.cache_record(domain, &record);
+        Ok(record)
+    }
+
+    pub async fn create_records(
+        &self,
+        domain: u64,
+        records: Vec<DomainRecordOptions>,
+    ) -> Vec<Result<DomainRecord, LinodeError>> {
+        self.create_records_concurrent(domain, records, BULK_CONCURRENCY)
+            .await
+    }
+
+    // create many records with at most `concurrency` requests in flight,
+    // returning one result per record in input order
+    pub async fn create_records_concurrent(
+        &self,
+        domain: u64,
+        records: Vec<DomainRecordOptions>,
+        concurrency: usize,
+    ) -> Vec<Result<DomainRecord, LinodeError>> {
+        info!(
+            "Creating {} records in domain ID: {}",
+            records.len(),
+            domain
+        );
+        let results = stream::iter(records)
+            .map(|options| async move {
+                let result = self.post_record(domain, &options).await;
+                if let Err(e) = &result {
+                    error!("Failed to create record: {}: {}", options.name, e);
+                }
+                result
+            })
+            .buffered(concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+
+        info!(
+            "Created {} of {} records in domain ID: {}",
+            results.iter().filter(|r| r.is_ok()).count(),
+            results.len(),
+            domain
+        );
+        results
+    }
+
+    // make sure at least `count` free slots exist for prefix, creating the
+    // missing ones in bulk so a multi-instance scale-up only claims
+    // existing records
+    pub async fn reserve_slots(
+        &self,
+        domain: u64,
+        prefix: &str,
+        count: usize,
+    ) -> Result<Vec<DomainRecord>, LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        let slots = records
+            .iter()
+            .filter(|rec| rec.record_type == A_RECORD && self.naming.is_slot(prefix, &rec.name));
+        let free = slots.clone().filter(|rec| rec.target == LOCALHOST).count();
+        let next = slots
+            .filter_map(|rec| self.naming.sequence(prefix, &rec.name))
+            .max()
+            .unwrap_or(0);
+
+        let missing = count.saturating_sub(free);
+        info!(
+            "Reserving {} new slots for prefix: {} ({} free)",
+            missing, prefix, free
+        );
+        let options = (1..=missing)
+            .map(|i| DomainRecordOptions::a(&self.naming.slot(prefix, next + i as i32), LOCALHOST))
+            .collect();
+
+        self.create_records(domain, options)
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    // claim the first free slot for prefix, or create the next sequential
+    // record, returning the name registered for ip
+    pub async fn register_slot(
+        &self,
+        domain: u64,
+        prefix: &str,
+        ip: &str,
+    ) -> Result<String, LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        let mut seqs = Vec::new();
+
+        for rec in &records {
+            if rec.record_type != A_RECORD {
+                continue;
+            }
+            if let Some(n) = self.naming.sequence(prefix, &rec.name) {
+                seqs.push(n);
+                // found a free slot, try to claim it - another scaler may
+                // have raced us to it, in which case move on to the next
+                if rec.target == LOCALHOST && self.claim_record(domain, rec.id, ip).await? {
+                    return Ok(rec.name.clone());
+                }
+            }
+        }
+
+        seqs.sort();
+        seqs.reverse();
+        let n = if !seqs.is_empty() { seqs[0] + 1 } else { 1 };
+        let record = self
+            .create_a_record(domain, self.naming.slot(prefix, n), ip.to_owned())
+            .await?;
+        Ok(record.name)
+    }
+
+    // Point the A record called name in domain at target, creating it if
+    // it's missing, so another zone mirrors a slot. A parked slot with no
+    // record isn't created. Returns whether anything changed.
+    pub async fn mirror_record(
+        &self,
+        domain: u64,
+        name: &str,
+        target: &str,
+    ) -> Result<bool, LinodeError> {
+        if target == LOCALHOST {
+            let records = self.fetch_records(domain).await?;
+            let exists = records
+                .iter()
+                .any(|rec| rec.record_type == A_RECORD && rec.name == name);
+            if !exists {
+                return Ok(false);
+            }
+        }
+        let upsert = self
+            .upsert_record(domain, name, A_RECORD, target, 30)
+            .await?;
+        Ok(upsert.is_changed())
+    }
+}
+
+impl<'a> Dns<'a> {
+    pub fn invalidate_record_cache(&self, domain: u64) {
+        self.client.invalidate_record_cache(domain)
+    }
+
+    pub async fn fetch_records(&self, domain: u64) -> Result<Vec<DomainRecord>, LinodeError> {
+        self.client.fetch_records(domain).await
+    }
+
+    pub fn fetch_records_stream(
+        &self,
+        domain: u64,
+    ) -> impl Stream<Item = Result<DomainRecord, LinodeError>> + 'a {
+        self.client.fetch_records_stream(domain)
+    }
+
+    pub async fn fetch_records_with(
+        &self,
+        domain: u64,
+        options: &ListOptions,
+    ) -> Result<Vec<DomainRecord>, LinodeError> {
+        self.client.fetch_records_with(domain, options).await
+    }
+
+    pub async fn fetch_domain(&self, domain: u64) -> Result<Domain, LinodeError> {
+        self.client.fetch_domain(domain).await
+    }
+
+    pub async fn fetch_record(&self, domain: u64, id: u64) -> Result<DomainRecord, LinodeError> {
+        self.client.fetch_record(domain, id).await
+    }
+
+    pub async fn claim_record(
+        &self,
+        domain: u64,
+        id: u64,
+        target: &str,
+    ) -> Result<bool, LinodeError> {
+        self.client.claim_record(domain, id, target).await
+    }
+
+    pub async fn delete_record(&self, domain: u64, id: u64) -> Result<(), LinodeError> {
+        self.client.delete_record(domain, id).await
+    }
+
+    pub async fn update_record_target(
+        &self,
+        domain: u64,
+        id: u64,
+        target: &str,
+    ) -> Result<(), LinodeError> {
+        self.client.update_record_target(domain, id, target).await
+    }
+
+    pub async fn create_a_record(
+        &self,
+        domain: u64,
+        name: String,
+        target: String,
+    ) -> Result<DomainRecord, LinodeError> {
+        self.client.create_a_record(domain, name, target).await
+    }
+
+    pub async fn create_records(
+        &self,
+        domain: u64,
+        records: Vec<DomainRecordOptions>,
+    ) -> Vec<Result<DomainRecord, LinodeError>> {
+        self.client.create_records(domain, records).await
+    }
+
+    pub async fn create_records_concurrent(
+        &self,
+        domain: u64,
+        records: Vec<DomainRecordOptions>,
+        concurrency: usize,
+    ) -> Vec<Result<DomainRecord, LinodeError>> {
+        self.client
+            .create_records_concurrent(domain, records, concurrency)
+            .await
+    }
+
+    pub async fn reserve_slots(
+        &self,
+        domain: u64,
+        prefix: &str,
+        count: usize,
+    ) -> Result<Vec<DomainRecord>, LinodeError> {
+        self.client.reserve_slots(domain, prefix, count).await
+    }
+
+    pub async fn register_slot(
+        &self,
+        domain: u64,
+        prefix: &str,
+        ip: &str,
+    ) -> Result<String, LinodeError> {
+        self.client.register_slot(domain, prefix, ip).await
+    }
+
+    pub async fn upsert_record(
+        &self,
+        domain: u64,
+        name: &str,
+        record_type: &str,
+        target: &str,
+        ttl: i32,
+    ) -> Result<Upsert, LinodeError> {
+        self.client
+            .upsert_record(domain, name, record_type, target, ttl)
+            .await
+    }
+}
+
+// Sort the records by prefix and then by the numeric suffix
+pub(crate) fn sort_records(records: &mut [DomainRecord]) {
+    records.sort_by(|a, b| {
+        let (prefix_a, num_a) = a.extract_prefix_and_number();
+        let (prefix_b, num_b) = b.extract_prefix_and_number();
+
+        match prefix_a.cmp(prefix_b) {
+            Ordering::Equal => num_a.cmp(&num_b),
+            other => other,
+        }
+    });
+}
+
+pub(crate) fn verify_record_field(
+    domain: u64,
+    id: u64,
+    field: &'static str,
+    expected: &str,
+    actual: &str,
+) -> Result<(), LinodeError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        error!(
+            "Record ID: {} in domain ID: {} stored {} '{}', expected '{}'",
+            id, domain, field, actual, expected
+        );
+        Err(LinodeError::RecordMismatch {
+            domain,
+            id,
+            field,
+            expected: expected.to_owned(),
+            actual: actual.to_owned(),
+        })
+    }
+}
+
+// the slot scale-up will claim when nobody races it: the first free
+// record for the prefix, or the next sequential name after the highest
+pub(crate) fn predict_slot_name(
+    records: &[DomainRecord],
+    naming: &NamingScheme,
+    prefix: &str,
+) -> String {
+    let slots = records
+        .iter()
+        .filter(|rec| rec.record_type == A_RECORD && naming.is_slot(prefix, &rec.name));
+    if let Some(free) = slots.clone().find(|rec| rec.target == LOCALHOST) {
+        return free.name.clone();
+    }
+    let n = slots
+        .filter_map(|rec| naming.sequence(prefix, &rec.name))
+        .max()
+        .map_or(1, |n| n + 1);
+    naming.slot(prefix, n)
+}
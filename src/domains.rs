@@ -0,0 +1,148 @@
+use crate::{Domain, LinodeClient, LinodeError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use tracing::info;
+
+// Zone-level settings. Unset fields are left as they are; the API rounds
+// timers to the nearest value it supports (300, 3600, 7200, 14400, 28800,
+// 57600, 86400, 172800, 345600, 604800, 1209600 or 2419200), and 0 resets
+// one to its default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soa_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_sec: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_sec: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expire_sec: Option<u32>,
+    // default TTL for records without their own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl_sec: Option<u32>,
+}
+
+impl DomainSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == DomainSettings::default()
+    }
+}
+
+impl LinodeClient {
+    // a master zone served by Linode's nameservers
+    pub async fn create_domain(
+        &self,
+        domain: &str,
+        soa_email: &str,
+    ) -> Result<Domain, LinodeError> {
+        info!("Creating domain: {} with SOA email: {}", domain, soa_email);
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/domains", self.base_url))
+                    .bearer_auth(self.token.expose())
+                    .json(&json!({
+                        "domain": domain,
+                        "type": "master",
+                        "soa_email": soa_email,
+                    })),
+            )
+            .await?;
+
+        let created = response.json::<Domain>().await?;
+        info!("Created domain: {} with ID: {}", domain, created.id);
+        Ok(created)
+    }
+
+    pub async fn update_domain_settings(
+        &self,
+        domain: u64,
+        settings: &DomainSettings,
+    ) -> Result<Domain, LinodeError> {
+        if settings.is_empty() {
+            return self.fetch_domain(domain).await;
+        }
+        info!("Updating settings of domain ID: {}: {:?}", domain, settings);
+        let response = self
+            .send(
+                self.client
+                    .put(format!("{}/domains/{}", self.base_url, domain))
+                    .bearer_auth(self.token.expose())
+                    .json(settings),
+            )
+            .await?;
+
+        let updated = response.json::<Domain>().await?;
+        info!("Domain ID: {} settings updated successfully", domain);
+        Ok(updated)
+    }
+
+    // copy a zone and all its records to a new domain
+    pub async fn clone_domain(&self, source: u64, new_domain: &str) -> Result<Domain, LinodeError> {
+        self.clone_domain_with(source, new_domain, &HashMap::new())
+            .await
+    }
+
+    // clone, then point records whose target is a key of targets at the
+    // mapped value instead, e.g. production IPs to staging ones
+    pub async fn clone_domain_with(
+        &self,
+        source: u64,
+        new_domain: &str,
+        targets: &HashMap<String, String>,
+    ) -> Result<Domain, LinodeError> {
+        info!("Cloning domain ID: {} to {}", source, new_domain);
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/domains/{}/clone", self.base_url, source))
+                    .bearer_auth(self.token.expose())
+                    .json(&json!({ "domain": new_domain })),
+            )
+            .await?;
+        let domain = response.json::<Domain>().await?;
+        info!("Cloned domain ID: {} as domain ID: {}", source, domain.id);
+
+        if targets.is_empty() {
+            return Ok(domain);
+        }
+        let records = self.fetch_records(domain.id).await?;
+        let mut rewritten = 0;
+        for record in &records {
+            let Some(target) = targets.get(&record.target) else {
+                continue;
+            };
+            info!(
+                "Rewriting record {} {} in domain ID: {} from {} to {}",
+                record.record_type, record.name, domain.id, record.target, target
+            );
+            // only the target changes, TTLs stay as cloned
+            self.send(
+                self.client
+                    .put(format!(
+                        "{}/domains/{}/records/{}",
+                        self.base_url, domain.id, record.id
+                    ))
+                    .bearer_auth(self.token.expose())
+                    .json(&json!({ "target": target })),
+            )
+            .await?
+            .error_for_status()
+            .await?;
+            rewritten += 1;
+        }
+        self.cache_records(domain.id, |records| {
+            for record in records.iter_mut() {
+                if let Some(target) = targets.get(&record.target) {
+                    record.target = target.clone();
+                }
+            }
+        });
+        info!(
+            "Rewrote {} record targets in domain ID: {}",
+            rewritten, domain.id
+        );
+        Ok(domain)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::cordon::cordoned_slot;
+use crate::regions::RegionInfo;
+use crate::{LinodeClient, LinodeError, ScaleUpOptions, ScaledNode};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+// Where to start replacements before a region is drained.
+#[derive(Debug)]
+pub struct DrainReplacement<'a> {
+    pub region: &'a RegionInfo,
+    pub image_id: String,
+    pub instance_type: String,
+    pub options: ScaleUpOptions,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DrainReport {
+    // (label, slot it was pulled from)
+    pub cordoned: Vec<(String, String)>,
+    pub replacements: Vec<ScaledNode>,
+}
+
+impl LinodeClient {
+    // Take a region out of rotation for maintenance: cordon every tagged
+    // instance there, leaving them running. With a replacement, the same
+    // number of nodes is scaled up in its region first, so capacity never
+    // dips. Instances already cordoned are left as they are. A shutdown
+    // stops between instances, and before any cordoning if it comes while
+    // replacements are scaled up.
+    pub async fn drain_region(
+        &self,
+        domain: u64,
+        tag: &str,
+        region: &RegionInfo,
+        replacement: Option<&DrainReplacement<'_>>,
+    ) -> Result<DrainReport, LinodeError> {
+        let instances = self
+            .get_fleet_instances(tag, region)
+            .await?
+            .into_iter()
+            .filter(|instance| cordoned_slot(instance).is_none())
+            .collect::<Vec<_>>();
+        info!(
+            "Draining {} instances with tag: {} from region: {}",
+            instances.len(),
+            tag,
+            region.code
+        );
+
+        let mut report = DrainReport::default();
+        if let Some(replacement) = replacement {
+            for _ in &instances {
+                if self.is_shutting_down() {
+                    break;
+                }
+                report.replacements.push(
+                    self.scale_up_one_with(
+                        &replacement.image_id,
+                        &replacement.instance_type,
+                        domain,
+                        replacement.region,
+                        tag,
+                        &replacement.options,
+                    )
+                    .await?,
+                );
+            }
+            // capacity is short of what cordoning would take away
+            if self.is_shutting_down() {
+                return Ok(report);
+            }
+        }
+
+        for instance in &instances {
+            if self.is_shutting_down() {
+                info!("Stopped draining region: {}", region.code);
+                break;
+            }
+            match self.cordon(domain, instance).await {
+                Ok(slot) => report.cordoned.push((instance.label.clone(), slot)),
+                // never registered, nothing to pull from traffic
+                Err(LinodeError::Spec(e)) => info!("Skipping {}: {}", instance.label, e),
+                Err(e) => return Err(e),
+            }
+        }
+        info!(
+            "Drained region: {}, cordoned {} instances",
+            region.code,
+            report.cordoned.len()
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+// Which API call an error came from. request_id is generated per call and
+// also recorded on its tracing span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub method: String,
+    pub endpoint: String,
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} (request {})",
+            self.method, self.endpoint, self.request_id
+        )
+    }
+}
+
+// One entry of Linode's {"errors": [{"field", "reason"}]} payload; field
+// names the request field a validation error is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: Option<String>,
+    pub reason: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{}: {}", field, self.reason),
+            None => write!(f, "{}", self.reason),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LinodeError {
+    // 401 or 403: the token is missing, expired or lacks a scope
+    Auth {
+        status: u16,
+        context: RequestContext,
+        reasons: Vec<FieldError>,
+    },
+    NotFound {
+        context: RequestContext,
+    },
+    // 429, with how long the API asked to wait if it said
+    RateLimited {
+        context: RequestContext,
+        retry_after: Option<Duration>,
+    },
+    // 400 or 422: the first field the API rejected (None when it rejected
+    // the request as a whole) and why; reasons has every entry
+    Validation {
+        field: Option<String>,
+        reason: String,
+        context: RequestContext,
+        reasons: Vec<FieldError>,
+    },
+    // any other error status, e.g. a 5xx
+    Api {
+        status: u16,
+        context: RequestContext,
+        reasons: Vec<FieldError>,
+    },
+    // the request never got an answer; context is None for a request
+    // that failed before it was sent
+    Transport {
+        context: Option<RequestContext>,
+        source: reqwest::Error,
+    },
+    // the answer couldn't be decoded
+    Decode {
+        context: Option<RequestContext>,
+        source: reqwest::Error,
+    },
+    // the API accepted a write but the stored record differs from the request
+    RecordMismatch {
+        domain: u64,
+        id: u64,
+        field: &'static str,
+        expected: String,
+        actual: String,
+    },
+    // user-data template failed to parse, render or validate
+    Template(String),
+    // user_data could not be read or is too large even when compressed
+    UserData(String),
+    // gave up waiting for an instance to reach a state
+    Timeout(String),
+    // fleet spec file could not be read or is invalid
+    Spec(String),
+    // a post-create provisioning step failed
+    Provision(String),
+    // a cassette could not be read or has no matching interaction
+    Cassette(String),
+    // a scale-up would take a tag's projected monthly spend over its cap
+    OverBudget {
+        tag: String,
+        projected: f64,
+        cap: f64,
+    },
+    // a zone transfer failed or was refused
+    Axfr(String),
+    // offline mode has no cached answer for a call, or the call would write
+    Offline(String),
+    // the operation journal could not be read or written
+    Journal(String),
+    // a NodeBalancer has nothing to change or didn't take the change
+    NodeBalancer(String),
+    // the client has sent as many API calls as with_max_api_calls allows
+    TooManyCalls {
+        limit: u64,
+    },
+    // a TokenSource couldn't produce a token
+    Token(String),
+    // another operator holds the advisory scaling lock
+    Locked {
+        name: String,
+        owner: String,
+        expires: u64,
+    },
+}
+
+impl fmt::Display for LinodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinodeError::Auth {
+                status,
+                context,
+                reasons,
+            }
+            | LinodeError::Api {
+                status,
+                context,
+                reasons,
+            } => {
+                write!(f, "{} returned {}", context, status)?;
+                write_reasons(f, reasons)
+            }
+            LinodeError::NotFound { context } => write!(f, "{} returned 404", context),
+            LinodeError::RateLimited {
+                context,
+                retry_after,
+            } => {
+                write!(f, "{} returned 429", context)?;
+                match retry_after {
+                    Some(wait) => write!(f, ", retry after {}s", wait.as_secs()),
+                    None => Ok(()),
+                }
+            }
+            LinodeError::Validation {
+                reason,
+                context,
+                reasons,
+                ..
+            } => {
+                if reasons.is_empty() {
+                    write!(f, "{}: {}", context, reason)
+                } else {
+                    write!(f, "{} was rejected", context)?;
+                    write_reasons(f, reasons)
+                }
+            }
+            LinodeError::Transport {
+                context: Some(context),
+                source,
+            }
+            | LinodeError::Decode {
+                context: Some(context),
+                source,
+            } => write!(f, "{}: {}", context, source),
+            LinodeError::Transport {
+                context: None,
+                source,
+            }
+            | LinodeError::Decode {
+                context: None,
+                source,
+            } => write!(f, "http error: {}", source),
+            LinodeError::RecordMismatch {
+                domain,
+                id,
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "record ID: {} in domain ID: {} has {} '{}', expected '{}'",
+                id, domain, field, actual, expected
+            ),
+            LinodeError::Template(e) => write!(f, "template error: {}", e),
+            LinodeError::UserData(e) => write!(f, "user_data error: {}", e),
+            LinodeError::Timeout(e) => write!(f, "timed out: {}", e),
+            LinodeError::Spec(e) => write!(f, "spec error: {}", e),
+            LinodeError::Provision(e) => write!(f, "provisioning failed: {}", e),
+            LinodeError::Cassette(e) => write!(f, "cassette error: {}", e),
+            LinodeError::OverBudget {
+                tag,
+                projected,
+                cap,
+            } => write!(
+                f,
+                "tag {} would cost ${:.2}/month, over its ${:.2} budget",
+                tag, projected, cap
+            ),
+            LinodeError::Axfr(e) => write!(f, "zone transfer failed: {}", e),
+            LinodeError::Offline(e) => write!(f, "offline: {}", e),
+            LinodeError::Journal(e) => write!(f, "journal error: {}", e),
+            LinodeError::NodeBalancer(e) => write!(f, "nodebalancer error: {}", e),
+            LinodeError::Token(e) => write!(f, "token error: {}", e),
+            LinodeError::TooManyCalls { limit } => {
+                write!(f, "reached the limit of {} API calls", limit)
+            }
+            LinodeError::Locked {
+                name,
+                owner,
+                expires,
+            } => write!(
+                f,
+                "lock {} is held by {} until {} (unix time)",
+                name, owner, expires
+            ),
+        }
+    }
+}
+
+// ": reason; reason" after an error status
+fn write_reasons(f: &mut fmt::Formatter<'_>, reasons: &[FieldError]) -> fmt::Result {
+    for (i, reason) in reasons.iter().enumerate() {
+        write!(f, "{}{}", if i == 0 { ": " } else { "; " }, reason)?;
+    }
+    Ok(())
+}
+
+impl LinodeError {
+    // Transport or Decode for a request that failed without an error
+    // status
+    pub(crate) fn request(context: Option<RequestContext>, source: reqwest::Error) -> Self {
+        if source.is_decode() {
+            LinodeError::Decode { context, source }
+        } else {
+            LinodeError::Transport { context, source }
+        }
+    }
+
+    // the error for a status the API answered with, and the reasons from
+    // its {"errors": [...]} payload
+    pub(crate) fn from_status(
+        status: u16,
+        context: RequestContext,
+        reasons: Vec<FieldError>,
+        retry_after: Option<Duration>,
+    ) -> Self {
+        match status {
+            401 | 403 => LinodeError::Auth {
+                status,
+                context,
+                reasons,
+            },
+            404 => LinodeError::NotFound { context },
+            429 => LinodeError::RateLimited {
+                context,
+                retry_after,
+            },
+            400 | 422 => {
+                let first = reasons.first();
+                LinodeError::Validation {
+                    field: first.and_then(|reason| reason.field.clone()),
+                    reason: first.map_or_else(
+                        || format!("rejected with {}", status),
+                        |reason| reason.reason.clone(),
+                    ),
+                    context,
+                    reasons,
+                }
+            }
+            _ => LinodeError::Api {
+                status,
+                context,
+                reasons,
+            },
+        }
+    }
+
+    // the field and reason pairs the API gave, empty for other errors
+    pub fn field_errors(&self) -> &[FieldError] {
+        match self {
+            LinodeError::Auth { reasons, .. }
+            | LinodeError::Validation { reasons, .. }
+            | LinodeError::Api { reasons, .. } => reasons,
+            _ => &[],
+        }
+    }
+}
+
+impl std::error::Error for LinodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LinodeError::Transport { source, .. } | LinodeError::Decode { source, .. } => {
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for LinodeError {
+    fn from(e: reqwest::Error) -> Self {
+        LinodeError::request(None, e)
+    }
+}
@@ -0,0 +1,280 @@
+use crate::journal::{JournalEntry, JournalStep};
+use crate::regions::RegionInfo;
+use crate::registry::{Registration, ServiceRegistry};
+use crate::{
+    DomainRecord, LinodeClient, LinodeError, LinodeInstance, ScaleUpOptions, ScaledNode, A_RECORD,
+    BOOT_TIMEOUT, LOCALHOST, STATUS_OFFLINE, STATUS_RUNNING,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use svix_ksuid::{Ksuid, KsuidLike};
+use tracing::{error, info};
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(300);
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Default)]
+pub struct EvacuateOptions {
+    pub scale_up: ScaleUpOptions,
+    // keep the images taken of each instance instead of deleting them
+    // once the copy is up
+    pub keep_images: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Evacuation {
+    pub slot: String,
+    pub image_id: String,
+    pub removed: ScaledNode,
+    pub created: ScaledNode,
+}
+
+// Registers the replacement of an evacuated or replaced instance into the
+// slot the original held, so it keeps its DNS name.
+pub(crate) struct SlotRegistry<'a> {
+    pub(crate) client: &'a LinodeClient,
+    pub(crate) domain: u64,
+    pub(crate) slot: DomainRecord,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ServiceRegistry for SlotRegistry<'_> {
+    async fn peek(&self, _prefix: &str) -> Result<Option<String>, LinodeError> {
+        let zone = self.client.fetch_domain(self.domain).await?;
+        Ok(Some(format!("{}.{}", self.slot.name, zone.domain)))
+    }
+
+    async fn register(
+        &self,
+        _prefix: &str,
+        instance: &LinodeInstance,
+    ) -> Result<String, LinodeError> {
+        self.client
+            .update_record_target(self.domain, self.slot.id, &instance.ipv4[0])
+            .await?;
+        Ok(self.slot.name.clone())
+    }
+
+    async fn deregister(
+        &self,
+        _prefix: &str,
+        instance: &LinodeInstance,
+    ) -> Result<Option<String>, LinodeError> {
+        if instance.ipv4.first() != Some(&self.slot.target) {
+            return Ok(None);
+        }
+        self.client
+            .update_record_target(self.domain, self.slot.id, LOCALHOST)
+            .await?;
+        Ok(Some(self.slot.name.clone()))
+    }
+
+    async fn list(&self, _prefix: &str) -> Result<Vec<Registration>, LinodeError> {
+        Ok(vec![Registration {
+            name: self.slot.name.clone(),
+            address: self.slot.target.clone(),
+        }])
+    }
+}
+
+impl LinodeClient {
+    // Move a tag's stateful instances from one region to another, one at
+    // a time: pull the instance from DNS, shut it down, image its main
+    // disk, copy the image to `to`, boot a replacement from it there in
+    // the same DNS slot, then destroy the original. Instances without a
+    // slot in domain aren't touched. If a step before the replacement is
+    // up fails, the original is booted and put back in its slot.
+    pub async fn evacuate_region(
+        &self,
+        domain: u64,
+        tag: &str,
+        from: &RegionInfo,
+        to: &RegionInfo,
+        options: &EvacuateOptions,
+    ) -> Result<Vec<Evacuation>, LinodeError> {
+        let operation_id = Ksuid::new(None, None).to_string();
+        let prefix = self.naming().prefix(tag, from.code);
+        let mut done = Vec::new();
+        self.journal_begin(JournalEntry {
+            domain: Some(domain),
+            ..JournalEntry::new(&operation_id, "evacuate", tag, from.code)
+        });
+
+        for instance in self.get_fleet_instances(tag, from).await? {
+            if self.is_shutting_down() {
+                break;
+            }
+            let records = self.fetch_records(domain).await?;
+            let Some(slot) = records
+                .into_iter()
+                .filter(|rec| {
+                    rec.record_type == A_RECORD && self.naming().is_slot(&prefix, &rec.name)
+                })
+                .find(|rec| instance.ipv4.first() == Some(&rec.target))
+            else {
+                info!("Skipping {}, it holds no slot", instance.label);
+                continue;
+            };
+            info!(
+                "Evacuating {} in slot: {} to region: {}",
+                instance.label, slot.name, to.code
+            );
+
+            // a stopped disk images consistently
+            self.update_record_target(domain, slot.id, LOCALHOST)
+                .await?;
+            self.journal_step(
+                &operation_id,
+                JournalStep::Deregistered {
+                    instance_id: instance.id,
+                    name: slot.name.clone(),
+                },
+            );
+            // from here the slot's only node is down and out of DNS, so any
+            // failure puts it back before returning
+            let moved = async {
+                self.shutdown_instance(instance.id).await?;
+                self.wait_for_status(instance.id, STATUS_OFFLINE, SHUTDOWN_TIMEOUT)
+                    .await?;
+
+                let disk = self
+                    .fetch_instance_disks(instance.id)
+                    .await?
+                    .into_iter()
+                    .filter(|disk| disk.filesystem != "swap")
+                    .max_by_key(|disk| disk.size)
+                    .ok_or_else(|| {
+                        LinodeError::Spec(format!("{} has no disk to image", instance.label))
+                    })?;
+                let image = self
+                    .create_image(
+                        disk.id,
+                        &format!("evacuate-{}", instance.label),
+                        Some(format!("{} evacuated to {}", slot.name, to.code)),
+                    )
+                    .await?;
+                self.journal_step(
+                    &operation_id,
+                    JournalStep::Imaged {
+                        instance_id: instance.id,
+                        image_id: image.id.clone(),
+                    },
+                );
+                let image = self.wait_for_image(&image.id, None, IMAGE_TIMEOUT).await?;
+                let mut regions = image
+                    .regions
+                    .iter()
+                    .map(|r| r.region.as_str())
+                    .collect::<Vec<_>>();
+                if !regions.contains(&to.region) {
+                    regions.push(to.region);
+                    self.replicate_image(&image.id, &regions).await?;
+                    self.wait_for_image(&image.id, Some(to.region), IMAGE_TIMEOUT)
+                        .await?;
+                }
+
+                let registry = SlotRegistry {
+                    client: self,
+                    domain,
+                    slot: slot.clone(),
+                };
+                let created = self
+                    .scale_up_one_in(
+                        &registry,
+                        &image.id,
+                        &instance.instance_type,
+                        to,
+                        tag,
+                        &options.scale_up,
+                    )
+                    .await?;
+                Ok::<_, LinodeError>((image, created))
+            }
+            .await;
+            let (image, created) = match moved {
+                Ok(moved) => moved,
+                Err(e) => {
+                    error!(
+                        "Evacuating {} failed, putting it back in slot: {}: {}",
+                        instance.label, slot.name, e
+                    );
+                    match self.restore_slot(domain, &instance, &slot).await {
+                        Ok(()) => {
+                            self.journal_step(
+                                &operation_id,
+                                JournalStep::Registered {
+                                    instance_id: instance.id,
+                                    name: slot.name.clone(),
+                                },
+                            );
+                            self.journal_finish(&operation_id);
+                        }
+                        // left in the journal for resume --rollback
+                        Err(restore) => error!(
+                            "Failed to put {} back in slot: {}: {}",
+                            instance.label, slot.name, restore
+                        ),
+                    }
+                    return Err(e);
+                }
+            };
+            self.journal_step(
+                &operation_id,
+                JournalStep::Replaced {
+                    instance_id: instance.id,
+                    replacement_id: created.id,
+                },
+            );
+
+            self.destroy_instance(instance.id).await?;
+            self.journal_step(
+                &operation_id,
+                JournalStep::Destroyed {
+                    instance_id: instance.id,
+                },
+            );
+            if !options.keep_images {
+                self.delete_image(&image.id).await?;
+            }
+            info!(
+                "Evacuated {} to {} in slot: {}",
+                instance.label, created.label, slot.name
+            );
+            done.push(Evacuation {
+                slot: slot.name.clone(),
+                image_id: image.id,
+                removed: ScaledNode::new(&instance, Some(slot.name), &operation_id),
+                created,
+            });
+        }
+        self.journal_finish(&operation_id);
+        Ok(done)
+    }
+
+    // boot an evacuating instance again and point its slot back at it
+    async fn restore_slot(
+        &self,
+        domain: u64,
+        instance: &LinodeInstance,
+        slot: &DomainRecord,
+    ) -> Result<(), LinodeError> {
+        let status = self.fetch_instance(instance.id).await?.status;
+        if status != STATUS_RUNNING {
+            if status != STATUS_OFFLINE {
+                self.wait_for_status(instance.id, STATUS_OFFLINE, SHUTDOWN_TIMEOUT)
+                    .await?;
+            }
+            self.boot_instance(instance.id, None).await?;
+            self.wait_for_status(instance.id, STATUS_RUNNING, BOOT_TIMEOUT)
+                .await?;
+        }
+        let ip = instance.ipv4.first().ok_or_else(|| {
+            LinodeError::Spec(format!("instance ID: {} has no IPv4", instance.id))
+        })?;
+        self.update_record_target(domain, slot.id, ip).await?;
+        info!("Put {} back in slot: {}", instance.label, slot.name);
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::reconcile::ReconcileReport;
+use crate::{LinodeClient, LinodeError, ListOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tracing::{error, info};
+
+// event actions that can leave a fleet's DNS out of date
+pub const RECONCILE_ACTIONS: &[&str] = &[
+    "linode_create",
+    "linode_delete",
+    "linode_clone",
+    "linode_migrate",
+    "linode_migrate_datacenter",
+    "linode_rebuild",
+    "linode_shutdown",
+    "linode_boot",
+    "linode_reboot",
+    "host_reboot",
+    "lassie_reboot",
+    "ipaddress_update",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEntity {
+    pub id: Option<u64>,
+    pub label: Option<String>,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountEvent {
+    pub id: u64,
+    pub action: String,
+    pub created: String,
+    pub status: String,
+    pub entity: Option<EventEntity>,
+}
+
+impl LinodeClient {
+    // account events newer than after, oldest first
+    pub async fn fetch_events_since(&self, after: u64) -> Result<Vec<AccountEvent>, LinodeError> {
+        let options = ListOptions {
+            filter: Some(json!({ "id": { "+gt": after } })),
+            order_by: Some("id".to_owned()),
+            ..Default::default()
+        };
+        let mut events = self
+            .fetch_all::<AccountEvent>("account/events", &options)
+            .await?;
+        events.sort_by_key(|event| event.id);
+        Ok(events)
+    }
+
+    // Which of tags an event may have changed. Deleted instances can't be
+    // looked up any more, so a delete affects all of them.
+    async fn tags_affected_by(
+        &self,
+        event: &AccountEvent,
+        tags: &[String],
+    ) -> Result<BTreeSet<String>, LinodeError> {
+        let Some(entity) = event
+            .entity
+            .as_ref()
+            .filter(|entity| entity.entity_type == "linode")
+        else {
+            return Ok(BTreeSet::new());
+        };
+        if event.action == "linode_delete" {
+            return Ok(tags.iter().cloned().collect());
+        }
+        let Some(id) = entity.id else {
+            return Ok(BTreeSet::new());
+        };
+        match self.fetch_instance(id).await {
+            Ok(instance) => Ok(tags
+                .iter()
+                .filter(|tag| instance.tags.contains(tag))
+                .cloned()
+                .collect()),
+            // gone again by the time we looked
+            Err(LinodeError::NotFound { .. }) => Ok(tags.iter().cloned().collect()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Tail the account event stream and reconcile the DNS of every watched
+    // tag an event touches, calling on_reconcile with each result. Each
+    // reconcile holds the fleet's scaling locks for lock_ttl, or runs
+    // unlocked without one. Starts from the newest event at startup and
+    // runs until an API call fails or a shutdown is requested.
+    pub async fn watch_events<F>(
+        &self,
+        domain: u64,
+        tags: &[String],
+        interval: Duration,
+        lock_ttl: Option<Duration>,
+        mut on_reconcile: F,
+    ) -> Result<(), LinodeError>
+    where
+        F: FnMut(&AccountEvent, &ReconcileReport),
+    {
+        let mut last = self
+            .fetch_page::<AccountEvent>(
+                "account/events",
+                &ListOptions {
+                    page_size: Some(25),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .data
+            .iter()
+            .map(|event| event.id)
+            .max()
+            .unwrap_or(0);
+        info!(
+            "Watching account events after ID: {} for tags: {:?}",
+            last, tags
+        );
+
+        loop {
+            for event in self.fetch_events_since(last).await? {
+                last = last.max(event.id);
+                if !RECONCILE_ACTIONS.contains(&event.action.as_str()) {
+                    continue;
+                }
+                info!("Event ID: {} {} ({})", event.id, event.action, event.status);
+                for tag in self.tags_affected_by(&event, tags).await? {
+                    let reconcile = self.reconcile_dns(domain, &tag);
+                    match self
+                        .with_fleet_locks(domain, &tag, lock_ttl, reconcile)
+                        .await
+                    {
+                        Ok(report) => on_reconcile(&event, &report),
+                        Err(e) => error!("Failed to reconcile tag: {}: {}", tag, e),
+                    }
+                }
+            }
+            // in short steps, so a shutdown doesn't wait out the interval
+            let mut waited = Duration::ZERO;
+            while waited < interval && !self.is_shutting_down() {
+                let step = (interval - waited).min(Duration::from_secs(1));
+                self.pause(step).await;
+                waited += step;
+            }
+            if self.is_shutting_down() {
+                info!("Stopped watching account events after ID: {}", last);
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::fleet::HealthCheck;
+use crate::regions::RegionInfo;
+use crate::{LinodeClient, LinodeError, ScaleUpOptions, ScaledNode, A_RECORD};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use svix_ksuid::{Ksuid, KsuidLike};
+use tracing::info;
+
+#[derive(Debug, Default)]
+pub struct FailoverOptions {
+    // default to each origin instance's own image and type
+    pub image_id: Option<String>,
+    pub instance_type: Option<String>,
+    pub scale_up: ScaleUpOptions,
+    // wait until every replacement accepts TCP connections on this port
+    pub health: Option<HealthCheck>,
+    // how long to wait for the replacements to pass, 10 minutes if unset
+    pub health_wait: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FailoverReport {
+    pub promoted: Vec<ScaledNode>,
+    // origin DNS name and the address it now points at
+    pub repointed: Vec<(String, String)>,
+    pub removed: Vec<ScaledNode>,
+}
+
+impl LinodeClient {
+    // Move a tag's traffic from one region to another: scale up one
+    // replacement in `to` per registered instance in `from`, wait for them
+    // to be healthy, point the origin's DNS names at the replacements, then
+    // destroy the origin instances. Nothing in `from` is touched until the
+    // replacements pass, so a failure part way leaves the origin serving.
+    // A shutdown stops between instances: while promoting, before anything
+    // in `from` is touched; while repointing, after an instance's names
+    // have all moved.
+    pub async fn failover_promote(
+        &self,
+        domain: u64,
+        tag: &str,
+        from: &RegionInfo,
+        to: &RegionInfo,
+        options: &FailoverOptions,
+    ) -> Result<FailoverReport, LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        let prefix = self.naming().prefix(tag, from.code);
+        let mut origin = Vec::new();
+        for instance in self.get_fleet_instances(tag, from).await? {
+            let slots = records
+                .iter()
+                .filter(|rec| {
+                    rec.record_type == A_RECORD && self.naming().is_slot(&prefix, &rec.name)
+                })
+                .filter(|rec| instance.ipv4.first() == Some(&rec.target))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !slots.is_empty() {
+                origin.push((instance, slots));
+            }
+        }
+        info!(
+            "Failing over {} instances with tag: {} from region: {} to region: {}",
+            origin.len(),
+            tag,
+            from.code,
+            to.code
+        );
+
+        let mut report = FailoverReport::default();
+        for (instance, _) in &origin {
+            if self.is_shutting_down() {
+                break;
+            }
+            let image = options
+                .image_id
+                .as_deref()
+                .or(instance.image.as_deref())
+                .ok_or_else(|| {
+                    LinodeError::Spec(format!("no image for replacing {}", instance.label))
+                })?;
+            let instance_type = options
+                .instance_type
+                .as_deref()
+                .unwrap_or(&instance.instance_type);
+            report.promoted.push(
+                self.scale_up_one_with(image, instance_type, domain, to, tag, &options.scale_up)
+                    .await?,
+            );
+        }
+
+        if self.is_shutting_down() {
+            info!(
+                "Stopped failover of tag: {} after promoting {} instances, the origin still serves",
+                tag,
+                report.promoted.len()
+            );
+            return Ok(report);
+        }
+
+        if let Some(check) = options.health {
+            let wait = options.health_wait.unwrap_or(Duration::from_secs(600));
+            self.wait_for_healthy(&report.promoted, check, wait).await?;
+        }
+
+        let operation_id = Ksuid::new(None, None).to_string();
+        for ((instance, slots), replacement) in origin.iter().zip(&report.promoted) {
+            if self.is_shutting_down() {
+                break;
+            }
+            let Some(address) = &replacement.ipv4 else {
+                continue;
+            };
+            for slot in slots {
+                self.remove_instance_record(domain, &slot.name).await?;
+                self.update_record_target(domain, slot.id, address).await?;
+                report.repointed.push((slot.name.clone(), address.clone()));
+            }
+            self.destroy_instance(instance.id).await?;
+            report.removed.push(ScaledNode::new(
+                instance,
+                slots.first().map(|slot| slot.name.clone()),
+                &operation_id,
+            ));
+        }
+        info!(
+            "Failed over tag: {} to region: {}, repointed {} names",
+            tag,
+            to.code,
+            report.repointed.len()
+        );
+        Ok(report)
+    }
+}
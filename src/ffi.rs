@@ -0,0 +1,218 @@
+// C interface for driving fleets from other languages. Build the shared
+// library with:
+//
+//   cargo rustc --release --lib --features ffi --crate-type cdylib
+//
+// Strings in and out are NUL-terminated UTF-8. Strings returned by the
+// library must be released with linode_string_free. On failure functions
+// return NULL or -1 and linode_last_error describes what went wrong.
+use crate::regions::find_region;
+use crate::LinodeClient;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+use tokio::runtime::Runtime;
+
+pub struct LinodeHandle {
+    runtime: Runtime,
+    client: LinodeClient,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+unsafe fn read_str<'a>(s: *const c_char, name: &str) -> Option<&'a str> {
+    if s.is_null() {
+        set_last_error(format!("{} is null", name));
+        return None;
+    }
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Some(s),
+        Err(e) => {
+            set_last_error(format!("{} is not UTF-8: {}", name, e));
+            None
+        }
+    }
+}
+
+/// Message for the last failed call on this thread, or NULL. Owned by the
+/// library and valid until the next failing call.
+#[no_mangle]
+pub extern "C" fn linode_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// # Safety
+///
+/// `token` and `pub_key` must be NULL or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn linode_client_new(
+    token: *const c_char,
+    pub_key: *const c_char,
+) -> *mut LinodeHandle {
+    let (Some(token), Some(pub_key)) = (read_str(token, "token"), read_str(pub_key, "pub_key"))
+    else {
+        return ptr::null_mut();
+    };
+    let runtime = match Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            set_last_error(format!("failed to start runtime: {}", e));
+            return ptr::null_mut();
+        }
+    };
+    match LinodeClient::new(token.to_owned(), pub_key.to_owned()) {
+        Ok(client) => Box::into_raw(Box::new(LinodeHandle {
+            runtime,
+            client: client.with_record_cache(true),
+        })),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be NULL or come from linode_client_new and not have been
+/// freed already.
+#[no_mangle]
+pub unsafe extern "C" fn linode_client_free(handle: *mut LinodeHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn linode_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// All instances as a JSON array, or NULL on error.
+///
+/// # Safety
+///
+/// `handle` must be a live handle from linode_client_new.
+#[no_mangle]
+pub unsafe extern "C" fn linode_fetch_instances_json(handle: *const LinodeHandle) -> *mut c_char {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is null".to_owned());
+        return ptr::null_mut();
+    };
+    let json = handle
+        .runtime
+        .block_on(handle.client.fetch_instances())
+        .map_err(|e| e.to_string())
+        .and_then(|instances| serde_json::to_string(&instances).map_err(|e| e.to_string()))
+        .and_then(|json| CString::new(json).map_err(|e| e.to_string()));
+    match json {
+        Ok(json) => json.into_raw(),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Add one instance to the tag's fleet in region. Returns 0 on success.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and the strings valid NUL-terminated
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn linode_scale_up(
+    handle: *const LinodeHandle,
+    image_id: *const c_char,
+    instance_type: *const c_char,
+    domain_id: u64,
+    region: *const c_char,
+    tag: *const c_char,
+) -> c_int {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is null".to_owned());
+        return -1;
+    };
+    let (Some(image_id), Some(instance_type), Some(region), Some(tag)) = (
+        read_str(image_id, "image_id"),
+        read_str(instance_type, "instance_type"),
+        read_str(region, "region"),
+        read_str(tag, "tag"),
+    ) else {
+        return -1;
+    };
+    let Some(region) = find_region(region) else {
+        set_last_error(format!("unknown region: {}", region));
+        return -1;
+    };
+
+    let result = handle.runtime.block_on(handle.client.scale_up_one(
+        image_id,
+        instance_type,
+        domain_id,
+        region,
+        tag,
+    ));
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            set_last_error(e.to_string());
+            -1
+        }
+    }
+}
+
+/// Remove up to n instances from the tag's fleet in region. Returns the
+/// number removed, or -1 on error.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and the strings valid NUL-terminated
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn linode_scale_down(
+    handle: *const LinodeHandle,
+    domain_id: u64,
+    region: *const c_char,
+    tag: *const c_char,
+    n: u32,
+) -> c_int {
+    let Some(handle) = handle.as_ref() else {
+        set_last_error("handle is null".to_owned());
+        return -1;
+    };
+    let (Some(region), Some(tag)) = (read_str(region, "region"), read_str(tag, "tag")) else {
+        return -1;
+    };
+    let Some(region) = find_region(region) else {
+        set_last_error(format!("unknown region: {}", region));
+        return -1;
+    };
+
+    let result = handle
+        .runtime
+        .block_on(handle.client.scale_down(domain_id, region, tag, n as usize));
+    match result {
+        Ok(removed) => removed as c_int,
+        Err(e) => {
+            set_last_error(e.to_string());
+            -1
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{LinodeClient, LinodeError, ListOptions};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FirewallAddresses {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv4: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirewallRule {
+    // ACCEPT or DROP
+    pub action: String,
+    // TCP, UDP, ICMP or IPENCAP
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports: Option<String>,
+    pub addresses: FirewallAddresses,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirewallRules {
+    pub inbound: Vec<FirewallRule>,
+    pub inbound_policy: String,
+    pub outbound: Vec<FirewallRule>,
+    pub outbound_policy: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Firewall {
+    pub id: u64,
+    pub label: String,
+    pub status: String,
+    pub rules: FirewallRules,
+    pub tags: Vec<String>,
+    pub created: String,
+    pub updated: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirewallDeviceEntity {
+    pub id: u64,
+    pub label: Option<String>,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirewallDevice {
+    pub id: u64,
+    pub entity: FirewallDeviceEntity,
+    pub created: String,
+    pub updated: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FirewallCreateOptions {
+    label: String,
+    rules: FirewallRules,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FirewallDeviceOptions {
+    id: u64,
+    #[serde(rename = "type")]
+    device_type: String,
+}
+
+impl LinodeClient {
+    pub async fn fetch_firewalls(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Vec<Firewall>, LinodeError> {
+        info!("Fetching firewalls");
+        let firewalls = self
+            .fetch_all::<Firewall>("networking/firewalls", options)
+            .await?;
+        info!("Fetched {} firewalls", firewalls.len());
+
+        Ok(firewalls)
+    }
+
+    pub async fn create_firewall(
+        &self,
+        label: &str,
+        rules: FirewallRules,
+        tags: Vec<String>,
+    ) -> Result<Firewall, LinodeError> {
+        info!("Creating firewall with label: {}", label);
+        let options = FirewallCreateOptions {
+            label: label.to_owned(),
+            rules,
+            tags,
+        };
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/networking/firewalls", self.base_url))
+                    .bearer_auth(self.token.expose())
+                    .json(&options),
+            )
+            .await?;
+
+        let firewall = response.json::<Firewall>().await?;
+        info!("Created firewall ID: {} with label: {}", firewall.id, label);
+        Ok(firewall)
+    }
+
+    pub async fn fetch_firewall_rules(&self, id: u64) -> Result<FirewallRules, LinodeError> {
+        info!("Fetching rules for firewall ID: {}", id);
+        let response = self
+            .send(
+                self.client
+                    .get(format!(
+                        "{}/networking/firewalls/{}/rules",
+                        self.base_url, id
+                    ))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+
+        response.json::<FirewallRules>().await
+    }
+
+    // replaces the whole rule set
+    pub async fn set_firewall_rules(
+        &self,
+        id: u64,
+        rules: &FirewallRules,
+    ) -> Result<FirewallRules, LinodeError> {
+        info!(
+            "Setting {} inbound and {} outbound rules on firewall ID: {}",
+            rules.inbound.len(),
+            rules.outbound.len(),
+            id
+        );
+        let response = self
+            .send(
+                self.client
+                    .put(format!(
+                        "{}/networking/firewalls/{}/rules",
+                        self.base_url, id
+                    ))
+                    .bearer_auth(self.token.expose())
+                    .json(rules),
+            )
+            .await?;
+
+        info!("Rules set successfully on firewall ID: {}", id);
+        response.json::<FirewallRules>().await
+    }
+
+    pub async fn fetch_firewall_devices(
+        &self,
+        id: u64,
+    ) -> Result<Vec<FirewallDevice>, LinodeError> {
+        info!("Fetching devices for firewall ID: {}", id);
+        let devices = self
+            .fetch_all::<FirewallDevice>(
+                &format!("networking/firewalls/{}/devices", id),
+                &ListOptions::default(),
+            )
+            .await?;
+        info!("Fetched {} devices for firewall ID: {}", devices.len(), id);
+        Ok(devices)
+    }
+
+    pub async fn attach_firewall(
+        &self,
+        id: u64,
+        linode_id: u64,
+    ) -> Result<FirewallDevice, LinodeError> {
+        info!(
+            "Attaching firewall ID: {} to instance ID: {}",
+            id, linode_id
+        );
+        let response = self
+            .send(
+                self.client
+                    .post(format!(
+                        "{}/networking/firewalls/{}/devices",
+                        self.base_url, id
+                    ))
+                    .bearer_auth(self.token.expose())
+                    .json(&FirewallDeviceOptions {
+                        id: linode_id,
+                        device_type: "linode".to_owned(),
+                    }),
+            )
+            .await?;
+
+        info!(
+            "Firewall ID: {} attached successfully to instance ID: {}",
+            id, linode_id
+        );
+        response.json::<FirewallDevice>().await
+    }
+}
@@ -0,0 +1,163 @@
+use crate::health::{check_tcp, HealthStatus};
+use crate::{LinodeClient, LinodeError, A_RECORD};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::info;
+
+// One instance of a tagged fleet joined with its DNS names and VLAN IP.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetMember {
+    pub instance_id: u64,
+    pub label: String,
+    pub region: String,
+    pub status: String,
+    pub image: Option<String>,
+    pub public_ip: Option<String>,
+    pub vlan_ip: Option<String>,
+    pub dns_names: Vec<String>,
+    pub health: Option<HealthStatus>,
+}
+
+// Disk allocated on one instance against its plan, all in MB. An image
+// upgrade builds a new disk beside the current one, so fits_upgrade says
+// whether the free space can hold the image; None when its size is unknown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub instance_id: u64,
+    pub label: String,
+    pub region: String,
+    pub instance_type: String,
+    pub plan: u64,
+    pub allocated: u64,
+    pub free: u64,
+    pub disks: usize,
+    pub image: Option<String>,
+    pub image_size: Option<u64>,
+    pub fits_upgrade: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HealthCheck {
+    pub port: u16,
+    pub timeout: Duration,
+}
+
+impl LinodeClient {
+    // the VLAN address of the interface labelled with the fleet tag
+    pub async fn get_vlan_ip(&self, id: u64, tag: &str) -> Result<Option<String>, LinodeError> {
+        let configs = self.get_instance_configurations(id).await?;
+        Ok(configs
+            .iter()
+            .flat_map(|config| &config.interfaces)
+            .filter(|interface| interface.label.as_deref() == Some(tag))
+            .find_map(|interface| interface.ipam_address.clone()))
+    }
+
+    pub async fn fleet_status(
+        &self,
+        tag: &str,
+        domain: u64,
+        health: Option<HealthCheck>,
+    ) -> Result<Vec<FleetMember>, LinodeError> {
+        info!("Building fleet status for tag: {}", tag);
+        let instances = self.get_instances_by_tag(vec![tag]).await?;
+        let zone = self.fetch_domain(domain).await?;
+        let records = self.fetch_records(domain).await?;
+
+        let mut members = Vec::new();
+        for instance in instances {
+            let public_ip = instance.ipv4.first().cloned();
+            let dns_names = records
+                .iter()
+                .filter(|rec| rec.record_type == A_RECORD)
+                .filter(|rec| Some(&rec.target) == public_ip.as_ref())
+                .map(|rec| format!("{}.{}", rec.name, zone.domain))
+                .collect();
+            let vlan_ip = self.get_vlan_ip(instance.id, tag).await?;
+            let health = match (health, &public_ip) {
+                (Some(check), Some(ip)) => Some(check_tcp(ip, check.port, check.timeout).await),
+                _ => None,
+            };
+
+            members.push(FleetMember {
+                instance_id: instance.id,
+                label: instance.label,
+                region: instance.region,
+                status: instance.status,
+                image: instance.image,
+                public_ip,
+                vlan_ip,
+                dns_names,
+                health,
+            });
+        }
+
+        info!("Fleet for tag: {} has {} members", tag, members.len());
+        Ok(members)
+    }
+
+    // Allocated vs plan disk for every instance of tag. Each is checked
+    // against image, or the image it was deployed from when None.
+    pub async fn fleet_disks(
+        &self,
+        tag: &str,
+        image: Option<&str>,
+    ) -> Result<Vec<DiskUsage>, LinodeError> {
+        info!("Building disk usage for tag: {}", tag);
+        let instances = self.get_instances_by_tag(vec![tag]).await?;
+
+        let mut image_sizes: HashMap<String, Option<u64>> = HashMap::new();
+        let mut usage = Vec::new();
+        for instance in instances {
+            let disks = self.fetch_instance_disks(instance.id).await?;
+            let allocated = disks.iter().map(|disk| disk.size).sum::<u64>();
+            let plan = u64::from(instance.specs.disk);
+            let free = plan.saturating_sub(allocated);
+
+            let image = image.map(str::to_owned).or(instance.image);
+            let image_size = match &image {
+                Some(id) => match image_sizes.get(id) {
+                    Some(size) => *size,
+                    None => {
+                        // deleted private images can't be sized any more
+                        let size = match self.fetch_image(id).await {
+                            Ok(image) => Some(image.size),
+                            Err(LinodeError::NotFound { .. }) => None,
+                            Err(e) => return Err(e),
+                        };
+                        image_sizes.insert(id.clone(), size);
+                        size
+                    }
+                },
+                None => None,
+            };
+            let fits_upgrade = image_size.map(|size| size <= free);
+            if fits_upgrade == Some(false) {
+                info!(
+                    "Instance {} has {}MB free, too little for {} ({}MB)",
+                    instance.label,
+                    free,
+                    image.as_deref().unwrap_or("-"),
+                    image_size.unwrap_or_default()
+                );
+            }
+
+            usage.push(DiskUsage {
+                instance_id: instance.id,
+                label: instance.label,
+                region: instance.region,
+                instance_type: instance.instance_type,
+                plan,
+                allocated,
+                free,
+                disks: disks.len(),
+                image,
+                image_size,
+                fits_upgrade,
+            });
+        }
+
+        Ok(usage)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::fleet::HealthCheck;
+use crate::{LinodeClient, LinodeError, ScaledNode, STATUS_POLL_INTERVAL};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(feature = "native")]
+use tokio::net::TcpStream;
+use tracing::{error, info};
+use web_time::Instant;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthStatus {
+    pub healthy: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+// healthy if a TCP connection to addr:port opens within timeout
+#[cfg(feature = "native")]
+pub async fn check_tcp(addr: &str, port: u16, timeout: Duration) -> HealthStatus {
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, TcpStream::connect((addr, port))).await;
+    let status = match result {
+        Ok(Ok(_)) => HealthStatus {
+            healthy: true,
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            error: None,
+        },
+        Ok(Err(e)) => HealthStatus {
+            healthy: false,
+            latency_ms: None,
+            error: Some(e.to_string()),
+        },
+        Err(_) => HealthStatus {
+            healthy: false,
+            latency_ms: None,
+            error: Some(format!("no connection after {:?}", timeout)),
+        },
+    };
+    info!("Health check {}:{} healthy: {}", addr, port, status.healthy);
+    status
+}
+
+// browsers can't open raw sockets
+#[cfg(not(feature = "native"))]
+pub async fn check_tcp(addr: &str, port: u16, _timeout: Duration) -> HealthStatus {
+    info!(
+        "Skipping health check {}:{} without native sockets",
+        addr, port
+    );
+    HealthStatus {
+        healthy: false,
+        latency_ms: None,
+        error: Some("TCP health checks need the native feature".to_owned()),
+    }
+}
+
+impl LinodeClient {
+    // poll each node's public address until check passes, failing once
+    // wait has passed
+    pub(crate) async fn wait_for_healthy(
+        &self,
+        nodes: &[ScaledNode],
+        check: HealthCheck,
+        wait: Duration,
+    ) -> Result<(), LinodeError> {
+        let start = Instant::now();
+        for node in nodes {
+            let Some(ip) = &node.ipv4 else {
+                continue;
+            };
+            loop {
+                let status = check_tcp(ip, check.port, check.timeout).await;
+                if status.healthy {
+                    break;
+                }
+                if start.elapsed() >= wait {
+                    error!("{} never became healthy", node.label);
+                    return Err(LinodeError::Timeout(format!(
+                        "{} not healthy on port {} after {:?}",
+                        node.label, check.port, wait
+                    )));
+                }
+                self.pause(STATUS_POLL_INTERVAL).await;
+            }
+        }
+        Ok(())
+    }
+}
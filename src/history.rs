@@ -0,0 +1,357 @@
+use crate::pagination::Page;
+use crate::reap::parse_age;
+use crate::state::now;
+use crate::zone_health::unix_seconds;
+use crate::{Domain, LinodeClient, LinodeError, ListOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::info;
+
+// An instance as the inventory history keeps it; status is left out so
+// boots and shutdowns aren't reported as changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventoryInstance {
+    pub id: u64,
+    pub label: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(rename = "type", default)]
+    pub instance_type: String,
+    pub image: Option<String>,
+    #[serde(default)]
+    pub ipv4: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventoryRecord {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub name: String,
+    pub target: String,
+    #[serde(default)]
+    pub ttl_sec: i32,
+}
+
+// Every instance and domain record the state cache has seen listed,
+// records by domain ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inventory {
+    #[serde(default)]
+    pub instances: BTreeMap<u64, InventoryInstance>,
+    #[serde(default)]
+    pub records: BTreeMap<u64, BTreeMap<u64, InventoryRecord>>,
+}
+
+// The inventory as of at (unix time), one per line of the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub at: u64,
+    pub inventory: Inventory,
+}
+
+// One page of an unfiltered listing. Once every page of a listing is in,
+// it replaces what the inventory had for that collection.
+#[derive(Debug)]
+pub(crate) enum InventoryUpdate {
+    Instances(Vec<InventoryInstance>),
+    Records {
+        domain: u64,
+        data: Vec<InventoryRecord>,
+    },
+}
+
+impl InventoryUpdate {
+    // the page, its number and the listing's page count; None for
+    // anything but instance and domain record listings
+    pub(crate) fn parse(path: &str, body: &str) -> Option<(Self, u32, u32)> {
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        match segments.as_slice() {
+            [.., "linode", "instances"] => {
+                let page = serde_json::from_str::<Page<InventoryInstance>>(body).ok()?;
+                Some((InventoryUpdate::Instances(page.data), page.page, page.pages))
+            }
+            [.., "domains", domain, "records"] => {
+                let page = serde_json::from_str::<Page<InventoryRecord>>(body).ok()?;
+                let update = InventoryUpdate::Records {
+                    domain: domain.parse().ok()?,
+                    data: page.data,
+                };
+                Some((update, page.page, page.pages))
+            }
+            _ => None,
+        }
+    }
+
+    // add the next page of the same listing
+    pub(crate) fn extend(&mut self, next: InventoryUpdate) {
+        match (self, next) {
+            (InventoryUpdate::Instances(data), InventoryUpdate::Instances(more)) => {
+                data.extend(more)
+            }
+            (
+                InventoryUpdate::Records { data, .. },
+                InventoryUpdate::Records { data: more, .. },
+            ) => data.extend(more),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn apply(self, inventory: &mut Inventory) {
+        match self {
+            InventoryUpdate::Instances(data) => {
+                inventory.instances = data
+                    .into_iter()
+                    .map(|instance| (instance.id, instance))
+                    .collect();
+            }
+            InventoryUpdate::Records { domain, data } => {
+                inventory
+                    .records
+                    .insert(domain, data.into_iter().map(|rec| (rec.id, rec)).collect());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+// An instance or record that appeared, went away or changed; domain is
+// set for records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryChange {
+    pub kind: ChangeKind,
+    pub object: String,
+    pub id: u64,
+    pub name: String,
+    pub domain: Option<u64>,
+    pub fields: Vec<FieldChange>,
+}
+
+// The changes between the snapshots taken at from and to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryDiff {
+    pub from: u64,
+    pub to: u64,
+    pub changes: Vec<InventoryChange>,
+}
+
+fn instance_fields(instance: &InventoryInstance) -> Vec<(&'static str, String)> {
+    vec![
+        ("label", instance.label.clone()),
+        ("region", instance.region.clone()),
+        ("type", instance.instance_type.clone()),
+        ("image", instance.image.clone().unwrap_or_default()),
+        ("ipv4", instance.ipv4.join(",")),
+        ("tags", instance.tags.join(",")),
+    ]
+}
+
+fn record_fields(record: &InventoryRecord) -> Vec<(&'static str, String)> {
+    vec![
+        ("name", record.name.clone()),
+        ("type", record.record_type.clone()),
+        ("target", record.target.clone()),
+        ("ttl", record.ttl_sec.to_string()),
+    ]
+}
+
+fn changed_fields(
+    before: Vec<(&'static str, String)>,
+    after: Vec<(&'static str, String)>,
+) -> Vec<FieldChange> {
+    before
+        .into_iter()
+        .zip(after)
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((field, before), (_, after))| FieldChange {
+            field: field.to_owned(),
+            before,
+            after,
+        })
+        .collect()
+}
+
+// added, removed and changed entries between two maps of the same kind
+fn diff_maps<T>(
+    before: &BTreeMap<u64, T>,
+    after: &BTreeMap<u64, T>,
+    change: impl Fn(ChangeKind, &T, Vec<FieldChange>) -> InventoryChange,
+    fields: impl Fn(&T) -> Vec<(&'static str, String)>,
+) -> Vec<InventoryChange> {
+    let mut changes = Vec::new();
+    for (id, old) in before {
+        match after.get(id) {
+            None => changes.push(change(ChangeKind::Removed, old, Vec::new())),
+            Some(new) => {
+                let fields = changed_fields(fields(old), fields(new));
+                if !fields.is_empty() {
+                    changes.push(change(ChangeKind::Changed, new, fields));
+                }
+            }
+        }
+    }
+    for (id, new) in after {
+        if !before.contains_key(id) {
+            changes.push(change(ChangeKind::Added, new, Vec::new()));
+        }
+    }
+    changes
+}
+
+impl Inventory {
+    // what changed from self to later, instances first; records only in
+    // domains both have listed
+    pub fn diff(&self, later: &Inventory) -> Vec<InventoryChange> {
+        let mut changes = diff_maps(
+            &self.instances,
+            &later.instances,
+            |kind, instance, fields| InventoryChange {
+                kind,
+                object: "instance".to_owned(),
+                id: instance.id,
+                name: instance.label.clone(),
+                domain: None,
+                fields,
+            },
+            instance_fields,
+        );
+        // a zone only one side has seen listed can't be compared
+        for (domain, before) in &self.records {
+            let Some(after) = later.records.get(domain) else {
+                continue;
+            };
+            changes.extend(diff_maps(
+                before,
+                after,
+                |kind, record, fields| InventoryChange {
+                    kind,
+                    object: "record".to_owned(),
+                    id: record.id,
+                    name: record.name.clone(),
+                    domain: Some(*domain),
+                    fields,
+                },
+                record_fields,
+            ));
+        }
+        changes
+    }
+}
+
+// Compare the snapshot in force at since (or the first one, if history
+// starts later) with the one in force at until, or the latest. None
+// without any snapshots.
+pub fn diff_history(
+    snapshots: &[Snapshot],
+    since: u64,
+    until: Option<u64>,
+) -> Option<InventoryDiff> {
+    let in_force = |at: u64| snapshots.iter().rev().find(|snapshot| snapshot.at <= at);
+    let from = in_force(since).or_else(|| snapshots.first())?;
+    let to = match until {
+        Some(until) => in_force(until).unwrap_or(from),
+        None => snapshots.last()?,
+    };
+    Some(InventoryDiff {
+        from: from.at,
+        to: to.at,
+        changes: from.inventory.diff(&to.inventory),
+    })
+}
+
+// A point in time as a UTC date ("2024-06-01"), date and time
+// ("2024-06-01T12:00:00") or an age before now ("7d"), in unix time.
+pub fn parse_point(point: &str) -> Result<u64, LinodeError> {
+    let point = point.trim();
+    let timestamp = if point.contains('T') {
+        point.to_owned()
+    } else {
+        format!("{}T00:00:00", point)
+    };
+    if let Some(at) = unix_seconds(&timestamp) {
+        return Ok(at);
+    }
+    let age = parse_age(point)
+        .map_err(|_| LinodeError::Spec(format!("invalid date or age: {}", point)))?;
+    Ok(now().saturating_sub(age.as_secs()))
+}
+
+// unix time as "2024-06-01T12:00:00", UTC
+pub fn format_time(at: u64) -> String {
+    let days = (at / 86_400) as i64;
+    let seconds = at % 86_400;
+    // civil from days, the inverse of unix_seconds
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+impl LinodeClient {
+    // List every instance and every domain's records, straight from the
+    // API, and return the inventory they make up.
+    pub async fn fetch_inventory(&self) -> Result<Inventory, LinodeError> {
+        let mut inventory = Inventory::default();
+        for instance in self
+            .fetch_all::<InventoryInstance>("linode/instances", &ListOptions::default())
+            .await?
+        {
+            inventory.instances.insert(instance.id, instance);
+        }
+        for domain in self
+            .fetch_all::<Domain>("domains", &ListOptions::default())
+            .await?
+        {
+            let records = self
+                .fetch_all::<InventoryRecord>(
+                    &format!("domains/{}/records", domain.id),
+                    &ListOptions::default(),
+                )
+                .await?;
+            inventory.records.insert(
+                domain.id,
+                records.into_iter().map(|rec| (rec.id, rec)).collect(),
+            );
+        }
+        info!(
+            "Inventory has {} instances and {} domains",
+            inventory.instances.len(),
+            inventory.records.len()
+        );
+        Ok(inventory)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{DomainRecord, DomainRecordOptions, LinodeClient, LinodeError, LinodeInstance};
+use serde_json::json;
+use std::future::Future;
+use tracing::{error, info};
+
+// Creates are not retried on timeouts or 5xx by default because the API
+// may have acted before failing, and a blind retry makes a duplicate.
+// Instead every create carries a key naming what it makes: the instance
+// label, which scale-up generates fresh per node and the API keeps
+// unique, or a record's type, name and target. After a failure that
+// leaves the outcome unknown the key is looked up first, and the create
+// is only repeated if nothing was made.
+
+// the request may or may not have reached the API
+pub(crate) fn outcome_unknown(e: &LinodeError) -> bool {
+    match e {
+        LinodeError::Transport { .. } | LinodeError::Decode { .. } => true,
+        LinodeError::Api { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+pub(crate) fn record_key(options: &DomainRecordOptions) -> String {
+    format!(
+        "{} {} {}",
+        options.record_type, options.name, options.target
+    )
+}
+
+impl LinodeClient {
+    // Run create, and when it fails without a clear answer look up what
+    // it would have made before trying again, up to the client's retry
+    // attempts.
+    pub(crate) async fn create_idempotent<T, C, CF, L, LF>(
+        &self,
+        key: &str,
+        create: C,
+        lookup: L,
+    ) -> Result<T, LinodeError>
+    where
+        C: Fn() -> CF,
+        CF: Future<Output = Result<T, LinodeError>>,
+        L: Fn() -> LF,
+        LF: Future<Output = Result<Option<T>, LinodeError>>,
+    {
+        let attempts = self.retry_policy.max_attempts;
+        let mut attempt = 1;
+        loop {
+            let e = match create().await {
+                Ok(created) => return Ok(created),
+                Err(e) if outcome_unknown(&e) && attempt < attempts => e,
+                Err(e) => return Err(e),
+            };
+            error!(
+                idempotency_key = %key,
+                "Create failed with an unknown outcome: {}, checking before retrying ({}/{})",
+                e, attempt, attempts
+            );
+            self.pause(self.retry_policy.backoff(attempt)).await;
+            if let Some(existing) = lookup().await? {
+                info!(idempotency_key = %key, "Create had succeeded, using the existing resource");
+                return Ok(existing);
+            }
+            attempt += 1;
+        }
+    }
+
+    // the instance with label, which the API keeps unique per account
+    pub async fn find_instance_by_label(
+        &self,
+        label: &str,
+    ) -> Result<Option<LinodeInstance>, LinodeError> {
+        Ok(self
+            .fetch_instances_filtered(&json!({ "label": label }))
+            .await?
+            .into_iter()
+            .find(|instance| instance.label == label))
+    }
+
+    // a record matching options' type, name and target, read fresh from
+    // the API rather than the record cache
+    pub async fn find_record(
+        &self,
+        domain: u64,
+        options: &DomainRecordOptions,
+    ) -> Result<Option<DomainRecord>, LinodeError> {
+        Ok(self
+            .fetch_records_with(domain, &Default::default())
+            .await?
+            .into_iter()
+            .find(|rec| {
+                rec.record_type == options.record_type
+                    && rec.name == options.name
+                    && rec.target == options.target
+            }))
+    }
+}
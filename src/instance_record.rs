@@ -0,0 +1,100 @@
+use crate::{DomainRecordOptions, LinodeClient, LinodeError, LinodeInstance, TXT_RECORD};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+// What a slot is running, stored as a TXT record with the same name as the
+// slot's A record: "linode-id=123 image=linode/debian12 created=...".
+// Lets reconcile and roll tell old nodes apart without listing instances.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceRecord {
+    pub instance_id: u64,
+    pub image: Option<String>,
+    pub created: String,
+}
+
+impl InstanceRecord {
+    pub fn from_instance(instance: &LinodeInstance) -> Self {
+        InstanceRecord {
+            instance_id: instance.id,
+            image: instance.image.clone(),
+            created: instance.created.clone(),
+        }
+    }
+
+    pub fn to_target(&self) -> String {
+        format!(
+            "linode-id={} image={} created={}",
+            self.instance_id,
+            self.image.as_deref().unwrap_or("-"),
+            self.created
+        )
+    }
+
+    pub fn parse(target: &str) -> Option<Self> {
+        let mut fields = HashMap::new();
+        for pair in target.trim_matches('"').split_whitespace() {
+            let (key, value) = pair.split_once('=')?;
+            fields.insert(key, value);
+        }
+        Some(InstanceRecord {
+            instance_id: fields.get("linode-id")?.parse().ok()?,
+            image: fields
+                .get("image")
+                .filter(|image| **image != "-")
+                .map(|image| image.to_string()),
+            created: fields.get("created")?.to_string(),
+        })
+    }
+}
+
+impl LinodeClient {
+    // create or overwrite the TXT record for slot name
+    pub async fn write_instance_record(
+        &self,
+        domain: u64,
+        name: &str,
+        record: &InstanceRecord,
+    ) -> Result<(), LinodeError> {
+        let target = record.to_target();
+        info!("Writing instance record for: {} ({})", name, target);
+        let existing = self.fetch_records(domain).await?.into_iter().find(|rec| {
+            rec.record_type == TXT_RECORD
+                && rec.name == name
+                && InstanceRecord::parse(&rec.target).is_some()
+        });
+
+        match existing {
+            Some(rec) => self.update_record_target(domain, rec.id, &target).await,
+            None => self
+                .post_record(domain, &DomainRecordOptions::txt(name, &target))
+                .await
+                .map(|_| ()),
+        }
+    }
+
+    pub async fn remove_instance_record(&self, domain: u64, name: &str) -> Result<(), LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        for rec in records.iter().filter(|rec| {
+            rec.record_type == TXT_RECORD
+                && rec.name == name
+                && InstanceRecord::parse(&rec.target).is_some()
+        }) {
+            self.delete_record(domain, rec.id).await?;
+        }
+        Ok(())
+    }
+
+    // slot name -> instance record, for every slot that has one
+    pub async fn fetch_instance_records(
+        &self,
+        domain: u64,
+    ) -> Result<HashMap<String, InstanceRecord>, LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        Ok(records
+            .into_iter()
+            .filter(|rec| rec.record_type == TXT_RECORD)
+            .filter_map(|rec| Some((rec.name, InstanceRecord::parse(&rec.target)?)))
+            .collect())
+    }
+}
@@ -0,0 +1,966 @@
+use crate::metadata::InstanceMetadata;
+use crate::networking::Interface;
+use crate::regions::RegionInfo;
+use crate::regions::{Region, REGIONS};
+use crate::secret::SecretString;
+use crate::tags::TagExpr;
+use crate::{
+    LinodeClient, LinodeError, ListOptions, POWER_CYCLE_TIMEOUT, STATUS_OFFLINE,
+    STATUS_POLL_INTERVAL,
+};
+use futures::Stream;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::info;
+use web_time::Instant;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinodeInstance {
+    pub id: u64,
+    pub label: String,
+    group: String,
+    pub status: String,
+    pub created: String,
+    updated: String,
+    #[serde(rename = "type")]
+    pub instance_type: String,
+    pub ipv4: Vec<String>,
+    pub ipv6: String,
+    pub image: Option<String>,
+    pub region: String,
+    pub specs: InstanceSpecs,
+    alerts: InstanceAlerts,
+    pub backups: InstanceBackups,
+    hypervisor: String,
+    watchdog_enabled: bool,
+    pub tags: Vec<String>,
+    host_uuid: String,
+    has_user_data: bool,
+}
+
+// What the instance's plan provides; disk and memory are in MB.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceSpecs {
+    pub disk: u32,
+    pub memory: u32,
+    pub vcpus: u32,
+    pub gpus: u32,
+    pub transfer: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct InstanceAlerts {
+    cpu: u32,
+    network_in: u32,
+    network_out: u32,
+    transfer_quota: u32,
+    io: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceBackups {
+    pub enabled: bool,
+    pub available: bool,
+    pub schedule: Option<BackupSchedule>,
+    pub last_successful: Option<String>,
+}
+
+// When backups run: day is a weekday name, e.g. "Saturday", or
+// "Scheduling" to let Linode choose; window is a two hour UTC window named
+// by its start, "W0" to "W22".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupSchedule {
+    pub day: Option<String>,
+    pub window: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LinodeInstanceCreateOptions {
+    image: String,
+    tags: Vec<String>,
+    label: String,
+    region: String,
+    #[serde(rename = "type")]
+    instance_type: String,
+    root_pass: SecretString,
+    authorized_keys: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<InstanceMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    swap_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    booted: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BootOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CreateInstanceOptions {
+    pub image: String,
+    pub tags: Vec<String>,
+    pub label: String,
+    pub region: String,
+    pub instance_type: String,
+    pub metadata: Option<InstanceMetadata>,
+    // swap disk size in MB, the API defaults to 512
+    pub swap_size: Option<u32>,
+    // Some(false) creates the instance powered off, e.g. for warm pools
+    pub booted: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Helpers {
+    updatedb_disabled: bool,
+    distro: bool,
+    modules_dep: bool,
+    network: bool,
+    devtmpfs_automount: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Devices {
+    sda: Option<DiskInfo>,
+    sdb: Option<DiskInfo>,
+    sdc: Option<DiskInfo>,
+    sdd: Option<DiskInfo>,
+    sde: Option<DiskInfo>,
+    sdf: Option<DiskInfo>,
+    sdg: Option<DiskInfo>,
+    sdh: Option<DiskInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskInfo {
+    disk_id: u64,
+    volume_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Configuration {
+    pub id: u64,
+    label: String,
+    helpers: Helpers,
+    kernel: String,
+    comments: String,
+    memory_limit: u64,
+    created: String,
+    updated: String,
+    root_device: String,
+    devices: Devices,
+    initrd: Option<String>,
+    run_level: String,
+    virt_mode: String,
+    pub(crate) interfaces: Vec<Interface>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Price {
+    pub hourly: f64,
+    pub monthly: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegionPrice {
+    pub id: String,
+    pub hourly: f64,
+    pub monthly: f64,
+}
+
+// An instance plan from /linode/types, e.g. g6-dedicated-2.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinodeType {
+    pub id: String,
+    pub label: String,
+    pub class: String,
+    pub disk: u64,
+    pub memory: u64,
+    pub vcpus: u32,
+    pub gpus: u32,
+    pub transfer: u64,
+    pub network_out: u64,
+    pub price: Price,
+    #[serde(default)]
+    pub region_prices: Vec<RegionPrice>,
+}
+
+impl LinodeType {
+    // some regions are priced differently from the base plan price
+    pub fn price_in(&self, region: &str) -> Price {
+        self.region_prices
+            .iter()
+            .find(|p| p.id == region)
+            .map(|p| Price {
+                hourly: p.hourly,
+                monthly: p.monthly,
+            })
+            .unwrap_or(self.price)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Image {
+    pub id: String,
+    pub label: String,
+    pub description: Option<String>,
+    pub created: String,
+    pub created_by: Option<String>,
+    pub deprecated: bool,
+    pub is_public: bool,
+    pub size: u64,
+    pub status: String,
+    #[serde(rename = "type")]
+    pub image_type: String,
+    pub vendor: Option<String>,
+    pub expiry: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // where copies of a private image are stored
+    #[serde(default)]
+    pub regions: Vec<ImageRegion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageRegion {
+    pub region: String,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ImageCreateOptions {
+    disk_id: u64,
+    label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Disk {
+    pub id: u64,
+    pub label: String,
+    pub filesystem: String,
+    pub size: u64,
+    pub status: String,
+    pub created: String,
+    pub updated: String,
+}
+
+// Instance, image, disk and plan calls, from LinodeClient::instances().
+#[derive(Clone, Copy)]
+pub struct Instances<'a> {
+    client: &'a LinodeClient,
+}
+
+impl LinodeClient {
+    pub fn instances(&self) -> Instances<'_> {
+        Instances { client: self }
+    }
+
+    pub async fn fetch_instances(&self) -> Result<Vec<LinodeInstance>, LinodeError> {
+        self.fetch_instances_with(&ListOptions::default()).await
+    }
+
+    // filter is sent as the X-Filter header, e.g. {"tags": "api"}
+    pub async fn fetch_instances_filtered(
+        &self,
+        filter: &Value,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        let options = ListOptions {
+            filter: Some(filter.clone()),
+            ..Default::default()
+        };
+        self.fetch_instances_with(&options).await
+    }
+
+    pub async fn fetch_instances_with(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        info!("Fetching Linode instances with options: {:?}", options);
+        let instances = self
+            .cached_list(
+                "linode/instances",
+                options,
+                self.fetch_all::<LinodeInstance>("linode/instances", options),
+            )
+            .await?;
+        info!("Fetched {} instances", instances.len());
+
+        Ok(instances)
+    }
+
+    // every instance, fetched a page at a time as the stream is read
+    pub fn fetch_instances_stream(
+        &self,
+    ) -> impl Stream<Item = Result<LinodeInstance, LinodeError>> + '_ {
+        self.paginate::<LinodeInstance>("linode/instances", &ListOptions::default())
+            .into_stream()
+    }
+
+    pub async fn get_instance_configurations(
+        &self,
+        id: u64,
+    ) -> Result<Vec<Configuration>, LinodeError> {
+        self.get_instance_configurations_with(id, &ListOptions::default())
+            .await
+    }
+
+    pub async fn get_instance_configurations_with(
+        &self,
+        id: u64,
+        options: &ListOptions,
+    ) -> Result<Vec<Configuration>, LinodeError> {
+        info!("Fetching configurations for instance ID: {}", id);
+        let path = format!("linode/instances/{}/configs", id);
+        let configs = self
+            .cached_list(
+                &path,
+                options,
+                self.fetch_all::<Configuration>(&path, options),
+            )
+            .await?;
+        info!(
+            "Fetched {} configurations for instance ID: {}",
+            configs.len(),
+            id
+        );
+
+        Ok(configs)
+    }
+
+    pub async fn get_instances_by_tag(
+        &self,
+        tags: Vec<&str>,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        info!("Filtering instances by tags: {:?}", tags);
+        let instances = self.fetch_instances().await?;
+        let filtered_instances = instances
+            .into_iter()
+            .filter(|instance| {
+                tags.iter()
+                    .all(|tag| instance.tags.contains(&tag.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        info!(
+            "Found {} instances with tags: {:?}",
+            filtered_instances.len(),
+            tags
+        );
+        Ok(filtered_instances)
+    }
+
+    pub async fn get_instances_by_tag_expr(
+        &self,
+        expr: &TagExpr,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        info!("Filtering instances by tag expression: {}", expr);
+        let instances = self
+            .fetch_instances()
+            .await?
+            .into_iter()
+            .filter(|instance| expr.matches(&instance.tags))
+            .collect::<Vec<_>>();
+
+        info!(
+            "Found {} instances matching tag expression: {}",
+            instances.len(),
+            expr
+        );
+        Ok(instances)
+    }
+
+    // instances managed by the scaler for a tag in a region, filtered
+    // server-side so large accounts don't list every instance per action
+    pub async fn get_fleet_instances(
+        &self,
+        tag: &str,
+        region: &RegionInfo,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        let filter = json!({ "+and": [{ "tags": tag }, { "region": region.region }] });
+        let instances = self
+            .fetch_instances_filtered(&filter)
+            .await?
+            .into_iter()
+            .filter(|instance| instance.tags.iter().any(|t| t == region.code))
+            .collect::<Vec<_>>();
+
+        info!(
+            "Found {} instances with tag: {} in region: {}",
+            instances.len(),
+            tag,
+            region.code
+        );
+        Ok(instances)
+    }
+
+    pub async fn destroy_instance(&self, id: u64) -> Result<(), LinodeError> {
+        info!("Destroying instance ID: {}", id);
+        self.send(
+            self.client
+                .delete(format!("{}/linode/instances/{}", self.base_url, id,))
+                .bearer_auth(self.token.expose()),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        info!("Instance ID: {} destroyed successfully", id);
+        Ok(())
+    }
+
+    pub async fn reboot_instance(&self, id: u64) -> Result<(), LinodeError> {
+        self.reboot_instance_with_config(id, None).await
+    }
+
+    // reboot into config_id, or the last booted config if None
+    pub async fn reboot_instance_with_config(
+        &self,
+        id: u64,
+        config_id: Option<u64>,
+    ) -> Result<(), LinodeError> {
+        info!("Rebooting instance ID: {} into config: {:?}", id, config_id);
+        self.send(
+            self.client
+                .post(format!("{}/linode/instances/{}/reboot", self.base_url, id,))
+                .bearer_auth(self.token.expose())
+                .json(&BootOptions { config_id }),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        info!("Instance ID: {} rebooted successfully", id);
+        Ok(())
+    }
+
+    pub async fn fetch_instance(&self, id: u64) -> Result<LinodeInstance, LinodeError> {
+        info!("Fetching instance ID: {}", id);
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}/linode/instances/{}", self.base_url, id))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+
+        response.json::<LinodeInstance>().await
+    }
+
+    pub async fn update_instance_tags(
+        &self,
+        id: u64,
+        tags: Vec<String>,
+    ) -> Result<LinodeInstance, LinodeError> {
+        info!("Setting tags: {:?} on instance ID: {}", tags, id);
+        let response = self
+            .send(
+                self.client
+                    .put(format!("{}/linode/instances/{}", self.base_url, id))
+                    .bearer_auth(self.token.expose())
+                    .json(&json!({ "tags": tags })),
+            )
+            .await?;
+
+        response.json::<LinodeInstance>().await
+    }
+
+    pub async fn shutdown_instance(&self, id: u64) -> Result<(), LinodeError> {
+        info!("Shutting down instance ID: {}", id);
+        self.send(
+            self.client
+                .post(format!(
+                    "{}/linode/instances/{}/shutdown",
+                    self.base_url, id
+                ))
+                .bearer_auth(self.token.expose()),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        info!("Instance ID: {} shut down successfully", id);
+        Ok(())
+    }
+
+    // boot into config_id, or the last booted config if None
+    pub async fn boot_instance(&self, id: u64, config_id: Option<u64>) -> Result<(), LinodeError> {
+        info!("Booting instance ID: {} into config: {:?}", id, config_id);
+        self.send(
+            self.client
+                .post(format!("{}/linode/instances/{}/boot", self.base_url, id))
+                .bearer_auth(self.token.expose())
+                .json(&BootOptions { config_id }),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        info!("Instance ID: {} booted successfully", id);
+        Ok(())
+    }
+
+    // poll until the instance reports status, e.g. "running" or "offline"
+    pub async fn wait_for_status(
+        &self,
+        id: u64,
+        status: &str,
+        timeout: Duration,
+    ) -> Result<LinodeInstance, LinodeError> {
+        info!("Waiting for instance ID: {} to be {}", id, status);
+        let deadline = Instant::now() + timeout;
+        loop {
+            let instance = self.fetch_instance(id).await?;
+            if instance.status == status {
+                info!("Instance ID: {} is {}", id, status);
+                return Ok(instance);
+            }
+            if Instant::now() >= deadline {
+                return Err(LinodeError::Timeout(format!(
+                    "instance ID: {} still {} after {:?}, expected {}",
+                    id, instance.status, timeout, status
+                )));
+            }
+            self.pause(STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    // shutdown, wait for offline, then boot - a cold restart for when a
+    // soft reboot doesn't pick up interface changes
+    pub async fn power_cycle_instance(
+        &self,
+        id: u64,
+        config_id: Option<u64>,
+    ) -> Result<(), LinodeError> {
+        info!("Power cycling instance ID: {}", id);
+        self.shutdown_instance(id).await?;
+        self.wait_for_status(id, STATUS_OFFLINE, POWER_CYCLE_TIMEOUT)
+            .await?;
+        self.boot_instance(id, config_id).await
+    }
+
+    pub async fn create_linode_instance(
+        &self,
+        image: String,
+        tags: Vec<String>,
+        label: String,
+        region: String,
+        instance_type: String,
+    ) -> Result<LinodeInstance, LinodeError> {
+        self.create_instance(CreateInstanceOptions {
+            image,
+            tags,
+            label,
+            region,
+            instance_type,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn create_instance(
+        &self,
+        create: CreateInstanceOptions,
+    ) -> Result<LinodeInstance, LinodeError> {
+        info!(
+            "Creating Linode instance with label: {}, region: {}, instance type: {}",
+            create.label, create.region, create.instance_type
+        );
+        // never logged; access is by the authorized key
+        let password = SecretString::new(
+            rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(30)
+                .map(char::from)
+                .collect::<String>(),
+        );
+        let authorized_keys = vec![self.pub_key.clone()];
+        let label = create.label;
+        let options = LinodeInstanceCreateOptions {
+            authorized_keys,
+            image: create.image,
+            tags: create.tags,
+            label: label.clone(),
+            region: create.region,
+            instance_type: create.instance_type,
+            root_pass: password,
+            metadata: create.metadata,
+            swap_size: create.swap_size,
+            booted: create.booted,
+        };
+
+        // the label is the idempotency key, see idempotency.rs
+        let instance = self
+            .create_idempotent(
+                &label,
+                || async {
+                    self.send(
+                        self.client
+                            .post(format!("{}/linode/instances", self.base_url))
+                            .bearer_auth(self.token.expose())
+                            .json(&options),
+                    )
+                    .await?
+                    .json::<LinodeInstance>()
+                    .await
+                },
+                || self.find_instance_by_label(&label),
+            )
+            .await?;
+        info!(idempotency_key = %label, "Created instance ID: {} with label: {}", instance.id, label);
+
+        Ok(instance)
+    }
+
+    pub async fn fetch_regions(&self) -> Result<Vec<Region>, LinodeError> {
+        info!("Fetching regions");
+        let regions = self
+            .fetch_all::<Region>("regions", &ListOptions::default())
+            .await?;
+        info!("Fetched {} regions", regions.len());
+
+        Ok(regions)
+    }
+
+    pub async fn fetch_types(&self) -> Result<Vec<LinodeType>, LinodeError> {
+        info!("Fetching instance types");
+        let types = self
+            .fetch_all::<LinodeType>("linode/types", &ListOptions::default())
+            .await?;
+        info!("Fetched {} instance types", types.len());
+
+        Ok(types)
+    }
+
+    pub async fn fetch_images(&self, options: &ListOptions) -> Result<Vec<Image>, LinodeError> {
+        info!("Fetching images");
+        let images = self.fetch_all::<Image>("images", options).await?;
+        info!("Fetched {} images", images.len());
+
+        Ok(images)
+    }
+
+    // capture a disk as a private image
+    pub async fn create_image(
+        &self,
+        disk_id: u64,
+        label: &str,
+        description: Option<String>,
+    ) -> Result<Image, LinodeError> {
+        info!("Creating image: {} from disk ID: {}", label, disk_id);
+        let options = ImageCreateOptions {
+            disk_id,
+            label: label.to_owned(),
+            description,
+        };
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/images", self.base_url))
+                    .bearer_auth(self.token.expose())
+                    .json(&options),
+            )
+            .await?;
+
+        let image = response.json::<Image>().await?;
+        info!("Created image ID: {} with label: {}", image.id, label);
+        Ok(image)
+    }
+
+    pub async fn fetch_image(&self, id: &str) -> Result<Image, LinodeError> {
+        info!("Fetching image ID: {}", id);
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}/images/{}", self.base_url, id))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+
+        response.json::<Image>().await
+    }
+
+    // store copies of a private image in regions; the list replaces the
+    // image's current regions, so include the ones to keep
+    pub async fn replicate_image(&self, id: &str, regions: &[&str]) -> Result<Image, LinodeError> {
+        info!("Replicating image ID: {} to regions: {:?}", id, regions);
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/images/{}/regions", self.base_url, id))
+                    .bearer_auth(self.token.expose())
+                    .json(&json!({ "regions": regions })),
+            )
+            .await?;
+
+        response.json::<Image>().await
+    }
+
+    // poll until the image is available, and in region if one is given
+    pub async fn wait_for_image(
+        &self,
+        id: &str,
+        region: Option<&str>,
+        timeout: Duration,
+    ) -> Result<Image, LinodeError> {
+        info!("Waiting for image ID: {} to be available", id);
+        let deadline = Instant::now() + timeout;
+        loop {
+            let image = self.fetch_image(id).await?;
+            let in_region = region.is_none_or(|region| {
+                image
+                    .regions
+                    .iter()
+                    .any(|r| r.region == region && r.status == "available")
+            });
+            if image.status == "available" && in_region {
+                info!("Image ID: {} is available", id);
+                return Ok(image);
+            }
+            if Instant::now() >= deadline {
+                return Err(LinodeError::Timeout(format!(
+                    "image ID: {} still {} after {:?}",
+                    id, image.status, timeout
+                )));
+            }
+            self.pause(STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    pub async fn delete_image(&self, id: &str) -> Result<(), LinodeError> {
+        info!("Deleting image ID: {}", id);
+        self.send(
+            self.client
+                .delete(format!("{}/images/{}", self.base_url, id))
+                .bearer_auth(self.token.expose()),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        info!("Image ID: {} deleted successfully", id);
+        Ok(())
+    }
+
+    pub async fn fetch_instance_disks(&self, id: u64) -> Result<Vec<Disk>, LinodeError> {
+        info!("Fetching disks for instance ID: {}", id);
+        let disks = self
+            .fetch_all::<Disk>(
+                &format!("linode/instances/{}/disks", id),
+                &ListOptions::default(),
+            )
+            .await?;
+        info!("Fetched {} disks for instance ID: {}", disks.len(), id);
+
+        Ok(disks)
+    }
+
+    pub fn is_legacy_region(&self, region: &str) -> bool {
+        info!("Checking if region: {} is a legacy region", region);
+        REGIONS
+            .get(region)
+            .map(|info| info.is_legacy)
+            .unwrap_or(false)
+    }
+}
+
+impl<'a> Instances<'a> {
+    pub async fn fetch_instances(&self) -> Result<Vec<LinodeInstance>, LinodeError> {
+        self.client.fetch_instances().await
+    }
+
+    pub fn fetch_instances_stream(
+        &self,
+    ) -> impl Stream<Item = Result<LinodeInstance, LinodeError>> + 'a {
+        self.client.fetch_instances_stream()
+    }
+
+    pub async fn fetch_instances_filtered(
+        &self,
+        filter: &Value,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        self.client.fetch_instances_filtered(filter).await
+    }
+
+    pub async fn fetch_instances_with(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        self.client.fetch_instances_with(options).await
+    }
+
+    pub async fn get_instance_configurations(
+        &self,
+        id: u64,
+    ) -> Result<Vec<Configuration>, LinodeError> {
+        self.client.get_instance_configurations(id).await
+    }
+
+    pub async fn get_instance_configurations_with(
+        &self,
+        id: u64,
+        options: &ListOptions,
+    ) -> Result<Vec<Configuration>, LinodeError> {
+        self.client
+            .get_instance_configurations_with(id, options)
+            .await
+    }
+
+    pub async fn get_instances_by_tag(
+        &self,
+        tags: Vec<&str>,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        self.client.get_instances_by_tag(tags).await
+    }
+
+    pub async fn get_instances_by_tag_expr(
+        &self,
+        expr: &TagExpr,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        self.client.get_instances_by_tag_expr(expr).await
+    }
+
+    pub async fn get_fleet_instances(
+        &self,
+        tag: &str,
+        region: &RegionInfo,
+    ) -> Result<Vec<LinodeInstance>, LinodeError> {
+        self.client.get_fleet_instances(tag, region).await
+    }
+
+    pub async fn destroy_instance(&self, id: u64) -> Result<(), LinodeError> {
+        self.client.destroy_instance(id).await
+    }
+
+    pub async fn reboot_instance(&self, id: u64) -> Result<(), LinodeError> {
+        self.client.reboot_instance(id).await
+    }
+
+    pub async fn reboot_instance_with_config(
+        &self,
+        id: u64,
+        config_id: Option<u64>,
+    ) -> Result<(), LinodeError> {
+        self.client.reboot_instance_with_config(id, config_id).await
+    }
+
+    pub async fn fetch_instance(&self, id: u64) -> Result<LinodeInstance, LinodeError> {
+        self.client.fetch_instance(id).await
+    }
+
+    pub async fn update_instance_tags(
+        &self,
+        id: u64,
+        tags: Vec<String>,
+    ) -> Result<LinodeInstance, LinodeError> {
+        self.client.update_instance_tags(id, tags).await
+    }
+
+    pub async fn update_backup_schedule(
+        &self,
+        id: u64,
+        schedule: &BackupSchedule,
+    ) -> Result<LinodeInstance, LinodeError> {
+        self.client.update_backup_schedule(id, schedule).await
+    }
+
+    pub async fn shutdown_instance(&self, id: u64) -> Result<(), LinodeError> {
+        self.client.shutdown_instance(id).await
+    }
+
+    pub async fn boot_instance(&self, id: u64, config_id: Option<u64>) -> Result<(), LinodeError> {
+        self.client.boot_instance(id, config_id).await
+    }
+
+    pub async fn wait_for_status(
+        &self,
+        id: u64,
+        status: &str,
+        timeout: Duration,
+    ) -> Result<LinodeInstance, LinodeError> {
+        self.client.wait_for_status(id, status, timeout).await
+    }
+
+    pub async fn power_cycle_instance(
+        &self,
+        id: u64,
+        config_id: Option<u64>,
+    ) -> Result<(), LinodeError> {
+        self.client.power_cycle_instance(id, config_id).await
+    }
+
+    pub async fn create_linode_instance(
+        &self,
+        image: String,
+        tags: Vec<String>,
+        label: String,
+        region: String,
+        instance_type: String,
+    ) -> Result<LinodeInstance, LinodeError> {
+        self.client
+            .create_linode_instance(image, tags, label, region, instance_type)
+            .await
+    }
+
+    pub async fn create_instance(
+        &self,
+        create: CreateInstanceOptions,
+    ) -> Result<LinodeInstance, LinodeError> {
+        self.client.create_instance(create).await
+    }
+
+    pub async fn fetch_regions(&self) -> Result<Vec<Region>, LinodeError> {
+        self.client.fetch_regions().await
+    }
+
+    pub async fn fetch_types(&self) -> Result<Vec<LinodeType>, LinodeError> {
+        self.client.fetch_types().await
+    }
+
+    pub async fn fetch_images(&self, options: &ListOptions) -> Result<Vec<Image>, LinodeError> {
+        self.client.fetch_images(options).await
+    }
+
+    pub async fn create_image(
+        &self,
+        disk_id: u64,
+        label: &str,
+        description: Option<String>,
+    ) -> Result<Image, LinodeError> {
+        self.client.create_image(disk_id, label, description).await
+    }
+
+    pub async fn fetch_image(&self, id: &str) -> Result<Image, LinodeError> {
+        self.client.fetch_image(id).await
+    }
+
+    pub async fn replicate_image(&self, id: &str, regions: &[&str]) -> Result<Image, LinodeError> {
+        self.client.replicate_image(id, regions).await
+    }
+
+    pub async fn wait_for_image(
+        &self,
+        id: &str,
+        region: Option<&str>,
+        timeout: Duration,
+    ) -> Result<Image, LinodeError> {
+        self.client.wait_for_image(id, region, timeout).await
+    }
+
+    pub async fn delete_image(&self, id: &str) -> Result<(), LinodeError> {
+        self.client.delete_image(id).await
+    }
+
+    pub async fn fetch_instance_disks(&self, id: u64) -> Result<Vec<Disk>, LinodeError> {
+        self.client.fetch_instance_disks(id).await
+    }
+
+    pub fn is_legacy_region(&self, region: &str) -> bool {
+        self.client.is_legacy_region(region)
+    }
+}
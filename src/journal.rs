@@ -0,0 +1,497 @@
+use crate::networking::{public_interface, vlan_interface, Interfaces};
+use crate::registry::ServiceRegistry;
+use crate::{
+    LinodeClient, LinodeError, A_RECORD, BOOT_TIMEOUT, STATUS_OFFLINE, STATUS_POLL_INTERVAL,
+    STATUS_RUNNING,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+// One step of a composite operation, appended once it has happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum JournalStep {
+    Created {
+        instance_id: u64,
+    },
+    Configured {
+        instance_id: u64,
+        config_id: u64,
+    },
+    Booted {
+        instance_id: u64,
+    },
+    Registered {
+        instance_id: u64,
+        name: String,
+    },
+    Deregistered {
+        instance_id: u64,
+        name: String,
+    },
+    Imaged {
+        instance_id: u64,
+        image_id: String,
+    },
+    Replaced {
+        instance_id: u64,
+        replacement_id: u64,
+    },
+    Destroyed {
+        instance_id: u64,
+    },
+}
+
+// Everything a scale_up, scale_down or evacuate operation did, kept until
+// it finishes so an interrupted run can be resumed or rolled back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub operation_id: String,
+    // scale_up, scale_down or evacuate
+    pub kind: String,
+    pub tag: String,
+    // region code
+    pub region: String,
+    // the domain an evacuation's slots are in
+    pub domain: Option<u64>,
+    // label and VLAN address a scale up creates its instance with
+    pub label: Option<String>,
+    pub ipam: Option<String>,
+    // unix time
+    pub started: u64,
+    pub steps: Vec<JournalStep>,
+    pub finished: bool,
+}
+
+impl JournalEntry {
+    pub fn new(operation_id: &str, kind: &str, tag: &str, region: &str) -> Self {
+        JournalEntry {
+            operation_id: operation_id.to_owned(),
+            kind: kind.to_owned(),
+            tag: tag.to_owned(),
+            region: region.to_owned(),
+            domain: None,
+            label: None,
+            ipam: None,
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            steps: Vec::new(),
+            finished: false,
+        }
+    }
+
+    fn find<T>(&self, f: impl FnMut(&JournalStep) -> Option<T>) -> Option<T> {
+        self.steps.iter().find_map(f)
+    }
+
+    fn destroyed(&self, id: u64) -> bool {
+        self.steps
+            .contains(&JournalStep::Destroyed { instance_id: id })
+    }
+}
+
+// A directory of journal entries, one JSON file per operation.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+    // operations begun through this journal and not finished yet
+    in_flight: Arc<Mutex<Vec<String>>>,
+}
+
+impl Journal {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Self {
+        Journal {
+            dir: dir.as_ref().to_path_buf(),
+            in_flight: Arc::default(),
+        }
+    }
+
+    pub fn in_flight(&self) -> Vec<String> {
+        self.in_flight.lock().unwrap().clone()
+    }
+
+    fn path(&self, operation_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", operation_id))
+    }
+
+    pub fn load(&self, operation_id: &str) -> Result<JournalEntry, LinodeError> {
+        let path = self.path(operation_id);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| LinodeError::Journal(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&source)
+            .map_err(|e| LinodeError::Journal(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, entry: &JournalEntry) -> Result<(), LinodeError> {
+        let path = self.path(&entry.operation_id);
+        let json =
+            serde_json::to_string_pretty(entry).map_err(|e| LinodeError::Journal(e.to_string()))?;
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&path, json))
+            .map_err(|e| LinodeError::Journal(format!("{}: {}", path.display(), e)))
+    }
+
+    // operations that never finished, oldest first
+    pub fn unfinished(&self) -> Result<Vec<JournalEntry>, LinodeError> {
+        let dir = match std::fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(LinodeError::Journal(format!(
+                    "{}: {}",
+                    self.dir.display(),
+                    e
+                )))
+            }
+        };
+        let mut entries = Vec::new();
+        for file in dir.flatten() {
+            let path = file.path();
+            let Some(operation_id) = path
+                .extension()
+                .filter(|ext| *ext == "json")
+                .and(path.file_stem())
+                .and_then(|stem| stem.to_str())
+            else {
+                continue;
+            };
+            let entry = self.load(operation_id)?;
+            if !entry.finished {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by_key(|entry| entry.started);
+        Ok(entries)
+    }
+}
+
+#[cfg(feature = "native")]
+pub fn default_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("linode").join("journal"))
+}
+
+impl LinodeClient {
+    // A failed journal write is logged rather than failing the operation
+    // it describes.
+    pub(crate) fn journal_begin(&self, entry: JournalEntry) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.save(&entry) {
+                error!("Failed to write journal: {}", e);
+            }
+            journal.in_flight.lock().unwrap().push(entry.operation_id);
+        }
+    }
+
+    pub(crate) fn journal_step(&self, operation_id: &str, step: JournalStep) {
+        self.journal_update(operation_id, |entry| entry.steps.push(step));
+    }
+
+    pub(crate) fn journal_finish(&self, operation_id: &str) {
+        self.journal_update(operation_id, |entry| entry.finished = true);
+        if let Some(journal) = &self.journal {
+            journal
+                .in_flight
+                .lock()
+                .unwrap()
+                .retain(|id| id != operation_id);
+        }
+    }
+
+    fn journal_update(&self, operation_id: &str, update: impl FnOnce(&mut JournalEntry)) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        let result = journal.load(operation_id).and_then(|mut entry| {
+            update(&mut entry);
+            journal.save(&entry)
+        });
+        if let Err(e) = result {
+            error!("Failed to write journal: {}", e);
+        }
+    }
+
+    // Carry an operation an interrupted run left unfinished through to
+    // the end, or with rollback undo the steps it took. registry is where
+    // its nodes are registered. A resumed scale up isn't provisioned or
+    // health checked, and a resumed scale down only finishes the nodes it
+    // had started removing.
+    pub async fn resume(
+        &self,
+        registry: &dyn ServiceRegistry,
+        operation_id: &str,
+        rollback: bool,
+    ) -> Result<JournalEntry, LinodeError> {
+        let journal = self
+            .journal
+            .as_ref()
+            .ok_or_else(|| LinodeError::Journal("no journal configured".to_owned()))?;
+        let entry = journal.load(operation_id)?;
+        if entry.finished {
+            info!("Operation: {} already finished", operation_id);
+            return Ok(entry);
+        }
+        info!(
+            "{} {} operation: {} with tag: {} in region: {}",
+            if rollback { "Rolling back" } else { "Resuming" },
+            entry.kind,
+            operation_id,
+            entry.tag,
+            entry.region
+        );
+        match entry.kind.as_str() {
+            "scale_up" => self.resume_scale_up(registry, &entry, rollback).await?,
+            "scale_down" => self.resume_scale_down(registry, &entry, rollback).await?,
+            "evacuate" | "replace" => self.resume_evacuate(&entry, rollback).await?,
+            kind => {
+                return Err(LinodeError::Journal(format!(
+                    "unknown operation kind: {}",
+                    kind
+                )))
+            }
+        }
+        self.journal_finish(operation_id);
+        journal.load(operation_id)
+    }
+
+    async fn resume_scale_up(
+        &self,
+        registry: &dyn ServiceRegistry,
+        entry: &JournalEntry,
+        rollback: bool,
+    ) -> Result<(), LinodeError> {
+        let id = &entry.operation_id;
+        let prefix = self.naming().prefix(&entry.tag, &entry.region);
+        let created = entry.find(|step| match step {
+            JournalStep::Created { instance_id } => Some(*instance_id),
+            _ => None,
+        });
+        // the run may have stopped between creating the instance and
+        // journaling it, so look for it by label too
+        let instance_id = match (created, &entry.label) {
+            (Some(instance_id), _) => Some(instance_id),
+            (None, Some(label)) => self
+                .get_instances_by_tag(vec![&entry.tag])
+                .await?
+                .into_iter()
+                .find(|instance| &instance.label == label)
+                .map(|instance| instance.id),
+            (None, None) => None,
+        };
+        let Some(instance_id) = instance_id else {
+            info!("Operation: {} created no instance", id);
+            return Ok(());
+        };
+        let instance = match self.fetch_instance(instance_id).await {
+            Ok(instance) => instance,
+            Err(LinodeError::NotFound { .. }) if rollback => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let registered = entry.find(|step| match step {
+            JournalStep::Registered { name, .. } => Some(name.clone()),
+            _ => None,
+        });
+
+        if rollback {
+            if let Some(name) = registry.deregister(&prefix, &instance).await? {
+                self.journal_step(id, JournalStep::Deregistered { instance_id, name });
+            }
+            self.destroy_instance(instance_id).await?;
+            self.journal_step(id, JournalStep::Destroyed { instance_id });
+            return Ok(());
+        }
+
+        let configured = entry.find(|step| match step {
+            JournalStep::Configured { config_id, .. } => Some(*config_id),
+            _ => None,
+        });
+        let config_id = match configured {
+            Some(config_id) => config_id,
+            None => {
+                let ipam = entry.ipam.clone().ok_or_else(|| {
+                    LinodeError::Journal(format!("operation: {} has no VLAN address", id))
+                })?;
+                let config_id = self
+                    .get_instance_configurations(instance_id)
+                    .await?
+                    .first()
+                    .map(|config| config.id)
+                    .ok_or_else(|| {
+                        LinodeError::Journal(format!("instance ID: {} has no config", instance_id))
+                    })?;
+                let interfaces = Interfaces {
+                    interfaces: vec![public_interface(), vlan_interface(&entry.tag, ipam)],
+                };
+                self.set_interfaces(instance_id, config_id, interfaces)
+                    .await?;
+                self.journal_step(
+                    id,
+                    JournalStep::Configured {
+                        instance_id,
+                        config_id,
+                    },
+                );
+                config_id
+            }
+        };
+
+        if !entry.steps.contains(&JournalStep::Booted { instance_id }) {
+            self.wait_for_status(instance_id, STATUS_RUNNING, BOOT_TIMEOUT)
+                .await?;
+            self.reboot_instance_with_config(instance_id, Some(config_id))
+                .await?;
+            self.pause(STATUS_POLL_INTERVAL).await;
+            self.wait_for_status(instance_id, STATUS_RUNNING, BOOT_TIMEOUT)
+                .await?;
+            self.journal_step(id, JournalStep::Booted { instance_id });
+        }
+
+        if registered.is_none() {
+            let name = registry.register(&prefix, &instance).await?;
+            info!("Registered instance ID: {} as {}", instance_id, name);
+            self.journal_step(id, JournalStep::Registered { instance_id, name });
+        }
+        Ok(())
+    }
+
+    async fn resume_scale_down(
+        &self,
+        registry: &dyn ServiceRegistry,
+        entry: &JournalEntry,
+        rollback: bool,
+    ) -> Result<(), LinodeError> {
+        let id = &entry.operation_id;
+        let prefix = self.naming().prefix(&entry.tag, &entry.region);
+        for step in &entry.steps {
+            let JournalStep::Deregistered { instance_id, name } = step else {
+                continue;
+            };
+            let instance_id = *instance_id;
+            if entry.destroyed(instance_id) {
+                continue;
+            }
+            if rollback {
+                let instance = self.fetch_instance(instance_id).await?;
+                let name = registry.register(&prefix, &instance).await?;
+                info!("Re-registered instance ID: {} as {}", instance_id, name);
+                self.journal_step(id, JournalStep::Registered { instance_id, name });
+            } else {
+                info!(
+                    "Destroying instance ID: {} pulled from {}",
+                    instance_id, name
+                );
+                self.destroy_instance(instance_id).await?;
+                self.journal_step(id, JournalStep::Destroyed { instance_id });
+            }
+        }
+        Ok(())
+    }
+
+    // Also resumes a replace, which journals the same steps without an
+    // image. A replacement that was still being scaled up has its own
+    // scale_up entry, resumed separately.
+    async fn resume_evacuate(
+        &self,
+        entry: &JournalEntry,
+        rollback: bool,
+    ) -> Result<(), LinodeError> {
+        let id = &entry.operation_id;
+        let domain = entry
+            .domain
+            .ok_or_else(|| LinodeError::Journal(format!("operation: {} has no domain", id)))?;
+        for step in &entry.steps {
+            let JournalStep::Deregistered { instance_id, name } = step else {
+                continue;
+            };
+            let instance_id = *instance_id;
+            if entry.destroyed(instance_id) {
+                continue;
+            }
+            let replacement = entry.find(|step| match step {
+                JournalStep::Replaced {
+                    instance_id: original,
+                    replacement_id,
+                } if *original == instance_id => Some(*replacement_id),
+                _ => None,
+            });
+            let image = entry.find(|step| match step {
+                JournalStep::Imaged {
+                    instance_id: original,
+                    image_id,
+                } if *original == instance_id => Some(image_id.clone()),
+                _ => None,
+            });
+
+            if !rollback {
+                let Some(replacement_id) = replacement else {
+                    return Err(LinodeError::Journal(format!(
+                        "instance ID: {} has no replacement yet, resume with rollback",
+                        instance_id
+                    )));
+                };
+                self.destroy_instance(instance_id).await?;
+                self.journal_step(id, JournalStep::Destroyed { instance_id });
+                info!(
+                    "Instance ID: {} replaced by ID: {} in slot: {}",
+                    instance_id, replacement_id, name
+                );
+                if let Some(image_id) = image {
+                    info!("Keeping image ID: {}", image_id);
+                }
+                continue;
+            }
+
+            // put the original back in its slot
+            let instance = self.fetch_instance(instance_id).await?;
+            if instance.status == STATUS_OFFLINE {
+                self.boot_instance(instance_id, None).await?;
+                self.wait_for_status(instance_id, STATUS_RUNNING, BOOT_TIMEOUT)
+                    .await?;
+            }
+            let slot = self
+                .fetch_records(domain)
+                .await?
+                .into_iter()
+                .find(|rec| rec.record_type == A_RECORD && &rec.name == name)
+                .ok_or_else(|| {
+                    LinodeError::Journal(format!(
+                        "slot {} is gone from domain ID: {}",
+                        name, domain
+                    ))
+                })?;
+            let ip = instance.ipv4.first().ok_or_else(|| {
+                LinodeError::Journal(format!("instance ID: {} has no IPv4", instance_id))
+            })?;
+            self.update_record_target(domain, slot.id, ip).await?;
+            self.journal_step(
+                id,
+                JournalStep::Registered {
+                    instance_id,
+                    name: name.clone(),
+                },
+            );
+
+            if let Some(replacement_id) = replacement {
+                self.destroy_instance(replacement_id).await?;
+                self.journal_step(
+                    id,
+                    JournalStep::Destroyed {
+                        instance_id: replacement_id,
+                    },
+                );
+            }
+            if let Some(image_id) = image {
+                self.delete_image(&image_id).await?;
+            }
+            info!(
+                "Rolled back evacuation of {} in slot: {}",
+                instance.label, name
+            );
+        }
+        Ok(())
+    }
+}
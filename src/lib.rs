@@ -1,216 +1,263 @@
+// LinodeError::Validation carries the request and every field the API
+// rejected, which is more than clippy likes in an Err
+#![allow(clippy::result_large_err)]
+
+pub mod adopt;
+pub mod allowlist;
+pub mod api_calls;
+pub mod api_version;
+#[cfg(feature = "native")]
+pub mod axfr;
+pub mod backups;
+pub mod balance;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod budget;
+pub mod builder;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod concurrency;
+#[cfg(feature = "native")]
+pub mod config;
+pub mod cordon;
+pub mod dns;
+pub mod domains;
+pub mod drain;
+pub mod error;
+pub mod evacuate;
+pub mod events;
+pub mod failover;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod firewall;
+pub mod fleet;
+pub mod health;
+pub mod history;
+pub mod idempotency;
+pub mod instance_record;
+pub mod instances;
+pub mod journal;
+pub mod lock;
+pub mod metadata;
+pub mod middleware;
+pub mod naming;
+pub mod networking;
+pub mod nodebalancers;
+pub mod pagination;
+pub mod placement;
+pub mod provisioner;
+pub mod rate_limit;
+pub mod reap;
+pub mod reconcile;
+pub mod region_names;
 pub mod regions;
-
-use crate::regions::{RegionInfo, REGIONS};
-use rand::{distributions::Alphanumeric, Rng};
-use regex::Regex;
-use reqwest::{Client, Error};
-use serde::{Deserialize, Serialize};
-use std::cmp::Ordering;
-use std::{collections::HashMap, f32::consts::LOG2_E};
+pub mod registry;
+pub mod rename;
+pub mod replace;
+pub mod response_cache;
+pub mod retry;
+pub mod roll;
+pub mod scaling;
+pub mod schedule;
+pub mod secret;
+pub mod shutdown;
+pub mod snapshot;
+pub mod spec;
+pub mod srv;
+pub mod state;
+pub mod tags;
+pub mod template;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod timings;
+pub mod token;
+pub mod token_source;
+pub mod transport;
+pub mod vcr;
+pub mod volumes;
+pub mod zone_health;
+
+pub use crate::api_version::ApiVersion;
+#[cfg(feature = "blocking")]
+pub use crate::blocking::BlockingLinodeClient;
+pub use crate::builder::LinodeClientBuilder;
+pub use crate::dns::{
+    Domain, DomainRecord, DomainRecordOptions, DomainRecordUpdateOptions, Upsert,
+};
+pub use crate::error::{FieldError, LinodeError, RequestContext};
+pub use crate::instances::{
+    BackupSchedule, Configuration, CreateInstanceOptions, Devices, Disk, DiskInfo, Helpers, Image,
+    ImageRegion, InstanceBackups, InstanceSpecs, LinodeInstance, LinodeType, Price, RegionPrice,
+};
+pub use crate::middleware::Middleware;
+pub use crate::networking::{Interface, Interfaces};
+pub use crate::pagination::{Page, Paginator};
+pub use crate::scaling::{ScaleFailure, ScaleSummary, ScaleUpOptions, ScaledNode, UserData};
+pub use crate::token_source::TokenSource;
+pub use crate::transport::{FnTransport, LinodeTransport};
+pub use reqwest::Method;
+
+use crate::api_calls::ApiCalls;
+use crate::concurrency::RequestLimiter;
+use crate::journal::Journal;
+use crate::naming::NamingScheme;
+use crate::rate_limit::RateLimit;
+use crate::response_cache::ResponseCache;
+use crate::retry::{retry_after, RetryPolicy};
+use crate::secret::SecretString;
+use crate::shutdown::ShutdownHandle;
+use crate::state::StateCache;
+use crate::timings::StepMetrics;
+use crate::vcr::Cassette;
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Client, Request, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use svix_ksuid::*;
-use tls_helpers::from_base64_raw;
-use tracing::{error, info};
+use tracing::{debug, error, info_span, Instrument};
+use web_time::Instant;
 
 const A_RECORD: &str = "A";
+const TXT_RECORD: &str = "TXT";
 const API_HOST: &str = "https://api.linode.com/v4/";
 const LOCALHOST: &str = "127.0.0.1";
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LinodeInstance {
-    pub id: u64,
-    pub label: String,
-    group: String,
-    status: String,
-    created: String,
-    updated: String,
-    #[serde(rename = "type")]
-    pub instance_type: String,
-    pub ipv4: Vec<String>,
-    pub ipv6: String,
-    image: Option<String>,
-    pub region: String,
-    specs: InstanceSpecs,
-    alerts: InstanceAlerts,
-    backups: InstanceBackups,
-    hypervisor: String,
-    watchdog_enabled: bool,
-    pub tags: Vec<String>,
-    host_uuid: String,
-    has_user_data: bool,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct InstanceSpecs {
-    disk: u32,
-    memory: u32,
-    vcpus: u32,
-    gpus: u32,
-    transfer: u32,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct InstanceAlerts {
-    cpu: u32,
-    network_in: u32,
-    network_out: u32,
-    transfer_quota: u32,
-    io: u32,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct InstanceBackups {
-    enabled: bool,
-    available: bool,
-    schedule: Option<BackupSchedule>,
-    last_successful: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct BackupSchedule {
-    day: Option<String>,
-    window: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct LinodeResponse {
-    data: Vec<LinodeInstance>,
-    page: u32,
-    pages: u32,
-    results: u32,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct LinodeInstanceCreateOptions {
-    image: String,
-    tags: Vec<String>,
-    label: String,
-    region: String,
-    #[serde(rename = "type")]
-    instance_type: String,
-    root_pass: String,
-    authorized_keys: Vec<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Helpers {
-    updatedb_disabled: bool,
-    distro: bool,
-    modules_dep: bool,
-    network: bool,
-    devtmpfs_automount: bool,
+const X_FILTER: &str = "X-Filter";
+const DEFAULT_PAGE_SIZE: u32 = 500;
+const STATUS_OFFLINE: &str = "offline";
+const STATUS_RUNNING: &str = "running";
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const POWER_CYCLE_TIMEOUT: Duration = Duration::from_secs(300);
+const BULK_CONCURRENCY: usize = 4;
+const BOOT_TIMEOUT: Duration = Duration::from_secs(600);
+
+// Query options accepted by every list method. page_size defaults to the
+// client's, 500 (the API maximum) unless its builder set another; filter
+// and order_by are sent via X-Filter. List methods follow every page
+// unless page is set, which fetches only it.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub page_size: Option<u32>,
+    pub page: Option<u32>,
+    pub filter: Option<Value>,
+    pub order_by: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Devices {
-    sda: Option<DiskInfo>,
-    sdb: Option<DiskInfo>,
-    sdc: Option<DiskInfo>,
-    sdd: Option<DiskInfo>,
-    sde: Option<DiskInfo>,
-    sdf: Option<DiskInfo>,
-    sdg: Option<DiskInfo>,
-    sdh: Option<DiskInfo>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DiskInfo {
-    disk_id: u64,
-    volume_id: Option<u64>,
+impl ListOptions {
+    fn x_filter(&self) -> Option<Value> {
+        let mut filter = self.filter.clone();
+        if let Some(order_by) = &self.order_by {
+            let filter = filter.get_or_insert_with(|| json!({}));
+            if let Some(map) = filter.as_object_mut() {
+                map.insert("+order_by".to_owned(), json!(order_by));
+            }
+        }
+        filter
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Configuration {
-    pub id: u64,
-    label: String,
-    helpers: Helpers,
-    kernel: String,
-    comments: String,
-    memory_limit: u64,
-    created: String,
-    updated: String,
-    root_device: String,
-    devices: Devices,
-    initrd: Option<String>,
-    run_level: String,
-    virt_mode: String,
-    interfaces: Vec<Interface>,
+// A response tagged with the request it answers, so decoding failures and
+// API errors can say which call they came from.
+pub struct ApiResponse {
+    response: Response,
+    context: RequestContext,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Interfaces {
-    pub interfaces: Vec<Interface>,
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    #[serde(default)]
+    errors: Vec<FieldError>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Interface {
-    pub purpose: String,
-    pub ipam_address: Option<String>,
-    pub label: Option<String>,
-}
+impl ApiResponse {
+    pub fn status(&self) -> StatusCode {
+        self.response.status()
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DomainRecordOptions {
-    #[serde(rename = "type")]
-    record_type: String,
-    name: String,
-    target: String,
-    ttl_sec: i32,
-}
+    pub fn context(&self) -> &RequestContext {
+        &self.context
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DomainRecordUpdateOptions {
-    target: String,
-    ttl_sec: i32,
-}
+    pub fn into_inner(self) -> Response {
+        self.response
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DomainRecord {
-    pub id: u64,
-    #[serde(rename = "type")]
-    pub record_type: String,
-    pub name: String,
-    pub target: String,
-    priority: Option<i32>,
-    weight: Option<i32>,
-    port: Option<i32>,
-    service: Option<String>,
-    protocol: Option<String>,
-    ttl_sec: i32,
-}
+    // decode a success body, or fail as error_for_status does
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T, LinodeError> {
+        let response = self.error_for_status().await?;
+        let context = response.context;
+        response
+            .response
+            .json::<T>()
+            .await
+            .map_err(|source| LinodeError::request(Some(context), source))
+    }
 
-impl DomainRecord {
-    fn extract_prefix_and_number(&self) -> (&str, Option<i32>) {
-        let re = Regex::new(r"^(.*?)-(\d+)$").unwrap();
-        if let Some(caps) = re.captures(&self.name) {
-            let prefix = caps.get(1).map_or("", |m| m.as_str());
-            let number = caps.get(2).map_or(None, |m| m.as_str().parse::<i32>().ok());
-            (prefix, number)
-        } else {
-            (&self.name, None)
+    // turn an error status into the LinodeError for it, with the reasons
+    // from Linode's {"errors": [...]} payload; for calls whose body isn't
+    // needed
+    pub async fn error_for_status(self) -> Result<ApiResponse, LinodeError> {
+        let status = self.response.status();
+        if status.is_success() {
+            return Ok(self);
         }
+        let retry_after = retry_after(self.response.headers());
+
+        let body = self.response.text().await.unwrap_or_default();
+        let reasons = serde_json::from_str::<ApiErrorBody>(&body)
+            .map(|body| body.errors)
+            .unwrap_or_default();
+        error!(
+            request_id = %self.context.request_id,
+            "{} {} returned {}: {:?}",
+            self.context.method,
+            self.context.endpoint,
+            status,
+            reasons.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+        Err(LinodeError::from_status(
+            status.as_u16(),
+            self.context,
+            reasons,
+            retry_after,
+        ))
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct DomainRecordsResponse {
-    data: Vec<DomainRecord>,
-    page: u64,
-    pages: u64,
-    results: u64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct InstanceConfigurationsResponse {
-    data: Vec<Configuration>,
-    page: u64,
-    pages: u64,
-    results: u64,
-}
-
 pub struct LinodeClient {
-    token: String,
+    token: SecretString,
+    base_url: String,
+    // builds requests; transport sends them
     client: Client,
+    transport: Arc<dyn LinodeTransport>,
     pub_key: String,
+    // for list calls whose options don't set page_size
+    page_size: u32,
+    verify_writes: bool,
+    // per-domain record lists, kept in step with our own mutations
+    record_cache: Option<Mutex<HashMap<u64, Vec<DomainRecord>>>>,
+    // listings reused for a while, dropped by our own writes
+    response_cache: Option<ResponseCache>,
+    retry_policy: RetryPolicy,
+    create_retry_policy: RetryPolicy,
+    trace_http: bool,
+    cassette: Option<Arc<Cassette>>,
+    state_cache: Option<Arc<StateCache>>,
+    journal: Option<Arc<Journal>>,
+    rate_limit: Mutex<Option<RateLimit>>,
+    rate_limit_pacing: bool,
+    step_metrics: StepMetrics,
+    api_calls: ApiCalls,
+    naming: NamingScheme,
+    middleware: Vec<Arc<dyn Middleware>>,
+    shutdown: ShutdownHandle,
+    // replaces token when set, see TokenSource
+    token_source: Option<Arc<dyn TokenSource>>,
+    token_refresh: Duration,
+    current_token: Mutex<Option<(String, Instant)>>,
+    limiter: RequestLimiter,
 }
 
 impl LinodeClient {
@@ -218,460 +265,386 @@ impl LinodeClient {
         token: String,
         pub_key: String,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let mut decoded_pub_key: Vec<u8> = from_base64_raw(&pub_key)?;
-
-        // Strip any newline or whitespace characters from the end
-        while let Some(&last_byte) = decoded_pub_key.last() {
-            if last_byte == b'\n' || last_byte == b'\r' || last_byte == b' ' {
-                decoded_pub_key.pop();
-            } else {
-                break;
-            }
-        }
+        LinodeClientBuilder::new(token, pub_key).build()
+    }
 
-        Ok(LinodeClient {
-            token,
-            pub_key: String::from_utf8_lossy(&decoded_pub_key).into_owned(),
-            client: Client::new(),
-        })
+    // record every API call to a cassette, or serve them from one
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(Arc::new(cassette));
+        self
     }
 
-    pub async fn fetch_records(&self, domain: u64) -> Result<Vec<DomainRecord>, Error> {
-        info!("Fetching domain records for domain ID: {}", domain);
-        let response = self
-            .client
-            .get(&format!("{}/domains/{}/records", API_HOST, domain))
-            .bearer_auth(&self.token)
-            .send()
-            .await?;
-
-        info!("Parsing response into DomainRecordsResponse");
-        let records = response.json::<DomainRecordsResponse>().await?;
-        info!(
-            "Fetched {} records for domain ID: {}",
-            records.data.len(),
-            domain
-        );
+    // keep every successful GET in a state cache, or answer from it when
+    // the cache is offline
+    pub fn with_state_cache(mut self, cache: StateCache) -> Self {
+        self.state_cache = Some(Arc::new(cache));
+        self
+    }
 
-        // Sort the records by prefix and then by the numeric suffix
-        let mut records = records.data;
-        records.sort_by(|a, b| {
-            let (prefix_a, num_a) = a.extract_prefix_and_number();
-            let (prefix_b, num_b) = b.extract_prefix_and_number();
+    pub fn state_cache(&self) -> Option<&StateCache> {
+        self.state_cache.as_deref()
+    }
 
-            match prefix_a.cmp(prefix_b) {
-                Ordering::Equal => num_a.cmp(&num_b),
-                other => other,
-            }
-        });
+    // send requests through transport from here on, e.g. to answer them
+    // from memory in tests
+    pub fn with_transport<T: LinodeTransport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
 
-        Ok(records)
+    // write each step of scale ups, scale downs and evacuations to a
+    // journal, so an interrupted run can be resumed
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(Arc::new(journal));
+        self
     }
 
-    pub async fn delete_record(&self, domain: u64, id: u64) -> Result<(), Error> {
-        info!("Deleting record with ID: {} in domain ID: {}", id, domain);
-        self.client
-            .delete(format!("{}/domains/{}/records/{}", API_HOST, domain, id))
-            .bearer_auth(&self.token)
-            .send()
-            .await?;
+    // waits between polls are skipped when replaying a cassette, and
+    // without a native timer
+    async fn pause(&self, duration: Duration) {
+        #[cfg(feature = "native")]
+        if !self
+            .cassette
+            .as_ref()
+            .is_some_and(|cassette| cassette.is_replay())
+        {
+            tokio::time::sleep(duration).await;
+        }
+        #[cfg(not(feature = "native"))]
+        let _ = duration;
+    }
 
-        info!("Record ID: {} deleted successfully", id);
-        Ok(())
+    // log method, URL and headers of every request and response at debug
+    // level; the token and all bodies are redacted
+    pub fn with_trace_http(mut self, enabled: bool) -> Self {
+        self.trace_http = enabled;
+        self
     }
 
-    pub async fn update_record_target(
-        &self,
-        domain: u64,
-        id: u64,
-        target: &str,
-    ) -> Result<(), Error> {
-        info!(
-            "Updating record ID: {} in domain ID: {} with new target: {}",
-            id, domain, target
-        );
-        let options = DomainRecordUpdateOptions {
-            target: target.to_owned(),
-            ttl_sec: 30,
-        };
-        self.client
-            .put(format!("{}/domains/{}/records/{}", API_HOST, domain, id))
-            .bearer_auth(&self.token)
-            .json(&options)
-            .send()
-            .await?;
-
-        info!(
-            "Record ID: {} updated successfully to target: {}",
-            id, target
-        );
-        Ok(())
+    // slow down as the rate limit window runs low instead of running into
+    // 429s, see LinodeClient::pace; on by default
+    pub fn with_rate_limit_pacing(mut self, enabled: bool) -> Self {
+        self.rate_limit_pacing = enabled;
+        self
     }
 
-    pub async fn create_a_record(
-        &self,
-        domain: u64,
-        name: String,
-        target: String,
-    ) -> Result<(), Error> {
-        info!(
-            "Creating new A record in domain ID: {} with name: {} and target: {}",
-            domain, name, target
-        );
-        let options = DomainRecordOptions {
-            record_type: "A".to_owned(),
-            name: name.clone(),
-            target: target.clone(),
-            ttl_sec: 30,
-        };
-        self.client
-            .post(format!("{}/domains/{}/records", API_HOST, domain))
-            .bearer_auth(&self.token)
-            .json(&options)
-            .send()
-            .await?;
-
-        info!(
-            "A record created successfully with name: {} in domain ID: {}",
-            name, domain
-        );
-        Ok(())
+    // used for GET, PUT and DELETE requests
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
     }
 
-    pub async fn fetch_instances(&self) -> Result<Vec<LinodeInstance>, Error> {
-        info!("Fetching all Linode instances");
-        let response = self
-            .client
-            .get(format!("{}/linode/instances?page_size=500", API_HOST))
-            .bearer_auth(&self.token)
-            .send()
-            .await?;
+    // used for POST requests, which mostly create things
+    pub fn with_create_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.create_retry_policy = policy;
+        self
+    }
 
-        info!("Parsing response into LinodeResponse");
-        let instances = response.json::<LinodeResponse>().await?;
-        info!("Fetched {} instances", instances.data.len());
+    // point the client at another API root, e.g. a mock server; paths are
+    // appended as-is so keep the trailing slash
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_owned();
+        self
+    }
 
-        Ok(instances.data)
+    // re-read DNS records after every write and fail with
+    // LinodeError::RecordMismatch if the stored values differ
+    pub fn with_verify_writes(mut self, verify_writes: bool) -> Self {
+        self.verify_writes = verify_writes;
+        self
     }
 
-    pub async fn get_instance_configurations(&self, id: u64) -> Result<Vec<Configuration>, Error> {
-        info!("Fetching configurations for instance ID: {}", id);
-        let response = self
-            .client
-            .get(format!("{}/linode/instances/{}/configs", API_HOST, id))
-            .bearer_auth(&self.token)
-            .send()
-            .await?;
-
-        info!("Parsing response into InstanceConfigurationsResponse");
-        let configs = response.json::<InstanceConfigurationsResponse>().await?;
-        info!(
-            "Fetched {} configurations for instance ID: {}",
-            configs.data.len(),
-            id
-        );
+    // fetch each domain's records once and apply our own creates, updates
+    // and deletes locally instead of re-fetching the zone every time
+    pub fn with_record_cache(mut self, enabled: bool) -> Self {
+        self.record_cache = enabled.then(|| Mutex::new(HashMap::new()));
+        self
+    }
 
-        Ok(configs.data)
+    async fn send(&self, request: RequestBuilder) -> Result<ApiResponse, LinodeError> {
+        let request = request.build()?;
+        let policy = if request.method() == Method::POST {
+            &self.create_retry_policy
+        } else {
+            &self.retry_policy
+        };
+        self.execute(request, policy).await
     }
 
-    pub async fn get_instances_by_tag(
+    // send a request under a policy other than the client's
+    pub(crate) async fn send_with(
         &self,
-        tags: Vec<&str>,
-    ) -> Result<Vec<LinodeInstance>, Error> {
-        info!("Filtering instances by tags: {:?}", tags);
-        let instances = self.fetch_instances().await?;
-        let filtered_instances = instances
-            .into_iter()
-            .filter(|instance| {
-                tags.iter()
-                    .all(|tag| instance.tags.contains(&tag.to_string()))
-            })
-            .collect::<Vec<_>>();
-
-        info!(
-            "Found {} instances with tags: {:?}",
-            filtered_instances.len(),
-            tags
-        );
-        Ok(filtered_instances)
+        request: RequestBuilder,
+        policy: &RetryPolicy,
+    ) -> Result<ApiResponse, LinodeError> {
+        self.execute(request.build()?, policy).await
     }
 
-    pub async fn set_interfaces(
+    // Call an endpoint this crate doesn't wrap, e.g.
+    // request::<Value>(Method::GET, "account/settings", None), with the
+    // client's auth, retries and error handling. path is relative to the
+    // API root.
+    pub async fn request<T: DeserializeOwned>(
         &self,
-        id: u64,
-        config_id: u64,
-        interfaces: Interfaces,
-    ) -> Result<(), Error> {
-        info!(
-            "Setting interfaces for instance ID: {} with config ID: {}",
-            id, config_id
-        );
-        self.client
-            .put(format!(
-                "{}/linode/instances/{}/configs/{}",
-                API_HOST, id, config_id
-            ))
-            .bearer_auth(&self.token)
-            .json(&interfaces)
-            .send()
-            .await?;
-
-        info!(
-            "Interfaces set successfully for instance ID: {} with config ID: {}",
-            id, config_id
-        );
-        Ok(())
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<T, LinodeError> {
+        self.request_at(&self.base_url, method, path, body).await
     }
 
-    pub async fn destroy_instance(&self, id: u64) -> Result<(), Error> {
-        info!("Destroying instance ID: {}", id);
-        self.client
-            .delete(format!("{}/linode/instances/{}", API_HOST, id,))
-            .bearer_auth(&self.token)
-            .send()
-            .await?;
-
-        info!("Instance ID: {} destroyed successfully", id);
-        Ok(())
+    // request under a retry policy other than the client's, e.g. to retry
+    // a create known to be idempotent
+    pub async fn request_with<T: DeserializeOwned>(
+        &self,
+        policy: &RetryPolicy,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<T, LinodeError> {
+        let request = self.api_request(&self.base_url, method, path, body);
+        self.send_with(request, policy).await?.json::<T>().await
     }
 
-    pub async fn reboot_instance(&self, id: u64) -> Result<(), Error> {
-        info!("Rebooting instance ID: {}", id);
-        self.client
-            .post(format!("{}/linode/instances/{}/reboot", API_HOST, id,))
-            .bearer_auth(&self.token)
-            .send()
-            .await?;
-
-        info!("Instance ID: {} rebooted successfully", id);
-        Ok(())
+    async fn request_at<T: DeserializeOwned>(
+        &self,
+        base_url: &str,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<T, LinodeError> {
+        let request = self.api_request(base_url, method, path, body);
+        self.send(request).await?.json::<T>().await
     }
 
-    pub async fn create_linode_instance(
+    fn api_request(
         &self,
-        image: String,
-        tags: Vec<String>,
-        label: String,
-        region: String,
-        instance_type: String,
-    ) -> Result<LinodeInstance, Error> {
-        info!(
-            "Creating Linode instance with label: {}, region: {}, instance type: {}",
-            label, region, instance_type
-        );
-        let password = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(30)
-            .map(char::from)
-            .collect::<String>();
-
-        info!("Generated password for instance: {}", password);
-        let authorized_keys = vec![self.pub_key.clone()];
-        let options = LinodeInstanceCreateOptions {
-            authorized_keys,
-            image,
-            tags,
-            label: label.clone(),
-            region,
-            instance_type,
-            root_pass: password,
-        };
-
-        let response = self
+        base_url: &str,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> RequestBuilder {
+        let request = self
             .client
-            .post(format!("{}/linode/instances", API_HOST))
-            .bearer_auth(&self.token)
-            .json(&options)
-            .send()
-            .await?;
-
-        info!("Parsing response into LinodeInstance");
-        let instance = response.json::<LinodeInstance>().await?;
-        info!("Created instance ID: {} with label: {}", instance.id, label);
-
-        Ok(instance)
-    }
-
-    fn is_legacy_region(&self, region: &str) -> bool {
-        info!("Checking if region: {} is a legacy region", region);
-        REGIONS
-            .get(region)
-            .map(|info| info.is_legacy)
-            .unwrap_or(false)
+            .request(
+                method,
+                format!("{}{}", base_url, path.trim_start_matches('/')),
+            )
+            .bearer_auth(self.token.expose());
+        match body {
+            Some(body) => request.json(body),
+            None => request,
+        }
     }
 
-    // remove an instance that has a particular tag
-    pub async fn scale_down(
+    // every call gets a correlation ID, carried on the request span and in
+    // any error the call produces
+    async fn execute(
         &self,
-        domain: u64,
-        region: &RegionInfo,
-        tag: &str,
-        n: usize,
-    ) -> Result<usize, Error> {
-        info!(
-            "Scaling down an instance in region: {} with tag: {}",
-            region.code, tag
+        mut request: Request,
+        policy: &RetryPolicy,
+    ) -> Result<ApiResponse, LinodeError> {
+        let context = RequestContext {
+            request_id: Ksuid::new(None, None).to_string(),
+            method: request.method().to_string(),
+            endpoint: request.url().path().to_owned(),
+        };
+        let span = info_span!(
+            "api_request",
+            request_id = %context.request_id,
+            method = %context.method,
+            endpoint = %context.endpoint
         );
-        let instances = self.get_instances_by_tag(vec![tag, region.code]).await?;
-        let records = self.fetch_records(domain).await?;
-        let done = false;
-        let mut a_records = HashMap::new();
-        for record in records {
-            a_records.insert(record.target, record.id);
-        }
-
-        let mut done = 0;
-        for (i, instance) in instances.iter().enumerate() {
-            if let Some(id) = a_records.get(&instance.ipv4[0]) {
-                self.update_record_target(domain, *id, LOCALHOST).await?;
-                self.destroy_instance(instance.id).await?;
-
-                info!(
-                    "Scaled down instance ID: {} with label: {} in region: {}",
-                    instance.id, instance.label, region.code
-                );
 
-                done += 1;
-                if i + 1 > n {
-                    break;
+        let cassette = self.cassette.as_deref();
+        if let Some(cassette) = cassette.filter(|cassette| cassette.is_replay()) {
+            let response = span.in_scope(|| cassette.play(&request))?;
+            return Ok(ApiResponse { response, context });
+        }
+        let state_cache = self.state_cache.as_deref();
+        if let Some(cache) = state_cache.filter(|cache| cache.is_offline()) {
+            let response = span.in_scope(|| cache.lookup(&request))?;
+            return Ok(ApiResponse { response, context });
+        }
+        self.api_calls.check()?;
+        // with a token source, sent once more if its token was rejected
+        let mut reauth = None;
+        if self.token_source.is_some() {
+            let token = self.current_token().await?;
+            token_source::authorize(&mut request, &token)?;
+            reauth = request.try_clone().map(|request| (request, token));
+        }
+        let recorded = cassette.and_then(|cassette| Some((cassette, request.try_clone()?)));
+        let cached = state_cache.and_then(|cache| Some((cache, request.try_clone()?)));
+        let written = self
+            .response_cache
+            .as_ref()
+            .filter(|_| request.method() != Method::GET)
+            .map(|cache| (cache, request.url().clone()));
+
+        let result = async move {
+            let mut response = self.execute_with_retries(request, policy).await?;
+            if let (StatusCode::UNAUTHORIZED, Some((mut request, stale))) =
+                (response.status(), reauth)
+            {
+                if let Some(token) = self.refreshed_token(&stale).await {
+                    if token_source::authorize(&mut request, &token).is_ok() {
+                        response = self.execute_with_retries(request, policy).await?;
+                    }
                 }
             }
+            self.note_rate_limit(response.headers());
+            if let Some((cassette, request)) = &recorded {
+                response = cassette.capture(request, response).await?;
+            }
+            match &cached {
+                Some((cache, request)) => cache.store(request, response).await,
+                None => Ok(response),
+            }
         }
-
-        Ok(done)
+        .instrument(span)
+        .await;
+        // once the write is done, so a listing racing it isn't kept
+        if let Some((cache, url)) = written {
+            cache.invalidate(&url);
+        }
+        result
+            .map(|response| ApiResponse {
+                response,
+                context: context.clone(),
+            })
+            .map_err(|source| LinodeError::request(Some(context), source))
     }
 
-    // add an instance to the same VLAN as other linodes in a region
-    // assigns instance to a sequential subdomain
-    pub async fn scale_up_one(
+    async fn execute_with_retries(
         &self,
-        image_id: &str,
-        instance_type: &str,
-        domain: u64,
-        region: &RegionInfo,
-        tag: &str,
-    ) -> Result<(), Error> {
-        info!(
-            "Scaling up an instance in region: {} with tag: {}",
-            region.code, tag
-        );
-        let instances = self.get_instances_by_tag(vec![tag, region.code]).await?;
-
-        let mut cidrs: Vec<u8> = Vec::new();
-        for instance in instances {
-            let configs = self.get_instance_configurations(instance.id).await?;
-            for config in &configs {
-                for interface in &config.interfaces {
-                    if let Some(label) = &interface.label {
-                        if label == tag {
-                            if let Some(ipam) = &interface.ipam_address {
-                                let parts: Vec<&str> = ipam.split('/').collect();
-                                let ip_parts: Vec<&str> = parts[0].split('.').collect();
-                                match ip_parts[3].parse::<u8>() {
-                                    Ok(n) => cidrs.push(n),
-                                    Err(e) => {
-                                        error!("Parsing error in scale_up_one: {}", e);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+        request: Request,
+        policy: &RetryPolicy,
+    ) -> Result<Response, reqwest::Error> {
+        let start = Instant::now();
+        let mut attempt = 1;
+        loop {
+            self.api_calls
+                .count(request.method().as_str(), request.url().path());
+            // bodies that can't be cloned (streams) only get one try
+            let Some(mut this_try) = request.try_clone() else {
+                let mut request = request;
+                self.intercept_request(&mut request);
+                let _permit = self.limiter.acquire().await;
+                let result = self.transport.execute(request).await;
+                self.intercept_result(&result);
+                return result;
+            };
+            self.pace().await;
+            self.intercept_request(&mut this_try);
+            if self.trace_http {
+                trace_request(&this_try, attempt);
+            }
+            let result = {
+                let _permit = self.limiter.acquire().await;
+                self.transport.execute(this_try).await
+            };
+            self.intercept_result(&result);
+            if let (true, Ok(response)) = (self.trace_http, &result) {
+                trace_response(response);
+            }
+            let retry = match &result {
+                Ok(response) => policy.retries_status(response.status()),
+                Err(e) => policy.retries_error(e),
+            };
+            let delay = policy.backoff_for(
+                attempt,
+                result.as_ref().ok().map(|response| response.headers()),
+            );
+            let out_of_budget = policy
+                .budget
+                .is_some_and(|budget| start.elapsed() + delay >= budget);
+            if !retry || attempt >= policy.max_attempts || out_of_budget {
+                return result;
             }
-        }
-
-        let kid = Ksuid::new(None, None);
-        let label = format!("{}-{}", region.code, kid.to_string());
-
-        let instance = self
-            .create_linode_instance(
-                image_id.to_string(),
-                vec![tag.to_string(), region.code.to_string()],
-                label.clone(),
-                region.region.to_string(),
-                instance_type.to_string(),
-            )
-            .await?;
-
-        let configs = self.get_instance_configurations(instance.id).await?;
-        let config_id = configs[0].id;
-
-        let cidr = if let Some(max) = cidrs.iter().max() {
-            max + 1
-        } else {
-            1
-        };
-
-        let ipam = format!("10.0.0.{}/24", cidr);
-
-        let new_interfaces = Interfaces {
-            interfaces: vec![
-                Interface {
-                    purpose: "public".to_string(),
-                    ipam_address: None,
-                    label: None,
-                },
-                Interface {
-                    label: Some(tag.to_string()),
-                    ipam_address: Some(ipam),
-                    purpose: "vlan".to_string(),
-                },
-            ],
-        };
 
-        self.set_interfaces(instance.id, config_id, new_interfaces)
-            .await?;
-
-        info!("Rebooting the newly created instance ID: {}", instance.id);
-        self.reboot_instance(instance.id).await?;
-
-        let records = self.fetch_records(domain).await?;
-        let prefix = format!("{}-{}", tag, region.code);
-        let mut dns_done = false;
-        let mut seqs = Vec::new();
-
-        for rec in &records {
-            if rec.name.starts_with(&prefix) && rec.record_type == A_RECORD {
-                if rec.target == LOCALHOST {
-                    // found a free slot, claim it
-                    self.update_record_target(domain, rec.id, &instance.ipv4[0])
-                        .await?;
-
-                    dns_done = true;
-                    break;
-                } else {
-                    if let Some(n) = extract_number(&rec.name) {
-                        seqs.push(n);
-                    }
-                }
+            match &result {
+                Ok(response) => error!(
+                    "{} {} returned {}, retrying in {:?} ({}/{})",
+                    request.method(),
+                    request.url().path(),
+                    response.status(),
+                    delay,
+                    attempt,
+                    policy.max_attempts
+                ),
+                Err(e) => error!(
+                    "{} {} failed: {}, retrying in {:?} ({}/{})",
+                    request.method(),
+                    request.url().path(),
+                    e,
+                    delay,
+                    attempt,
+                    policy.max_attempts
+                ),
             }
+            self.pause(delay).await;
+            attempt += 1;
         }
+    }
 
-        if !dns_done {
-            seqs.sort();
-            seqs.reverse();
-            let n = if !seqs.is_empty() { seqs[0] + 1 } else { 1 };
-            self.create_a_record(
-                domain,
-                format!("{}-{}", prefix, n),
-                instance.ipv4[0].clone(),
-            )
-            .await?;
+    fn list_request(&self, path: &str, options: &ListOptions) -> RequestBuilder {
+        let mut query = vec![(
+            "page_size",
+            options.page_size.unwrap_or(self.page_size).to_string(),
+        )];
+        if let Some(page) = options.page {
+            query.push(("page", page.to_string()));
         }
 
-        info!(
-            "Scaled up instance ID: {} with label: {} in region: {}",
-            instance.id, label, region.code
-        );
-        Ok(())
+        let mut request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .bearer_auth(self.token.expose())
+            .query(&query);
+        if let Some(filter) = options.x_filter() {
+            request = request.header(X_FILTER, filter.to_string());
+        }
+        request
     }
 }
 
-fn extract_number(input: &str) -> Option<i32> {
-    let parts: Vec<&str> = input.split('-').collect();
+fn redacted_headers(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            if name == AUTHORIZATION {
+                format!("{}: <redacted>", name)
+            } else {
+                format!("{}: {}", name, value.to_str().unwrap_or("<binary>"))
+            }
+        })
+        .collect()
+}
 
-    if let Some(last_part) = parts.last() {
-        return last_part.parse().ok();
-    }
-    None
+fn trace_request(request: &Request, attempt: u32) {
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map_or(0, |bytes| bytes.len());
+    debug!(
+        attempt,
+        "--> {} {} headers={:?} body=<redacted {} bytes>",
+        request.method(),
+        request.url(),
+        redacted_headers(request.headers()),
+        body
+    );
+}
+
+fn trace_response(response: &Response) {
+    debug!(
+        "<-- {} {} headers={:?} body=<redacted {} bytes>",
+        response.status(),
+        response.url(),
+        redacted_headers(response.headers()),
+        response
+            .content_length()
+            .map_or("unknown".to_owned(), |n| n.to_string())
+    );
 }
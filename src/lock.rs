@@ -0,0 +1,223 @@
+use crate::regions::find_region;
+use crate::{DomainRecordOptions, LinodeClient, LinodeError, ListOptions, A_RECORD, TXT_RECORD};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use svix_ksuid::*;
+use tracing::{error, info};
+
+pub const DEFAULT_LOCK_TTL: Duration = Duration::from_secs(15 * 60);
+
+// Advisory lock stored as a well-known TXT record in the managed domain.
+// The record target is "{owner}:{expires}" where expires is a unix
+// timestamp; an expired lock may be taken over by anyone.
+#[derive(Debug, Clone)]
+pub struct ScaleLock {
+    pub domain: u64,
+    pub record_id: u64,
+    pub name: String,
+    pub owner: String,
+    pub expires: u64,
+}
+
+pub fn lock_name(tag: &str, region_code: &str) -> String {
+    format!("_lock-{}-{}", tag, region_code)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn parse_lock_target(target: &str) -> Option<(&str, u64)> {
+    let (owner, expires) = target.trim_matches('"').rsplit_once(':')?;
+    Some((owner, expires.parse().ok()?))
+}
+
+impl LinodeClient {
+    pub async fn acquire_lock(
+        &self,
+        domain: u64,
+        name: &str,
+        ttl: Duration,
+    ) -> Result<ScaleLock, LinodeError> {
+        let owner = Ksuid::new(None, None).to_string();
+        let expires = now() + ttl.as_secs();
+        let target = format!("{}:{}", owner, expires);
+        info!("Acquiring lock: {} in domain ID: {}", name, domain);
+
+        // always list fresh, the record cache can't see other operators
+        let existing = self
+            .fetch_records_with(domain, &ListOptions::default())
+            .await?
+            .into_iter()
+            .filter(|rec| rec.record_type == TXT_RECORD && rec.name == name)
+            .min_by_key(|rec| rec.id);
+
+        let record_id = match existing {
+            Some(rec) => {
+                if let Some((holder, until)) = parse_lock_target(&rec.target) {
+                    if until > now() {
+                        return Err(LinodeError::Locked {
+                            name: name.to_owned(),
+                            owner: holder.to_owned(),
+                            expires: until,
+                        });
+                    }
+                }
+                // expired or unreadable, take it over
+                info!("Taking over stale lock record ID: {}", rec.id);
+                self.put_record_target(domain, rec.id, &target).await?;
+                rec.id
+            }
+            None => {
+                let options = DomainRecordOptions::txt(name, &target);
+                self.post_record(domain, &options).await?.id
+            }
+        };
+
+        // re-read: if someone else created or took over the lock at the
+        // same time, the lowest record ID holding a live lock wins
+        let records = self
+            .fetch_records_with(domain, &ListOptions::default())
+            .await?;
+        let mut contenders = records
+            .iter()
+            .filter(|rec| rec.record_type == TXT_RECORD && rec.name == name)
+            .collect::<Vec<_>>();
+        contenders.sort_by_key(|rec| rec.id);
+
+        if let Some(winner) = contenders.first() {
+            if winner.id != record_id
+                || parse_lock_target(&winner.target) != Some((&owner, expires))
+            {
+                if winner.id != record_id {
+                    self.delete_record(domain, record_id).await?;
+                }
+                let (holder, until) = parse_lock_target(&winner.target).unwrap_or(("unknown", 0));
+                return Err(LinodeError::Locked {
+                    name: name.to_owned(),
+                    owner: holder.to_owned(),
+                    expires: until,
+                });
+            }
+        }
+
+        info!("Acquired lock: {} as {}", name, owner);
+        Ok(ScaleLock {
+            domain,
+            record_id,
+            name: name.to_owned(),
+            owner,
+            expires,
+        })
+    }
+
+    // Push a held lock's expiry out to ttl from now, for runs that outlast
+    // the ttl it was taken with. Fails with Locked if someone has taken it
+    // over in the meantime.
+    pub async fn refresh_lock(
+        &self,
+        lock: &mut ScaleLock,
+        ttl: Duration,
+    ) -> Result<(), LinodeError> {
+        let record = self.fetch_record(lock.domain, lock.record_id).await?;
+        match parse_lock_target(&record.target) {
+            Some((owner, _)) if owner == lock.owner => {}
+            holder => {
+                let (holder, until) = holder.unwrap_or(("unknown", 0));
+                return Err(LinodeError::Locked {
+                    name: lock.name.clone(),
+                    owner: holder.to_owned(),
+                    expires: until,
+                });
+            }
+        }
+        let expires = now() + ttl.as_secs();
+        let target = format!("{}:{}", lock.owner, expires);
+        self.put_record_target(lock.domain, lock.record_id, &target)
+            .await?;
+        lock.expires = expires;
+        info!(
+            "Refreshed lock: {} until {} (unix time)",
+            lock.name, expires
+        );
+        Ok(())
+    }
+
+    pub async fn release_lock(&self, lock: &ScaleLock) -> Result<(), LinodeError> {
+        let record = self.fetch_record(lock.domain, lock.record_id).await?;
+        match parse_lock_target(&record.target) {
+            Some((owner, _)) if owner == lock.owner => {
+                self.delete_record(lock.domain, lock.record_id).await?;
+                info!("Released lock: {}", lock.name);
+            }
+            _ => info!("Lock: {} is no longer held by {}", lock.name, lock.owner),
+        }
+        Ok(())
+    }
+
+    // Run op holding the scaling lock of every region tag has instances or
+    // slots in, e.g. a reconcile that mustn't race a scale of the fleet.
+    // Without a ttl op runs unlocked, as with --no-lock.
+    pub async fn with_fleet_locks<T, F>(
+        &self,
+        domain: u64,
+        tag: &str,
+        ttl: Option<Duration>,
+        op: F,
+    ) -> Result<T, LinodeError>
+    where
+        F: Future<Output = Result<T, LinodeError>>,
+    {
+        let Some(ttl) = ttl else {
+            return op.await;
+        };
+        let mut locks = Vec::new();
+        for code in self.fleet_regions(domain, tag).await? {
+            match self.acquire_lock(domain, &lock_name(tag, code), ttl).await {
+                Ok(lock) => locks.push(lock),
+                Err(e) => {
+                    self.release_locks(&locks).await;
+                    return Err(e);
+                }
+            }
+        }
+        let result = op.await;
+        self.release_locks(&locks).await;
+        result
+    }
+
+    // codes of the regions tag has slots or instances in
+    async fn fleet_regions(
+        &self,
+        domain: u64,
+        tag: &str,
+    ) -> Result<BTreeSet<&'static str>, LinodeError> {
+        let mut codes = self
+            .fetch_records(domain)
+            .await?
+            .iter()
+            .filter(|rec| rec.record_type == A_RECORD)
+            .filter_map(|rec| self.naming().slot_of(tag, &rec.name))
+            .map(|(code, _)| code)
+            .collect::<BTreeSet<_>>();
+        for instance in self.get_instances_by_tag(vec![tag]).await? {
+            if let Some(region) = find_region(&instance.region) {
+                codes.insert(region.code);
+            }
+        }
+        Ok(codes)
+    }
+
+    // the locks expire on their own, so a failed release is only logged
+    async fn release_locks(&self, locks: &[ScaleLock]) {
+        for lock in locks {
+            if let Err(e) = self.release_lock(lock).await {
+                error!("Failed to release lock: {}: {}", lock.name, e);
+            }
+        }
+    }
+}
@@ -1,131 +1,2883 @@
-use linode::regions::{RegionInfo, REGIONS};
-use linode::LinodeClient;
+// LinodeError is large, see lib.rs
+#![allow(clippy::result_large_err)]
+
+use linode::allowlist::{Allowlist, AllowlistSync};
+use linode::balance::ScaleDownPolicy;
+#[cfg(feature = "chaos")]
+use linode::chaos::Chaos;
+use linode::config::{Config, HookEvent, LinodeCliConfig};
+use linode::domains::DomainSettings;
+use linode::drain::DrainReplacement;
+use linode::evacuate::EvacuateOptions;
+use linode::failover::FailoverOptions;
+use linode::firewall::FirewallRules;
+use linode::fleet::{DiskUsage, FleetMember, HealthCheck};
+use linode::health::check_tcp;
+use linode::history::{diff_history, format_time, parse_point, ChangeKind};
+use linode::journal::{self, Journal};
+use linode::lock::{lock_name, ScaleLock};
+use linode::metadata::InstanceMetadata;
+use linode::nodebalancers::CertRotation;
+use linode::placement::{is_capacity_error, RegionPicker};
+use linode::provisioner::{HttpCallbackProvisioner, Provisioner, SshProvisioner};
+use linode::reap::{parse_age, ReapReport};
+use linode::regions::{find_region, RegionGroups, RegionInfo, REGIONS};
+use linode::registry::LinodeDnsRegistry;
+use linode::retry::RetryPolicy;
+use linode::roll::RollStatus;
+use linode::secret::{RedactingWriter, SecretString};
+use linode::shutdown::shutdown_signal;
+use linode::snapshot::FleetSnapshot;
+use linode::spec::{DiffKind, DiffOp, FleetDiff, FleetSpec};
+use linode::srv::SrvService;
+use linode::state::{self, StateCache};
+use linode::tags::TagExpr;
+use linode::template::UserDataTemplate;
+use linode::token::TokenReport;
+use linode::token_source::{FileToken, KeychainToken};
+use linode::vcr::Cassette;
+use linode::volumes::{volume_filter, VolumeCreateOptions};
+use linode::{
+    ApiVersion, BackupSchedule, LinodeClient, LinodeError, LinodeType, ListOptions, Method,
+    ScaleFailure, ScaleSummary, ScaleUpOptions, ScaledNode, Upsert, UserData,
+};
+use std::collections::HashMap;
 use std::error::Error;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
-use tracing::{error, info};
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+use tracing_subscriber::{prelude::*, EnvFilter};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "linode", about = "Linode API abstractions")]
 struct Command {
+    /// API token, defaults to the one in ~/.config/linode-cli
     #[structopt(long, env = "LINODE_API_TOKEN")]
-    token: String,
+    token: Option<String>,
+
+    /// Read the token from this file instead, re-reading it as it rotates
+    #[structopt(long, env = "LINODE_TOKEN_FILE", parse(from_os_str))]
+    token_file: Option<PathBuf>,
+
+    /// Read the token from the OS keychain instead, as SERVICE or
+    /// SERVICE:ACCOUNT (account defaults to api-token)
+    #[structopt(long, env = "LINODE_TOKEN_KEYCHAIN")]
+    token_keychain: Option<String>,
+
+    /// linode-cli user whose defaults are used instead of its default-user
+    #[structopt(long)]
+    as_user: Option<String>,
+
+    /// Account from the config file supplying the token, pub key, region
+    /// and API URL; defaults to its default_account
+    #[structopt(long, env = "LINODE_ACCOUNT")]
+    account: Option<String>,
+
+    /// Defaults to the account's pub_key
+    #[structopt(long, env = "LINODE_PUB_KEY")]
+    pub_key: Option<String>,
+
+    /// Re-read DNS records after each write and fail on mismatches
+    #[structopt(long)]
+    verify_writes: bool,
+
+    /// Log filter, e.g. "debug" or "linode=debug,reqwest=info"; overrides RUST_LOG
+    #[structopt(long)]
+    log_level: Option<String>,
+
+    /// Log format: full, compact or pretty
+    #[structopt(long, default_value = "full")]
+    log_format: LogFormat,
+
+    /// Log request and response metadata at debug level, bodies redacted
+    #[structopt(long)]
+    trace_http: bool,
+
+    /// Record every API call to a cassette file (tokens and passwords redacted)
+    #[structopt(long, parse(from_os_str), conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Serve API calls from a recorded cassette instead of the API
+    #[structopt(long, parse(from_os_str))]
+    replay: Option<PathBuf>,
+
+    /// Answer instance ls, status and diff from the state cache, without the API
+    #[structopt(long, conflicts_with_all = &["record", "replay"])]
+    offline: bool,
+
+    /// Where API reads are cached for --offline, defaults to ~/.cache/linode/state.json
+    #[structopt(long, env = "LINODE_STATE_CACHE", parse(from_os_str))]
+    state_cache: Option<PathBuf>,
+
+    /// Where each step of scale and evacuate operations is journaled for resume,
+    /// defaults to ~/.local/share/linode/journal
+    #[structopt(long, env = "LINODE_JOURNAL_DIR", parse(from_os_str))]
+    journal_dir: Option<PathBuf>,
+
+    /// Write per-step scale timings as Prometheus histograms to this file
+    #[structopt(long, env = "LINODE_METRICS_FILE", parse(from_os_str))]
+    metrics_file: Option<PathBuf>,
+
+    /// Fail instead of sending more than this many API calls, retries included
+    #[structopt(long, env = "LINODE_MAX_API_CALLS")]
+    max_api_calls: Option<u64>,
+
+    /// API requests sent at once at most, however many run in parallel
+    #[structopt(long, env = "LINODE_MAX_IN_FLIGHT", default_value = "32")]
+    max_in_flight: usize,
+
+    /// Reuse instance, record and config listings for this many seconds,
+    /// e.g. across the nodes of a scale up --count; writes drop what they change
+    #[structopt(long, env = "LINODE_RESPONSE_CACHE_TTL")]
+    response_cache_ttl: Option<u64>,
+
+    /// Attempts per API call for reads, updates and deletes
+    #[structopt(long, default_value = "3")]
+    max_attempts: u32,
+
+    /// Milliseconds before the first retry, doubling per retry up to --retry-max-delay-ms
+    #[structopt(long, default_value = "1000")]
+    retry_delay_ms: u64,
+
+    #[structopt(long, default_value = "30000")]
+    retry_max_delay_ms: u64,
+
+    /// API root to talk to instead of https://api.linode.com/v4/
+    #[structopt(long, env = "LINODE_API_URL")]
+    api_url: Option<String>,
+
+    /// Fail API calls of these steps against a mock --api-url, e.g.
+    /// "create=0.5,dns=0.1"; steps are create, set_interfaces, reboot, dns
+    /// and delete
+    #[cfg(feature = "chaos")]
+    #[structopt(long, env = "LINODE_CHAOS")]
+    chaos: Option<String>,
+
+    /// Seed for --chaos, so a failing run can be repeated
+    #[cfg(feature = "chaos")]
+    #[structopt(long, env = "LINODE_CHAOS_SEED", default_value = "0")]
+    chaos_seed: u64,
+
+    /// v4 or v4beta, e.g. for endpoints only in beta; swaps the version in
+    /// the API root
+    #[structopt(long, env = "LINODE_API_VERSION")]
+    api_version: Option<ApiVersion>,
+
+    /// Seconds before an API request is abandoned
+    #[structopt(long, default_value = "60")]
+    request_timeout: u64,
+
+    /// Proxy for API requests, e.g. http://proxy.corp:3128
+    #[structopt(long, env = "LINODE_PROXY")]
+    proxy: Option<String>,
+
+    /// Check the token and its scopes before running the command
+    #[structopt(long, env = "LINODE_VERIFY_TOKEN")]
+    verify_token: bool,
+
+    /// Skip the advisory scaling lock
+    #[structopt(long)]
+    no_lock: bool,
+
+    /// Seconds before an unreleased scaling lock expires; scale up renews it per instance
+    #[structopt(long, default_value = "900")]
+    lock_ttl: u64,
+
+    /// Config file with lifecycle hooks and profiles, defaults to ~/.config/linode/config.toml
+    #[structopt(long, env = "LINODE_CONFIG", parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Define or override a region group, e.g. "us=us-iad,us-ord"
+    #[structopt(
+        long,
+        env = "LINODE_REGION_GROUPS",
+        number_of_values = 1,
+        value_delimiter = ";"
+    )]
+    region_group: Vec<String>,
+
+    /// DNS slot names, e.g. "{tag}-{region}-{n}" or "{region}-{tag}{n}"; overrides the
+    /// config's [naming] template
+    #[structopt(long, env = "LINODE_NAMING_TEMPLATE")]
+    naming_template: Option<String>,
+
+    /// Zero pad slot numbers to this many digits
+    #[structopt(long)]
+    naming_width: Option<usize>,
+
+    /// Environment every slot name starts with, e.g. "prod"
+    #[structopt(long, env = "LINODE_ENVIRONMENT")]
+    environment: Option<String>,
+
+    #[structopt(subcommand)]
+    action: Action,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LogFormat {
+    Full,
+    Compact,
+    Pretty,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(LogFormat::Full),
+            "compact" => Ok(LogFormat::Compact),
+            "pretty" => Ok(LogFormat::Pretty),
+            _ => Err(format!("unknown log format: {}", s)),
+        }
+    }
+}
+
+// parsed once per run, boxing the big variants buys nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+enum Action {
+    /// Bring an existing instance under the fleet's tag, VLAN and DNS naming
+    Adopt {
+        #[structopt(long)]
+        instance_id: u64,
+
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        region: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+    },
+    /// Call any API endpoint, e.g. `api GET account/settings`, and print the JSON
+    Api {
+        method: Method,
+
+        path: String,
+
+        /// JSON request body
+        #[structopt(long)]
+        data: Option<String>,
+    },
+    Backup(BackupAction),
+    /// Show what it would take to bring the fleet in line with a spec file
+    Diff {
+        #[structopt(short = "f", long = "file", parse(from_os_str))]
+        file: PathBuf,
+
+        /// Print JSON instead of a colorized diff
+        #[structopt(long)]
+        json: bool,
+
+        #[structopt(long)]
+        no_color: bool,
+    },
+    Dns(DnsAction),
+    Events(EventsAction),
+    /// Write a snapshot of a tagged fleet to a JSON file
+    Export {
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        #[structopt(short = "o", long = "output", parse(from_os_str))]
+        output: PathBuf,
+    },
+    Failover(FailoverAction),
+    Firewall(FirewallAction),
+    Fleet(FleetAction),
+    History(HistoryAction),
+    Images(ImagesAction),
+    Instance(InstanceAction),
+    Nb(NbAction),
+    /// Show the API rate limit budget left in the current window
+    Quota,
+    /// Destroy tagged instances, e.g. forgotten image builders, older than an age
+    Reap {
+        #[structopt(long)]
+        tag: String,
+
+        /// e.g. 24h, 7d or 2w
+        #[structopt(long, parse(try_from_str = parse_age))]
+        older_than: Duration,
+
+        /// Also delete private images carrying the tag that no instance uses
+        #[structopt(long)]
+        images: bool,
+
+        /// List what would be removed without removing it
+        #[structopt(long)]
+        dry_run: bool,
+    },
+    Region(RegionAction),
+    /// Retag a fleet and rename its {tag}-{region}-N records
+    RenameFleet {
+        #[structopt(long)]
+        from: String,
+
+        #[structopt(long)]
+        to: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Also relabel VLAN interfaces, rebooting every instance
+        #[structopt(long)]
+        relabel_vlan: bool,
+    },
+    /// Recreate missing instances and DNS records from an export
+    Restore {
+        #[structopt(short = "f", long = "file", parse(from_os_str))]
+        file: PathBuf,
+
+        /// Defaults to the domain the snapshot was taken from
+        #[structopt(long)]
+        domain_id: Option<u64>,
+    },
+    /// Finish or roll back an operation an interrupted run left half done
+    Resume {
+        /// Lists unfinished operations when omitted
+        operation_id: Option<String>,
+
+        /// Domain the operation's nodes are registered in
+        #[structopt(long)]
+        domain_id: Option<u64>,
+
+        /// Undo the steps the operation took instead of finishing it
+        #[structopt(long)]
+        rollback: bool,
+    },
+    Roll(RollAction),
+    /// List region codes, legacy aliases and capabilities
+    Regions {
+        /// Only show regions with all of these, e.g. "vlan,block-storage"
+        #[structopt(long, use_delimiter = true)]
+        capabilities: Vec<String>,
+    },
+    Scale(ScaleAction),
+    Schedule(ScheduleAction),
+    /// Make a firewall rule accept exactly the public (and VLAN) IPs of a tagged fleet
+    SyncAllowlist {
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        firewall_id: u64,
+
+        /// Label of the inbound rule to keep in sync, default "{tag}-fleet"
+        #[structopt(long)]
+        label: Option<String>,
+
+        #[structopt(long, default_value = "TCP")]
+        protocol: String,
+
+        /// Ports for a newly created rule, e.g. "5432"; all ports if omitted
+        #[structopt(long)]
+        ports: Option<String>,
+
+        /// Also allow each instance's VLAN address
+        #[structopt(long)]
+        include_vlan: bool,
+    },
+    /// Instances of a tag joined with DNS names, VLAN IPs and health
+    Status {
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// TCP port to health check on each public IP
+        #[structopt(long)]
+        health_port: Option<u16>,
+
+        #[structopt(long, default_value = "3")]
+        health_timeout: u64,
+
+        /// Print JSON instead of a table
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Check the token is valid and has the scopes scaling needs
+    Verify {
+        /// Print JSON instead of a table
+        #[structopt(long)]
+        json: bool,
+    },
+    /// List, create, attach, detach and resize block storage volumes
+    #[structopt(name = "volume")]
+    Volume(VolumeAction),
+    /// List instance plans with specs and prices, cheapest first
+    Types {
+        /// Price plans for this region
+        #[structopt(long)]
+        region: Option<String>,
+
+        /// Minimum memory in MB
+        #[structopt(long)]
+        min_memory: Option<u64>,
+
+        /// Minimum number of vCPUs
+        #[structopt(long)]
+        min_vcpus: Option<u32>,
+
+        /// Only show plans of this class, e.g. "dedicated"
+        #[structopt(long)]
+        class: Option<String>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum FirewallAction {
+    Ls,
+    Create {
+        #[structopt(long)]
+        label: String,
+
+        #[structopt(long, default_value = "DROP")]
+        inbound_policy: String,
+
+        #[structopt(long, default_value = "ACCEPT")]
+        outbound_policy: String,
+
+        #[structopt(long)]
+        tag: Vec<String>,
+    },
+    Rules(FirewallRulesAction),
+    /// Attach to one instance, or to every instance with a tag
+    Attach {
+        #[structopt(long)]
+        firewall_id: u64,
+
+        #[structopt(long, required_unless = "tag")]
+        instance_id: Option<u64>,
+
+        #[structopt(long)]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum NbAction {
+    Cert(NbCertAction),
+}
+
+#[derive(Debug, StructOpt)]
+enum NbCertAction {
+    /// Upload a new certificate and key to a NodeBalancer's HTTPS configs and confirm it is served
+    Rotate {
+        #[structopt(long)]
+        nb_id: u64,
+
+        /// Only rotate this config; defaults to every HTTPS config
+        #[structopt(long)]
+        config_id: Option<u64>,
+
+        /// PEM certificate, with any intermediates after it
+        #[structopt(long, parse(from_os_str))]
+        cert: PathBuf,
+
+        /// PEM private key
+        #[structopt(long, parse(from_os_str))]
+        key: PathBuf,
+
+        /// Seconds to wait for each rotated port to accept connections
+        #[structopt(long, default_value = "5")]
+        timeout: u64,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum FirewallRulesAction {
+    /// Print the rules as JSON
+    Show {
+        #[structopt(long)]
+        firewall_id: u64,
+    },
+    /// Replace the rules from a JSON file in the format printed by show
+    Set {
+        #[structopt(long)]
+        firewall_id: u64,
+
+        #[structopt(long, parse(from_os_str))]
+        file: PathBuf,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum ImagesAction {
+    /// List images with the number of instances using each
+    Ls {
+        /// Only count instances with this tag as users
+        #[structopt(long)]
+        tag: Option<String>,
+
+        /// Include public distribution images
+        #[structopt(long)]
+        public: bool,
+    },
+    /// Capture an instance disk as a private image
+    Create {
+        #[structopt(long)]
+        instance_id: u64,
+
+        /// Defaults to the instance's largest non-swap disk
+        #[structopt(long)]
+        disk_id: Option<u64>,
+
+        #[structopt(long)]
+        label: String,
+
+        #[structopt(long)]
+        description: Option<String>,
+    },
+    Rm {
+        #[structopt(long)]
+        image_id: String,
+
+        /// Delete even if instances still reference the image
+        #[structopt(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum VolumeAction {
+    Ls {
+        #[structopt(long)]
+        tag: Option<String>,
+
+        #[structopt(long)]
+        region: Option<String>,
+    },
+    Create {
+        #[structopt(long)]
+        label: String,
+
+        /// Size in GB
+        #[structopt(long)]
+        size: u64,
+
+        /// Required unless attaching on create
+        #[structopt(long)]
+        region: Option<String>,
+
+        /// Attach to this instance on create
+        #[structopt(long)]
+        instance_id: Option<u64>,
+
+        #[structopt(long)]
+        tag: Vec<String>,
+    },
+    Attach {
+        #[structopt(long)]
+        volume_id: u64,
+
+        #[structopt(long)]
+        instance_id: u64,
+
+        #[structopt(long)]
+        config_id: Option<u64>,
+    },
+    Detach {
+        #[structopt(long)]
+        volume_id: u64,
+    },
+    Resize {
+        #[structopt(long)]
+        volume_id: u64,
+
+        /// New size in GB, volumes can only grow
+        #[structopt(long)]
+        size: u64,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum InstanceAction {
+    /// Park the instance's DNS slot on the sentinel target, leaving it running
+    Cordon {
+        label: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+    },
+    Ls {
+        /// Tag expression, e.g. "prod & !canary | staging"
+        #[structopt(long)]
+        tag_expr: Option<TagExpr>,
+    },
+    Reboot {
+        #[structopt(long)]
+        instance_id: u64,
+
+        /// Config profile to boot into, defaults to the last booted one
+        #[structopt(long)]
+        config_id: Option<u64>,
+    },
+    /// Rebuild an instance as a new one of the same image and type, swap its DNS slot
+    /// over once healthy and destroy the original
+    Replace {
+        label: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Image to build the replacement from; defaults to the instance's current image
+        #[structopt(long)]
+        image: Option<String>,
+
+        /// TCP port the replacement must accept connections on before the slot moves
+        #[structopt(long)]
+        health_port: Option<u16>,
+    },
+    /// Put a cordoned instance back in its old DNS slot, or the next free one
+    Uncordon {
+        label: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum BackupAction {
+    /// Put every instance of a tag on the same backup day and window
+    Schedule {
+        #[structopt(long)]
+        tag: String,
+
+        /// Weekday, e.g. "Saturday", or "Scheduling" to let Linode pick
+        #[structopt(long)]
+        day: String,
+
+        /// Two hour UTC window by its start, "W0" to "W22"
+        #[structopt(long)]
+        window: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum EventsAction {
+    /// Follow account events and reconcile DNS for tags that instance events touch
+    Watch {
+        #[structopt(long)]
+        domain_id: u64,
+
+        #[structopt(long = "tag", required = true, number_of_values = 1)]
+        tags: Vec<String>,
+
+        /// Seconds between polls of the event stream
+        #[structopt(long, default_value = "30")]
+        interval: u64,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum HistoryAction {
+    /// Instances and DNS records added, removed or changed between two
+    /// snapshots in the state cache's history
+    Diff {
+        /// A date ("2024-06-01"), date and time ("2024-06-01T12:00:00", UTC)
+        /// or an age ("7d")
+        #[structopt(long)]
+        since: String,
+
+        /// Defaults to the latest snapshot
+        #[structopt(long)]
+        until: Option<String>,
+
+        #[structopt(long)]
+        json: bool,
+    },
+    /// List every instance and domain record and add them to the history as a snapshot
+    Snapshot,
+}
+
+#[derive(Debug, StructOpt)]
+enum RollAction {
+    /// How many of a fleet's instances run each image per region, and the
+    /// DNS slots they hold, e.g. to see how far a rolling upgrade got
+    Status {
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Print JSON instead of a table
+        #[structopt(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum FleetAction {
+    /// Allocated vs plan disk per instance, flagging any without room for an image upgrade
+    Disks {
+        #[structopt(long)]
+        tag: String,
+
+        /// Image to check room for; defaults to each instance's current image
+        #[structopt(long)]
+        image: Option<String>,
+
+        /// Print JSON instead of a table
+        #[structopt(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum FailoverAction {
+    /// Replace a region's fleet with one in another region and move its DNS names over
+    Promote {
+        #[structopt(long)]
+        from: String,
+
+        #[structopt(long)]
+        to: String,
+
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Defaults to each origin instance's image
+        #[structopt(long)]
+        image_id: Option<String>,
+
+        /// Defaults to each origin instance's type
+        #[structopt(long)]
+        instance_type: Option<String>,
+
+        /// TCP port the replacements must accept connections on before DNS moves
+        #[structopt(long)]
+        health_port: Option<u16>,
+
+        /// Seconds to wait for the replacements to become healthy
+        #[structopt(long, default_value = "600")]
+        health_wait: u64,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum RegionAction {
+    /// Cordon every instance of a tag in a region, optionally replacing them elsewhere first
+    Drain {
+        #[structopt(long)]
+        region: String,
+
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Scale up as many replacements in this region before cordoning
+        #[structopt(long, requires_all = &["image-id", "instance-type"])]
+        replace_in: Option<String>,
+
+        #[structopt(long)]
+        image_id: Option<String>,
+
+        #[structopt(long)]
+        instance_type: Option<String>,
+    },
+    /// Image each instance of a tag, recreate it in another region in the same DNS slot
+    /// and destroy the original
+    Evacuate {
+        #[structopt(long)]
+        from: String,
+
+        #[structopt(long)]
+        to: String,
+
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Keep the images taken of each instance
+        #[structopt(long)]
+        keep_images: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum ScheduleAction {
+    /// Tear down or rebuild every scheduled fleet in a spec file as its schedule says; run from cron
+    Run {
+        #[structopt(short = "f", long = "file", parse(from_os_str))]
+        file: PathBuf,
+
+        /// Only this fleet
+        #[structopt(long)]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum DnsAction {
+    /// Copy a zone and its records to a new domain
+    Clone {
+        #[structopt(long)]
+        domain_id: u64,
+
+        #[structopt(long)]
+        new_domain: String,
+
+        /// Rewrite records targeting OLD to NEW in the copy, e.g. "192.0.2.1=198.51.100.1"
+        #[structopt(long = "map-target", number_of_values = 1)]
+        map_targets: Vec<String>,
+    },
+    /// Create a Linode domain from a zone transferred (AXFR) from another provider
+    Import {
+        /// Nameserver to transfer from, "host" or "host:port"
+        #[structopt(long)]
+        axfr: String,
+
+        #[structopt(long)]
+        zone: String,
+
+        /// Defaults to the contact in the zone's SOA
+        #[structopt(long)]
+        soa_email: Option<String>,
+    },
+    /// Probe the A records of managed prefixes and export their reachability,
+    /// TTL and last change age as metrics, to --metrics-file or stdout
+    Health {
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Record name prefix, e.g. "api" or "api-us-iad"; repeatable
+        #[structopt(long = "prefix", required = true, number_of_values = 1)]
+        prefixes: Vec<String>,
+
+        /// TCP port to probe each target on; without it targets aren't probed
+        #[structopt(long)]
+        port: Option<u16>,
+
+        #[structopt(long, default_value = "3")]
+        timeout: u64,
+    },
+    Ls {
+        #[structopt(long)]
+        domain_id: u64,
+    },
+    /// Park {tag}-{region}-N slots left pointing at instances that no longer exist
+    Reconcile {
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Also keep the slots in this domain, e.g. another environment's
+        /// zone; adds to the config's [domains] for the tag
+        #[structopt(long, number_of_values = 1)]
+        mirror_domain_id: Vec<u64>,
+
+        #[structopt(long)]
+        tag: String,
+    },
+    /// Point {tag}.{group} (e.g. api.eu) at every live slot of the tag in the group's regions
+    RegionNames {
+        #[structopt(long)]
+        domain_id: u64,
+
+        #[structopt(long)]
+        tag: String,
+    },
+    /// Show or change a zone's SOA email, timers and default TTL
+    Settings {
+        #[structopt(long)]
+        domain_id: u64,
+
+        #[structopt(long)]
+        soa_email: Option<String>,
+
+        /// Seconds, rounded by the API to a supported value
+        #[structopt(long)]
+        refresh: Option<u32>,
+
+        #[structopt(long)]
+        retry: Option<u32>,
+
+        #[structopt(long)]
+        expire: Option<u32>,
+
+        /// Default TTL for records without one
+        #[structopt(long)]
+        ttl: Option<u32>,
+    },
+    /// Point a record at a target, creating it if it doesn't exist
+    Upsert {
+        #[structopt(long)]
+        domain_id: u64,
+
+        #[structopt(long)]
+        name: String,
+
+        #[structopt(long = "type", default_value = "A")]
+        record_type: String,
+
+        #[structopt(long)]
+        target: String,
+
+        #[structopt(long, default_value = "30")]
+        ttl: i32,
+    },
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+enum ScaleAction {
+    Up {
+        /// Named profile from the config file supplying the tag, image, type,
+        /// subnet, firewall, user-data template and health check
+        #[structopt(long)]
+        profile: Option<String>,
+
+        /// Defaults to the profile's image
+        #[structopt(long)]
+        image_id: Option<String>,
+
+        /// Defaults to the linode-cli type
+        #[structopt(long, env = "LINODE_TYPE")]
+        instance_type: Option<String>,
+
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Also keep the slots in this domain, e.g. another environment's
+        /// zone; adds to the config's [domains] for the tag
+        #[structopt(long, number_of_values = 1)]
+        mirror_domain_id: Vec<u64>,
+
+        /// Region, comma separated regions, or @group (one pass per region);
+        /// defaults to a region picked per instance by the config's
+        /// region_weights, then the linode-cli region
+        #[structopt(long, env = "LINODE_REGION")]
+        region: Option<String>,
+
+        /// Defaults to the profile's tag
+        #[structopt(long)]
+        tag: Option<String>,
+
+        #[structopt(long, default_value = "1")]
+        n: u32,
+
+        /// Regions or @groups to retry in, in order, when a region is out
+        /// of capacity; defaults to the profile's fallback_regions
+        #[structopt(long, use_delimiter = true)]
+        fallback_regions: Vec<String>,
+
+        /// Regenerate the {tag}.{group} round-robin names afterwards
+        #[structopt(long)]
+        region_names: bool,
+
+        /// VLAN /24 for the fleet, e.g. "10.8.0.0/24"; defaults to 10.0.0.0/24
+        #[structopt(long)]
+        vlan_subnet: Option<String>,
+
+        /// Firewall to attach each new instance to
+        #[structopt(long)]
+        firewall_id: Option<u64>,
+
+        /// TCP port each new node must accept connections on before it gets DNS
+        #[structopt(long)]
+        health_port: Option<u16>,
+
+        /// minijinja user-data template rendered per instance
+        #[structopt(long, parse(from_os_str), conflicts_with = "user-data-file")]
+        user_data_template: Option<PathBuf>,
+
+        /// user-data file, gzipped if it exceeds the API size limit
+        #[structopt(long, parse(from_os_str))]
+        user_data_file: Option<PathBuf>,
+
+        /// Swap disk size in MB
+        #[structopt(long)]
+        swap_size: Option<u32>,
+
+        /// Shutdown and boot instead of rebooting after interface changes
+        #[structopt(long)]
+        power_cycle: bool,
+
+        /// Seconds to wait for each boot to reach running
+        #[structopt(long, default_value = "600")]
+        boot_timeout: u64,
+
+        /// Publish an SRV record _<service>._tcp.<tag> for each new node
+        #[structopt(long, requires = "srv-port")]
+        srv_service: Option<String>,
+
+        /// Port advertised in the SRV record
+        #[structopt(long, requires = "srv-service")]
+        srv_port: Option<u16>,
+
+        #[structopt(long, default_value = "tcp")]
+        srv_protocol: String,
+
+        #[structopt(long, default_value = "10")]
+        srv_weight: i32,
+
+        /// Write a TXT record with instance ID, image and creation time
+        #[structopt(long)]
+        instance_record: bool,
+
+        /// Refuse to scale once the tag would cost more than this many USD a month
+        #[structopt(long)]
+        monthly_budget: Option<f64>,
+
+        /// Print a summary of added nodes, step timings and failures as JSON
+        #[structopt(long)]
+        json: bool,
+
+        /// Keep adding instances after one fails instead of stopping
+        #[structopt(long)]
+        keep_going: bool,
+
+        /// Command run over ssh on each new node before it is registered
+        #[structopt(long, conflicts_with = "provision-callback")]
+        provision_ssh: Option<String>,
+
+        #[structopt(long, default_value = "root")]
+        provision_ssh_user: String,
+
+        /// URL POSTed the new node's ID, address and identity before it is registered
+        #[structopt(long)]
+        provision_callback: Option<String>,
+    },
+    Down {
+        #[structopt(long)]
+        domain_id: u64,
+
+        /// Also keep the slots in this domain, e.g. another environment's
+        /// zone; adds to the config's [domains] for the tag
+        #[structopt(long, number_of_values = 1)]
+        mirror_domain_id: Vec<u64>,
+
+        /// Region, comma separated regions, or @group (one pass per region);
+        /// defaults to the linode-cli region
+        #[structopt(long, env = "LINODE_REGION")]
+        region: Option<String>,
+
+        #[structopt(long)]
+        tag: String,
+
+        #[structopt(long, default_value = "1")]
+        n: u32,
+
+        /// per-region removes n from each region; most-instances and
+        /// least-load remove n in total from the fullest or least busy regions
+        #[structopt(long, default_value = "per-region")]
+        balance: ScaleDownPolicy,
+
+        /// Regenerate the {tag}.{group} round-robin names afterwards
+        #[structopt(long)]
+        region_names: bool,
+
+        /// Print the removed nodes and their step timings as JSON
+        #[structopt(long)]
+        json: bool,
+    },
+}
+
+// progress lines go to stderr when stdout carries --json output
+fn progress(json: bool, line: String) {
+    if json {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+async fn sync_region_names(
+    client: &LinodeClient,
+    domain_id: u64,
+    tag: &str,
+    groups: &RegionGroups,
+    json: bool,
+) -> Result<(), LinodeError> {
+    for sync in client.sync_region_names(domain_id, tag, groups).await? {
+        for target in &sync.added {
+            progress(json, format!("{} +{}", sync.name, target));
+        }
+        for target in &sync.removed {
+            progress(json, format!("{} -{}", sync.name, target));
+        }
+    }
+    Ok(())
+}
+
+// keep the config's allowlists for tag in step after a scale event; a
+// failure is reported but doesn't fail the scale
+async fn sync_allowlists(client: &LinodeClient, allowlists: &[Allowlist], tag: &str, json: bool) {
+    for allowlist in allowlists.iter().filter(|allowlist| allowlist.tag == tag) {
+        match client.sync_allowlist(allowlist).await {
+            Ok(sync) => print_allowlist_sync(allowlist, &sync, json),
+            Err(e) => eprintln!(
+                "Failed to sync allowlist {} on firewall {}: {}",
+                allowlist.rule_label(),
+                allowlist.firewall_id,
+                e
+            ),
+        }
+    }
+}
+
+fn print_allowlist_sync(allowlist: &Allowlist, sync: &AllowlistSync, json: bool) {
+    let label = allowlist.rule_label();
+    if sync.created {
+        progress(
+            json,
+            format!("{} created on firewall {}", label, allowlist.firewall_id),
+        );
+    }
+    for address in &sync.added {
+        progress(json, format!("{} +{}", label, address));
+    }
+    for address in &sync.removed {
+        progress(json, format!("{} -{}", label, address));
+    }
+}
+
+fn print_cert_rotations(rotations: &[CertRotation]) {
+    println!("config_id\tport\tcommon_name\tfingerprint\tchanged");
+    for rotation in rotations {
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            rotation.config_id,
+            rotation.port,
+            rotation.common_name.as_deref().unwrap_or("-"),
+            rotation.fingerprint,
+            !rotation.is_unchanged()
+        );
+    }
+}
+
+fn print_reap_report(report: &ReapReport, dry_run: bool) {
+    let verb = if dry_run { "would reap" } else { "reaped" };
+    for (kind, reaped) in [("instance", &report.instances), ("image", &report.images)] {
+        for r in reaped {
+            println!(
+                "{} {}\t{}\t{}\tcreated {} ({}h old)",
+                verb,
+                kind,
+                r.id,
+                r.label,
+                r.created,
+                r.age_seconds / 3_600
+            );
+        }
+    }
+    for (label, error) in &report.failed {
+        eprintln!("failed {}: {}", label, error);
+    }
+}
+
+fn print_token_report(report: &TokenReport) {
+    if !report.valid {
+        println!("token\tinvalid");
+        return;
+    }
+    println!("user\t{}", report.username.as_deref().unwrap_or("-"));
+    if report.restricted {
+        println!("restricted\tyes, grants may narrow the scopes");
+    }
+    println!("label\t{}", report.label.as_deref().unwrap_or("-"));
+    match &report.scopes {
+        Some(scopes) => println!("scopes\t{}", scopes.join(" ")),
+        None => println!("scopes\tunknown"),
+    }
+    println!("expiry\t{}", report.expiry.as_deref().unwrap_or("never"));
+    for scope in &report.missing {
+        println!("missing\t{}", scope);
+    }
+}
+
+fn stops_gracefully(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::Scale(_)
+            | Action::Events(EventsAction::Watch { .. })
+            | Action::Region(RegionAction::Evacuate { .. })
+            | Action::Region(RegionAction::Drain { .. })
+            | Action::Failover(FailoverAction::Promote { .. })
+            | Action::Schedule(ScheduleAction::Run { .. })
+    )
+}
+
+struct LockArgs {
+    disabled: bool,
+    ttl: Duration,
+}
+
+impl LockArgs {
+    // for with_fleet_locks, None under --no-lock
+    fn fleet_ttl(&self) -> Option<Duration> {
+        (!self.disabled).then_some(self.ttl)
+    }
+}
+
+async fn acquire_lock(
+    client: &LinodeClient,
+    args: &LockArgs,
+    domain: u64,
+    tag: &str,
+    region_code: &str,
+) -> Result<Option<ScaleLock>, LinodeError> {
+    if args.disabled {
+        return Ok(None);
+    }
+    let lock = client
+        .acquire_lock(domain, &lock_name(tag, region_code), args.ttl)
+        .await?;
+    Ok(Some(lock))
+}
+
+// between the instances of a long run, so the lock outlives --lock-ttl
+async fn refresh_lock(
+    client: &LinodeClient,
+    args: &LockArgs,
+    lock: Option<&mut ScaleLock>,
+) -> Result<(), LinodeError> {
+    if let Some(lock) = lock {
+        client.refresh_lock(lock, args.ttl).await?;
+    }
+    Ok(())
+}
 
-    #[structopt(long, env = "LINODE_PUB_KEY")]
-    pub_key: String,
+async fn release_lock(client: &LinodeClient, lock: Option<ScaleLock>) -> Result<(), LinodeError> {
+    if let Some(lock) = lock {
+        client.release_lock(&lock).await?;
+    }
+    Ok(())
+}
 
-    #[structopt(subcommand)]
-    action: Action,
+// What every instance of a scale up is created with, whichever region it
+// lands in.
+struct ScaleUpJob<'a> {
+    image_id: &'a str,
+    instance_type: &'a str,
+    domain_id: u64,
+    tag: &'a str,
+    options: &'a ScaleUpOptions,
+    // progress to stderr, see progress
+    json: bool,
 }
 
-#[derive(Debug, StructOpt)]
-enum Action {
-    Dns(DnsAction),
-    Scale(ScaleAction),
+// One instance in region_info under that region's lock, named with its
+// {tag}-{code}-N prefix.
+async fn scale_up_locked(
+    client: &LinodeClient,
+    args_lock: &LockArgs,
+    region_info: &RegionInfo,
+    job: &ScaleUpJob<'_>,
+) -> Result<ScaledNode, LinodeError> {
+    let lock = acquire_lock(client, args_lock, job.domain_id, job.tag, region_info.code).await?;
+    let result = client
+        .scale_up_one_with(
+            job.image_id,
+            job.instance_type,
+            job.domain_id,
+            region_info,
+            job.tag,
+            job.options,
+        )
+        .await;
+    // the lock expires on its own, so a failed release doesn't undo the node
+    if let Err(e) = release_lock(client, lock).await {
+        eprintln!(
+            "Failed to release lock for region {}: {}",
+            region_info.code, e
+        );
+    }
+    result
 }
 
-#[derive(Debug, StructOpt)]
-enum DnsAction {
-    Ls {
-        #[structopt(long)]
-        domain_id: u64,
-    },
+// Scale up one instance in a region picked by weight. A region that fails
+// with a capacity error is left out for the rest of the run and another
+// is picked, until none are left.
+async fn scale_up_weighted(
+    client: &LinodeClient,
+    args_lock: &LockArgs,
+    picker: &mut RegionPicker,
+    job: &ScaleUpJob<'_>,
+) -> Result<ScaledNode, ScaleFailure> {
+    loop {
+        let Some(region_info) = picker.pick() else {
+            return Err(ScaleFailure {
+                region: "-".to_owned(),
+                iteration: 0,
+                error: "no weighted region left with capacity".to_owned(),
+            });
+        };
+        match scale_up_locked(client, args_lock, region_info, job).await {
+            Ok(node) => {
+                progress(
+                    job.json,
+                    format!("Scaled up 1 instance in region: {}", region_info.region),
+                );
+                return Ok(node);
+            }
+            Err(e) if is_capacity_error(&e) => {
+                eprintln!(
+                    "Region {} has no capacity, picking another: {}",
+                    region_info.region, e
+                );
+                picker.exclude(region_info.region);
+            }
+            Err(e) => {
+                return Err(ScaleFailure {
+                    region: region_info.region.to_owned(),
+                    iteration: 0,
+                    error: e.to_string(),
+                })
+            }
+        }
+    }
 }
 
-#[derive(Debug, StructOpt)]
-enum ScaleAction {
-    Up {
-        #[structopt(long)]
-        image_id: String,
+// After full_region answered with a capacity error, try the fallback
+// regions in order until one has room. When none do the last capacity
+// error is returned.
+async fn scale_up_fallback(
+    client: &LinodeClient,
+    args_lock: &LockArgs,
+    fallbacks: &[&'static RegionInfo],
+    full_region: &RegionInfo,
+    job: &ScaleUpJob<'_>,
+    err: LinodeError,
+) -> Result<ScaledNode, LinodeError> {
+    let mut err = err;
+    let mut full = full_region.region;
+    for region_info in fallbacks.iter().filter(|r| r.region != full_region.region) {
+        eprintln!(
+            "Region {} has no capacity, retrying in {}: {}",
+            full, region_info.region, err
+        );
+        match scale_up_locked(client, args_lock, region_info, job).await {
+            Ok(node) => {
+                progress(
+                    job.json,
+                    format!(
+                        "Scaled up 1 instance in fallback region: {}",
+                        region_info.region
+                    ),
+                );
+                return Ok(node);
+            }
+            Err(e) if is_capacity_error(&e) => {
+                err = e;
+                full = region_info.region;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(err)
+}
 
-        #[structopt(long)]
-        instance_type: String,
+async fn list_regions(client: &LinodeClient, capabilities: &[String]) {
+    match client.fetch_regions().await {
+        Ok(mut regions) => {
+            regions.retain(|r| capabilities.iter().all(|c| r.has_capability(c)));
+            regions.sort_by(|a, b| a.id.cmp(&b.id));
+            for region in &regions {
+                let (code, legacy) = match REGIONS.get(region.id.as_str()) {
+                    Some(info) => (info.code, if info.is_legacy { "legacy" } else { "" }),
+                    None => (region.id.as_str(), ""),
+                };
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    code,
+                    region.id,
+                    legacy,
+                    region.status,
+                    region.capabilities.join(",")
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to fetch regions, using the static map: {}", e);
+            if !capabilities.is_empty() {
+                eprintln!("Capabilities are unknown offline, not filtering.");
+            }
+            let mut regions = REGIONS.values().collect::<Vec<_>>();
+            regions.sort_by(|a, b| a.region.cmp(b.region));
+            for info in regions {
+                println!(
+                    "{}\t{}\t{}",
+                    info.code,
+                    info.region,
+                    if info.is_legacy { "legacy" } else { "" }
+                );
+            }
+        }
+    }
+}
 
-        #[structopt(long)]
-        domain_id: u64,
+fn print_diff(diff: &FleetDiff, color: bool) {
+    if diff.is_empty() {
+        println!("No changes, the fleet matches the spec.");
+        return;
+    }
+    for e in &diff.entries {
+        let (sign, ansi) = match e.op {
+            DiffOp::Add => ("+", "\x1b[32m"),
+            DiffOp::Remove => ("-", "\x1b[31m"),
+            DiffOp::Change => ("~", "\x1b[33m"),
+        };
+        let kind = match e.kind {
+            DiffKind::Instance => "instance",
+            DiffKind::Dns => "dns",
+            DiffKind::Interface => "interface",
+        };
+        let line = format!(
+            "{} {:<9} {}/{} {}: {}",
+            sign, kind, e.tag, e.region, e.subject, e.detail
+        );
+        if color {
+            println!("{}{}\x1b[0m", ansi, line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
 
-        #[structopt(long)]
-        region: String,
+fn print_fleet(members: &[FleetMember]) {
+    println!("id\tlabel\tregion\tstatus\tpublic_ip\tvlan_ip\tdns\thealth");
+    for m in members {
+        let health = match &m.health {
+            Some(h) if h.healthy => "ok".to_owned(),
+            Some(h) => h.error.clone().unwrap_or_else(|| "failed".to_owned()),
+            None => "-".to_owned(),
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            m.instance_id,
+            m.label,
+            m.region,
+            m.status,
+            m.public_ip.as_deref().unwrap_or("-"),
+            m.vlan_ip.as_deref().unwrap_or("-"),
+            if m.dns_names.is_empty() {
+                "-".to_owned()
+            } else {
+                m.dns_names.join(",")
+            },
+            health
+        );
+    }
+}
 
-        #[structopt(long)]
-        tag: String,
+fn print_roll_status(status: &RollStatus) {
+    println!("region\timage\tinstances\tslots");
+    for region in &status.regions {
+        for rollout in &region.images {
+            println!(
+                "{}\t{}\t{}\t{}",
+                region.region,
+                rollout.image.as_deref().unwrap_or("-"),
+                rollout.instance_ids.len(),
+                if rollout.slots.is_empty() {
+                    "-".to_owned()
+                } else {
+                    rollout.slots.join(",")
+                }
+            );
+        }
+    }
+    for region in status.regions.iter().filter(|region| region.is_mixed()) {
+        println!(
+            "{} is mid-roll across {} images",
+            region.region,
+            region.images.len()
+        );
+    }
+}
 
-        #[structopt(long, default_value = "1")]
-        n: u32,
-    },
-    Down {
-        #[structopt(long)]
-        domain_id: u64,
+fn print_disk_usage(usage: &[DiskUsage]) {
+    println!("id\tlabel\tregion\ttype\tdisks\tallocated_mb\tplan_mb\tfree_mb\timage\tupgrade");
+    for u in usage {
+        let upgrade = match (u.fits_upgrade, u.image_size) {
+            (Some(true), _) => "ok".to_owned(),
+            (Some(false), Some(size)) => format!("NO ROOM (needs {}MB)", size),
+            _ => "-".to_owned(),
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            u.instance_id,
+            u.label,
+            u.region,
+            u.instance_type,
+            u.disks,
+            u.allocated,
+            u.plan,
+            u.free,
+            u.image.as_deref().unwrap_or("-"),
+            upgrade
+        );
+    }
+}
 
-        #[structopt(long)]
-        region: String,
+async fn firewall(
+    client: &LinodeClient,
+    action: FirewallAction,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match action {
+        FirewallAction::Ls => {
+            let firewalls = client.fetch_firewalls(&ListOptions::default()).await?;
+            println!("id\tlabel\tstatus\tinbound\toutbound\ttags");
+            for fw in &firewalls {
+                println!(
+                    "{}\t{}\t{}\t{} rules ({})\t{} rules ({})\t{}",
+                    fw.id,
+                    fw.label,
+                    fw.status,
+                    fw.rules.inbound.len(),
+                    fw.rules.inbound_policy,
+                    fw.rules.outbound.len(),
+                    fw.rules.outbound_policy,
+                    fw.tags.join(",")
+                );
+            }
+        }
+        FirewallAction::Create {
+            label,
+            inbound_policy,
+            outbound_policy,
+            tag,
+        } => {
+            let rules = FirewallRules {
+                inbound: Vec::new(),
+                inbound_policy,
+                outbound: Vec::new(),
+                outbound_policy,
+            };
+            let fw = client.create_firewall(&label, rules, tag).await?;
+            println!("Created firewall ID: {} ({})", fw.id, fw.label);
+        }
+        FirewallAction::Rules(FirewallRulesAction::Show { firewall_id }) => {
+            let rules = client.fetch_firewall_rules(firewall_id).await?;
+            println!("{}", serde_json::to_string_pretty(&rules)?);
+        }
+        FirewallAction::Rules(FirewallRulesAction::Set { firewall_id, file }) => {
+            let rules: FirewallRules = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+            client.set_firewall_rules(firewall_id, &rules).await?;
+            println!(
+                "Set {} inbound and {} outbound rules on firewall ID: {}",
+                rules.inbound.len(),
+                rules.outbound.len(),
+                firewall_id
+            );
+        }
+        FirewallAction::Attach {
+            firewall_id,
+            instance_id,
+            tag,
+        } => {
+            let mut ids = instance_id.into_iter().collect::<Vec<_>>();
+            if let Some(tag) = &tag {
+                let instances = client.get_instances_by_tag(vec![tag]).await?;
+                ids.extend(instances.iter().map(|i| i.id));
+            }
+            let attached = client
+                .fetch_firewall_devices(firewall_id)
+                .await?
+                .into_iter()
+                .map(|d| d.entity.id)
+                .collect::<Vec<_>>();
+            for id in ids.into_iter().filter(|id| !attached.contains(id)) {
+                client.attach_firewall(firewall_id, id).await?;
+                println!(
+                    "Attached firewall ID: {} to instance ID: {}",
+                    firewall_id, id
+                );
+            }
+        }
+    }
+    Ok(())
+}
 
-        #[structopt(long)]
-        tag: String,
+async fn images(
+    client: &LinodeClient,
+    action: ImagesAction,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match action {
+        ImagesAction::Ls { tag, public } => {
+            let mut images = client.fetch_images(&ListOptions::default()).await?;
+            images.retain(|image| public || !image.is_public);
+            let instances = match &tag {
+                Some(tag) => client.get_instances_by_tag(vec![tag]).await?,
+                None => client.fetch_instances().await?,
+            };
 
-        #[structopt(long, default_value = "1")]
-        n: u32,
-    },
+            println!("id\tlabel\tsize\tstatus\tcreated\tin_use");
+            for image in &images {
+                let in_use = instances
+                    .iter()
+                    .filter(|i| i.image.as_ref() == Some(&image.id))
+                    .count();
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    image.id, image.label, image.size, image.status, image.created, in_use
+                );
+            }
+        }
+        ImagesAction::Create {
+            instance_id,
+            disk_id,
+            label,
+            description,
+        } => {
+            let disk_id = match disk_id {
+                Some(id) => id,
+                None => client
+                    .fetch_instance_disks(instance_id)
+                    .await?
+                    .into_iter()
+                    .filter(|d| d.filesystem != "swap")
+                    .max_by_key(|d| d.size)
+                    .map(|d| d.id)
+                    .ok_or_else(|| format!("Instance ID: {} has no disks", instance_id))?,
+            };
+            let image = client.create_image(disk_id, &label, description).await?;
+            println!("Created image: {} ({})", image.id, image.status);
+        }
+        ImagesAction::Rm { image_id, force } => {
+            let users = client
+                .fetch_instances()
+                .await?
+                .into_iter()
+                .filter(|i| i.image.as_ref() == Some(&image_id))
+                .map(|i| i.label)
+                .collect::<Vec<_>>();
+            if !users.is_empty() && !force {
+                return Err(format!(
+                    "Image {} is used by: {} (pass --force to delete anyway)",
+                    image_id,
+                    users.join(", ")
+                )
+                .into());
+            }
+            client.delete_image(&image_id).await?;
+            println!("Deleted image: {}", image_id);
+        }
+    }
+    Ok(())
+}
+
+async fn volumes(
+    client: &LinodeClient,
+    action: VolumeAction,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match action {
+        VolumeAction::Ls { tag, region } => {
+            let region = region
+                .as_deref()
+                .map(|r| find_region(r).map_or(r, |i| i.region));
+            let volumes = client
+                .fetch_volumes(&volume_filter(tag.as_deref(), region))
+                .await?;
+            println!("id\tlabel\tregion\tsize\tstatus\tinstance\ttags");
+            for v in &volumes {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    v.id,
+                    v.label,
+                    v.region,
+                    v.size,
+                    v.status,
+                    v.linode_label.as_deref().unwrap_or("-"),
+                    v.tags.join(",")
+                );
+            }
+        }
+        VolumeAction::Create {
+            label,
+            size,
+            region,
+            instance_id,
+            tag,
+        } => {
+            let options = VolumeCreateOptions {
+                label,
+                size,
+                region: region.map(|r| find_region(&r).map_or(r, |i| i.region.to_owned())),
+                linode_id: instance_id,
+                tags: tag,
+            };
+            let volume = client.create_volume(&options).await?;
+            println!("Created volume ID: {} ({})", volume.id, volume.status);
+        }
+        VolumeAction::Attach {
+            volume_id,
+            instance_id,
+            config_id,
+        } => {
+            client
+                .attach_volume(volume_id, instance_id, config_id)
+                .await?;
+            println!(
+                "Attached volume ID: {} to instance ID: {}",
+                volume_id, instance_id
+            );
+        }
+        VolumeAction::Detach { volume_id } => {
+            client.detach_volume(volume_id).await?;
+            println!("Detached volume ID: {}", volume_id);
+        }
+        VolumeAction::Resize { volume_id, size } => {
+            client.resize_volume(volume_id, size).await?;
+            println!("Resized volume ID: {} to {}GB", volume_id, size);
+        }
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
-    let subscriber = tracing_subscriber::registry()
-        .with(EnvFilter::new("info"))
-        .with(tracing_subscriber::fmt::Layer::default());
+    let args = Command::from_args();
+
+    // --log-level wins, then RUST_LOG, then info
+    let mut filter = match &args.log_level {
+        Some(level) => EnvFilter::try_new(level)?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    };
+    if args.trace_http {
+        filter = filter.add_directive("linode=debug".parse()?);
+    }
+    // secrets are scrubbed from every line, whatever logged them; logs go
+    // to stderr so stdout is only command output, e.g. --json
+    let layer =
+        tracing_subscriber::fmt::Layer::default().with_writer(RedactingWriter(std::io::stderr));
+    let layer = match args.log_format {
+        LogFormat::Full => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+    };
+    let subscriber = tracing_subscriber::registry().with(filter).with(layer);
 
     tracing::subscriber::set_global_default(subscriber)
         .expect("failed to set global default subscriber");
 
-    let args = Command::from_args();
+    let config = Config::load(args.config.as_deref())?;
+    let account = config
+        .account(args.account.as_deref())?
+        .cloned()
+        .unwrap_or_default();
+    let mut cli_defaults = LinodeCliConfig::load(args.as_user.as_deref());
+    // an account's settings come before linode-cli's
+    cli_defaults.region = account.region.clone().or(cli_defaults.region);
+    // the account's token file only stands in when no token or token source
+    // was given on the command line
+    let token_file = args.token_file.clone().or_else(|| {
+        if args.token.is_some() || args.token_keychain.is_some() {
+            None
+        } else {
+            account.token_file.clone()
+        }
+    });
+    let token_source = token_file.is_some() || args.token_keychain.is_some();
+    let token = match args
+        .token
+        .map(SecretString::new)
+        .or(account.token)
+        .or(cli_defaults.token)
+    {
+        Some(token) => token.expose().to_owned(),
+        // nothing is sent offline, and a token source replaces the token
+        None if args.offline || token_source => String::new(),
+        None => {
+            return Err(
+                "No API token: pass --token, set LINODE_API_TOKEN or configure linode-cli".into(),
+            )
+        }
+    };
+    let pub_key = args
+        .pub_key
+        .or(account.pub_key)
+        .ok_or("No pub key: pass --pub-key, set LINODE_PUB_KEY or use an --account")?;
+    if args.offline
+        && !matches!(
+            args.action,
+            Action::Instance(InstanceAction::Ls { .. })
+                | Action::Status { .. }
+                | Action::Roll(RollAction::Status { .. })
+                | Action::Diff { .. }
+                | Action::History(HistoryAction::Diff { .. })
+        )
+    {
+        return Err(
+            "--offline only answers instance ls, status, roll status, diff and history diff".into(),
+        );
+    }
+    let retry_delay = Duration::from_millis(args.retry_delay_ms);
+    let retry_max_delay = Duration::from_millis(args.retry_max_delay_ms);
+    let mut builder = LinodeClient::builder(token, pub_key)
+        .timeout(Duration::from_secs(args.request_timeout))
+        .connect_timeout(Duration::from_secs(10))
+        .user_agent(concat!("linode/", env!("CARGO_PKG_VERSION")));
+    if let Some(api_url) = args.api_url.as_ref().or(account.api_url.as_ref()) {
+        builder = builder.base_url(api_url);
+    }
+    if let Some(version) = args.api_version {
+        builder = builder.api_version(version);
+    }
+    if let Some(proxy) = &args.proxy {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &token_file {
+        builder = builder.token_source(FileToken::new(path));
+    } else if let Some(entry) = &args.token_keychain {
+        let (service, account) = entry.split_once(':').unwrap_or((entry, "api-token"));
+        builder = builder.token_source(KeychainToken::new(service, account));
+    }
+    let mut client = builder
+        .build()?
+        .with_verify_writes(args.verify_writes)
+        .with_record_cache(true)
+        .with_trace_http(args.trace_http)
+        .with_retry_policy(
+            RetryPolicy::default()
+                .with_max_attempts(args.max_attempts)
+                .with_delay(retry_delay, retry_max_delay),
+        )
+        .with_create_retry_policy(RetryPolicy::creates().with_delay(retry_delay, retry_max_delay))
+        .with_max_in_flight(args.max_in_flight);
+    #[cfg(feature = "chaos")]
+    if let Some(spec) = &args.chaos {
+        if args.api_url.is_none() {
+            return Err("--chaos only runs against a mock API, pass --api-url".into());
+        }
+        client = client.with_chaos(Chaos::parse(spec, args.chaos_seed)?);
+    }
+    if let Some(ttl) = args.response_cache_ttl {
+        client = client.with_response_cache(Duration::from_secs(ttl));
+    }
+    if let Some(limit) = args.max_api_calls {
+        client = client.with_max_api_calls(limit);
+    }
+    if let Some(path) = &args.record {
+        client = client.with_cassette(Cassette::record(path));
+    } else if let Some(path) = &args.replay {
+        client = client.with_cassette(Cassette::replay(path)?);
+    }
+    match args.state_cache.clone().or_else(state::default_path) {
+        Some(path) if args.offline => client = client.with_state_cache(StateCache::offline(path)?),
+        Some(path) => client = client.with_state_cache(StateCache::open(path)),
+        None if args.offline => return Err("--offline needs a state cache path".into()),
+        None => {}
+    }
+    let journal = args
+        .journal_dir
+        .clone()
+        .or_else(journal::default_dir)
+        .map(Journal::open);
+    if let Some(journal) = &journal {
+        client = client.with_journal(journal.clone());
+    }
+    let mut region_groups = RegionGroups::default();
+    for spec in &args.region_group {
+        region_groups.insert_override(spec)?;
+    }
+    let hooks = config.hooks.clone();
+    let mut naming = config.naming.clone();
+    naming.template = args.naming_template.clone().or(naming.template);
+    naming.width = args.naming_width.unwrap_or(naming.width);
+    naming.environment = args.environment.clone().or(naming.environment);
+    client = client.with_naming(naming.scheme()?);
+    let args_lock = LockArgs {
+        disabled: args.no_lock,
+        ttl: Duration::from_secs(args.lock_ttl),
+    };
+
+    // fail before the command rather than halfway through it
+    let online = !args.offline && args.replay.is_none();
+    if args.verify_token && online && !matches!(args.action, Action::Verify { .. }) {
+        let report = client.verify().await?;
+        if !report.is_ok() {
+            print_token_report(&report);
+            return Err("the token can't be used for scaling".into());
+        }
+    }
 
-    let client = LinodeClient::new(args.token, args.pub_key)?;
+    // In scale, evacuate, drain, failover, schedule and watch runs the
+    // first SIGINT or SIGTERM lets the instance in flight finish, claiming
+    // or parking its slot and releasing its lock; a second aborts, leaving
+    // unfinished operations in the journal for `resume`.
+    if stops_gracefully(&args.action) {
+        let shutdown = client.shutdown_handle();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            eprintln!("Stopping after the operation in flight, signal again to abort");
+            shutdown.request();
+            shutdown_signal().await;
+            for operation_id in journal.iter().flat_map(Journal::in_flight) {
+                eprintln!("Interrupted operation: {}, see resume", operation_id);
+            }
+            std::process::exit(130);
+        });
+    }
 
+    // written to --metrics-file with the step timings
+    let mut zone_metrics = None;
     match args.action {
         Action::Scale(action) => match action {
             ScaleAction::Up {
+                profile,
                 image_id,
                 instance_type,
                 domain_id,
+                mirror_domain_id,
                 region,
                 tag,
                 n,
+                fallback_regions,
+                region_names,
+                vlan_subnet,
+                firewall_id,
+                health_port,
+                user_data_template,
+                user_data_file,
+                swap_size,
+                power_cycle,
+                boot_timeout,
+                srv_service,
+                srv_port,
+                srv_protocol,
+                srv_weight,
+                instance_record,
+                provision_ssh,
+                provision_ssh_user,
+                provision_callback,
+                monthly_budget,
+                json,
+                keep_going,
             } => {
-                if let Some(region_info) = REGIONS.get(region.as_str()) {
-                    for _ in 0..n {
-                        client
-                            .scale_up_one(&image_id, &instance_type, domain_id, region_info, &tag)
-                            .await
-                            .map_err(|e| format!("Failed to scale up: {}", e))?;
+                let profile = profile
+                    .map(|name| config.profile(&name).cloned())
+                    .transpose()?
+                    .unwrap_or_default();
+                let tag = tag
+                    .or_else(|| Some(profile.tag.clone()).filter(|tag| !tag.is_empty()))
+                    .ok_or("No tag: pass --tag or a --profile")?;
+                let mirror_domains = config.mirror_domains(&tag, domain_id, &mirror_domain_id);
+                let image_id = image_id
+                    .or_else(|| profile.image.clone())
+                    .ok_or("No image: pass --image-id or a --profile with an image")?;
+                let instance_type = instance_type.or_else(|| profile.instance_type.clone());
+                let user_data_template = match &user_data_file {
+                    Some(_) => user_data_template,
+                    None => user_data_template.or_else(|| profile.user_data_template.clone()),
+                };
+                // without --region, weights pick a region per instance
+                let mut picker = match &region {
+                    None if !config.region_weights.is_empty() => {
+                        Some(client.region_picker(&config.region_weights).await?)
                     }
-                    println!(
-                        "Scaled up {} instance(s) in region: {}",
-                        n, region_info.region
-                    );
+                    _ => None,
+                };
+                let region = match &picker {
+                    Some(picker) if picker.is_empty() => {
+                        return Err("No region in region_weights can take instances".into());
+                    }
+                    Some(_) => String::new(),
+                    None => region
+                        .or_else(|| cli_defaults.region.clone())
+                        .ok_or("No region: pass --region or set a linode-cli default")?,
+                };
+                let instance_type = instance_type
+                    .or_else(|| cli_defaults.instance_type.clone())
+                    .ok_or("No instance type: pass --instance-type or set a linode-cli default")?;
+                let fallback_regions = if fallback_regions.is_empty() {
+                    &profile.fallback_regions
                 } else {
-                    eprintln!("Region code '{}' not found.", region);
+                    &fallback_regions
+                };
+                let fallbacks = region_groups.resolve(&fallback_regions.join(","))?;
+                match region_groups.resolve(&region) {
+                    Ok(regions) => {
+                        let user_data = match (user_data_template, user_data_file) {
+                            (Some(path), _) => {
+                                Some(UserData::Template(UserDataTemplate::from_file(path)?))
+                            }
+                            (None, Some(path)) => {
+                                Some(UserData::Static(InstanceMetadata::from_file(path)?))
+                            }
+                            (None, None) => None,
+                        };
+                        let provisioner: Option<Arc<dyn Provisioner>> =
+                            match (provision_ssh, provision_callback) {
+                                (Some(command), _) => Some(Arc::new(SshProvisioner {
+                                    user: provision_ssh_user,
+                                    ..SshProvisioner::new(&command)
+                                })),
+                                (None, Some(url)) => {
+                                    Some(Arc::new(HttpCallbackProvisioner::new(&url)))
+                                }
+                                (None, None) => None,
+                            };
+                        let options = ScaleUpOptions {
+                            user_data,
+                            swap_size,
+                            power_cycle,
+                            boot_timeout: Some(Duration::from_secs(boot_timeout)),
+                            srv: srv_service.zip(srv_port).map(|(service, port)| SrvService {
+                                protocol: srv_protocol.clone(),
+                                weight: srv_weight,
+                                ..SrvService::new(&service, port)
+                            }),
+                            instance_record,
+                            provisioner,
+                            monthly_budget,
+                            vlan_subnet: vlan_subnet.or_else(|| profile.subnet.clone()),
+                            firewall_id: firewall_id.or(profile.firewall_id),
+                            health: health_port.or(profile.health_port).map(|port| HealthCheck {
+                                port,
+                                timeout: Duration::from_secs(5),
+                            }),
+                            mirror_domains: mirror_domains.clone(),
+                        };
+                        let job = ScaleUpJob {
+                            image_id: &image_id,
+                            instance_type: &instance_type,
+                            domain_id,
+                            tag: &tag,
+                            options: &options,
+                            json,
+                        };
+                        let mut summary = ScaleSummary::default();
+                        if let Some(picker) = &mut picker {
+                            for iteration in 1..=n {
+                                let stopped = !summary.failed.is_empty() && !keep_going;
+                                if stopped || client.is_shutting_down() {
+                                    summary.skipped += 1;
+                                    continue;
+                                }
+                                match scale_up_weighted(&client, &args_lock, picker, &job).await {
+                                    Ok(node) => {
+                                        hooks.node_event(HookEvent::ScaleUp, &tag, &node).await;
+                                        summary.succeeded.push(node);
+                                    }
+                                    Err(failure) => {
+                                        hooks
+                                            .failure(
+                                                "scale-up",
+                                                &tag,
+                                                &failure.region,
+                                                &failure.error,
+                                            )
+                                            .await;
+                                        summary.failed.push(ScaleFailure {
+                                            iteration,
+                                            ..failure
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                        for region_info in regions {
+                            // without --keep-going the first failure stops
+                            // everything after it
+                            let stopped = !summary.failed.is_empty() && !keep_going;
+                            if stopped || client.is_shutting_down() {
+                                summary.skipped += n;
+                                continue;
+                            }
+                            let failures = summary.failed.len();
+                            // another operator's lock only stops this region
+                            let mut lock = match acquire_lock(
+                                &client,
+                                &args_lock,
+                                domain_id,
+                                &tag,
+                                region_info.code,
+                            )
+                            .await
+                            {
+                                Ok(lock) => lock,
+                                Err(e) => {
+                                    summary.failed.push(ScaleFailure {
+                                        region: region_info.region.to_owned(),
+                                        iteration: 0,
+                                        error: format!("locking: {}", e),
+                                    });
+                                    summary.skipped += n;
+                                    hooks
+                                        .failure("scale-up", &tag, region_info.code, &e.to_string())
+                                        .await;
+                                    continue;
+                                }
+                            };
+                            if n > 1 {
+                                let prefix = client.naming().prefix(&tag, region_info.code);
+                                if let Err(e) =
+                                    client.reserve_slots(domain_id, &prefix, n as usize).await
+                                {
+                                    summary.failed.push(ScaleFailure {
+                                        region: region_info.region.to_owned(),
+                                        iteration: 0,
+                                        error: format!("reserving slots: {}", e),
+                                    });
+                                }
+                            }
+                            let mut added = 0;
+                            for iteration in 1..=n {
+                                let stopped = summary.failed.len() > failures && !keep_going;
+                                if stopped || client.is_shutting_down() {
+                                    summary.skipped += 1;
+                                    continue;
+                                }
+                                if iteration > 1 {
+                                    if let Err(e) =
+                                        refresh_lock(&client, &args_lock, lock.as_mut()).await
+                                    {
+                                        // the lock was lost, the rest of the
+                                        // region is left alone
+                                        summary.failed.push(ScaleFailure {
+                                            region: region_info.region.to_owned(),
+                                            iteration,
+                                            error: format!("refreshing lock: {}", e),
+                                        });
+                                        summary.skipped += n - iteration;
+                                        break;
+                                    }
+                                }
+                                let result = match client
+                                    .scale_up_one_with(
+                                        &image_id,
+                                        &instance_type,
+                                        domain_id,
+                                        region_info,
+                                        &tag,
+                                        &options,
+                                    )
+                                    .await
+                                {
+                                    Err(e) if is_capacity_error(&e) && !fallbacks.is_empty() => {
+                                        scale_up_fallback(
+                                            &client,
+                                            &args_lock,
+                                            &fallbacks,
+                                            region_info,
+                                            &job,
+                                            e,
+                                        )
+                                        .await
+                                    }
+                                    result => result,
+                                };
+                                match result {
+                                    Ok(node) => {
+                                        hooks.node_event(HookEvent::ScaleUp, &tag, &node).await;
+                                        summary.succeeded.push(node);
+                                        added += 1;
+                                    }
+                                    Err(e) => summary.failed.push(ScaleFailure {
+                                        region: region_info.region.to_owned(),
+                                        iteration,
+                                        error: e.to_string(),
+                                    }),
+                                }
+                            }
+                            // the lock expires on its own, so a failed release
+                            // doesn't undo the region
+                            if let Err(e) = release_lock(&client, lock).await {
+                                eprintln!(
+                                    "Failed to release lock for region {}: {}",
+                                    region_info.code, e
+                                );
+                            }
+                            for failure in &summary.failed[failures..] {
+                                hooks
+                                    .failure("scale-up", &tag, region_info.code, &failure.error)
+                                    .await;
+                            }
+                            progress(
+                                json,
+                                format!(
+                                    "Scaled up {} of {} instance(s) in region: {}",
+                                    added, n, region_info.region
+                                ),
+                            );
+                        }
+                        if region_names {
+                            sync_region_names(&client, domain_id, &tag, &region_groups, json)
+                                .await?;
+                        }
+                        sync_allowlists(&client, &config.allowlists, &tag, json).await;
+                        summary.api_calls = client.api_calls().total();
+                        if json {
+                            println!("{}", serde_json::to_string_pretty(&summary)?);
+                        } else {
+                            print_scale_summary(&summary);
+                        }
+                        if !summary.is_complete() {
+                            return Err(format!(
+                                "Failed to scale up: {} added, {} failed, {} not tried",
+                                summary.succeeded.len(),
+                                summary.failed.len(),
+                                summary.skipped
+                            )
+                            .into());
+                        }
+                    }
+                    Err(e) => eprintln!("{}", e),
                 }
             }
             ScaleAction::Down {
                 domain_id,
+                mirror_domain_id,
                 region,
                 tag,
                 n,
+                balance,
+                region_names,
+                json,
             } => {
-                if let Some(region_info) = REGIONS.get(region.as_str()) {
-                    let i = client
-                        .scale_down(domain_id, region_info, &tag, n as usize)
-                        .await
-                        .map_err(|e| format!("Failed to scale down: {}", e))?;
-                    println!(
-                        "Scaled down {} instance(s) in region: {}",
-                        i, region_info.region
-                    );
-                } else {
-                    eprintln!("Region code '{}' not found.", region);
+                let region = region
+                    .or_else(|| cli_defaults.region.clone())
+                    .ok_or("No region: pass --region or set a linode-cli default")?;
+                let mirror_domains = config.mirror_domains(&tag, domain_id, &mirror_domain_id);
+                match region_groups.resolve(&region) {
+                    Ok(regions) if balance != ScaleDownPolicy::PerRegion => {
+                        // the whole set of regions is in play for every removal
+                        let mut locks = Vec::new();
+                        for region_info in &regions {
+                            locks.push(
+                                acquire_lock(
+                                    &client,
+                                    &args_lock,
+                                    domain_id,
+                                    &tag,
+                                    region_info.code,
+                                )
+                                .await?,
+                            );
+                        }
+                        let registry = LinodeDnsRegistry::new(&client, domain_id)
+                            .with_mirrors(&mirror_domains);
+                        let result = client
+                            .scale_down_balanced(&registry, &regions, &tag, n as usize, balance)
+                            .await;
+                        for lock in locks {
+                            release_lock(&client, lock).await?;
+                        }
+                        if let Err(e) = &result {
+                            hooks
+                                .failure("scale-down", &tag, &region, &e.to_string())
+                                .await;
+                        }
+                        let removed = result.map_err(|e| format!("Failed to scale down: {}", e))?;
+                        for node in &removed {
+                            hooks.node_event(HookEvent::ScaleDown, &tag, node).await;
+                        }
+                        for region_info in &regions {
+                            let count = removed
+                                .iter()
+                                .filter(|node| node.region == region_info.region)
+                                .count();
+                            progress(
+                                json,
+                                format!(
+                                    "Scaled down {} instance(s) in region: {}",
+                                    count, region_info.region
+                                ),
+                            );
+                        }
+                        if region_names {
+                            sync_region_names(&client, domain_id, &tag, &region_groups, json)
+                                .await?;
+                        }
+                        sync_allowlists(&client, &config.allowlists, &tag, json).await;
+                        if json {
+                            println!("{}", serde_json::to_string_pretty(&removed)?);
+                        }
+                    }
+                    Ok(regions) => {
+                        let mut all_removed = Vec::new();
+                        for region_info in regions {
+                            let lock = acquire_lock(
+                                &client,
+                                &args_lock,
+                                domain_id,
+                                &tag,
+                                region_info.code,
+                            )
+                            .await?;
+                            let registry = LinodeDnsRegistry::new(&client, domain_id)
+                                .with_mirrors(&mirror_domains);
+                            let result = client
+                                .scale_down_in(&registry, region_info, &tag, n as usize)
+                                .await;
+                            release_lock(&client, lock).await?;
+                            if let Err(e) = &result {
+                                hooks
+                                    .failure("scale-down", &tag, region_info.code, &e.to_string())
+                                    .await;
+                            }
+                            let removed =
+                                result.map_err(|e| format!("Failed to scale down: {}", e))?;
+                            for node in &removed {
+                                hooks.node_event(HookEvent::ScaleDown, &tag, node).await;
+                            }
+                            progress(
+                                json,
+                                format!(
+                                    "Scaled down {} instance(s) in region: {}",
+                                    removed.len(),
+                                    region_info.region
+                                ),
+                            );
+                            all_removed.extend(removed);
+                        }
+                        if region_names {
+                            sync_region_names(&client, domain_id, &tag, &region_groups, json)
+                                .await?;
+                        }
+                        sync_allowlists(&client, &config.allowlists, &tag, json).await;
+                        if json {
+                            println!("{}", serde_json::to_string_pretty(&all_removed)?);
+                        }
+                    }
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+        },
+        Action::Instance(InstanceAction::Cordon { label, domain_id }) => {
+            let slot = client.cordon_instance(domain_id, &label).await?;
+            println!("Cordoned {} from {}", label, slot);
+        }
+        Action::Instance(InstanceAction::Uncordon { label, domain_id }) => {
+            let slot = client.uncordon_instance(domain_id, &label).await?;
+            println!("Uncordoned {} into {}", label, slot);
+        }
+        Action::Instance(InstanceAction::Ls { tag_expr }) => {
+            let instances = match &tag_expr {
+                Some(expr) => client.get_instances_by_tag_expr(expr).await?,
+                None => client.fetch_instances().await?,
+            };
+            for instance in &instances {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    instance.id,
+                    instance.label,
+                    instance.region,
+                    instance.ipv4.first().map(String::as_str).unwrap_or("-"),
+                    instance.tags.join(",")
+                );
+            }
+        }
+        Action::Instance(InstanceAction::Reboot {
+            instance_id,
+            config_id,
+        }) => {
+            client
+                .reboot_instance_with_config(instance_id, config_id)
+                .await?;
+            println!("Rebooted instance ID: {}", instance_id);
+        }
+        Action::Instance(InstanceAction::Replace {
+            label,
+            domain_id,
+            image,
+            health_port,
+        }) => {
+            let options = ScaleUpOptions {
+                health: health_port.map(|port| HealthCheck {
+                    port,
+                    timeout: Duration::from_secs(5),
+                }),
+                ..Default::default()
+            };
+            let replacement = client
+                .replace_instance(domain_id, &label, image.as_deref(), &options)
+                .await?;
+            hooks
+                .node_event(HookEvent::ScaleDown, &replacement.tag, &replacement.removed)
+                .await;
+            hooks
+                .node_event(HookEvent::ScaleUp, &replacement.tag, &replacement.created)
+                .await;
+            println!(
+                "{}: {} -> {}",
+                replacement.slot, replacement.removed.label, replacement.created.label
+            );
+        }
+        Action::Failover(FailoverAction::Promote {
+            from,
+            to,
+            tag,
+            domain_id,
+            image_id,
+            instance_type,
+            health_port,
+            health_wait,
+        }) => {
+            let from =
+                find_region(&from).ok_or_else(|| format!("Region code '{}' not found.", from))?;
+            let to = find_region(&to).ok_or_else(|| format!("Region code '{}' not found.", to))?;
+            let options = FailoverOptions {
+                image_id,
+                instance_type,
+                health: health_port.map(|port| HealthCheck {
+                    port,
+                    timeout: Duration::from_secs(3),
+                }),
+                health_wait: Some(Duration::from_secs(health_wait)),
+                ..Default::default()
+            };
+            let locks = vec![
+                acquire_lock(&client, &args_lock, domain_id, &tag, from.code).await?,
+                acquire_lock(&client, &args_lock, domain_id, &tag, to.code).await?,
+            ];
+            let result = client
+                .failover_promote(domain_id, &tag, from, to, &options)
+                .await;
+            for lock in locks {
+                release_lock(&client, lock).await?;
+            }
+            let report = match result {
+                Ok(report) => report,
+                Err(e) => {
+                    hooks
+                        .failure("failover", &tag, from.code, &e.to_string())
+                        .await;
+                    return Err(format!("Failover from {} failed: {}", from.code, e).into());
+                }
+            };
+            for node in &report.promoted {
+                hooks.node_event(HookEvent::ScaleUp, &tag, node).await;
+            }
+            for node in &report.removed {
+                hooks.node_event(HookEvent::ScaleDown, &tag, node).await;
+            }
+            for (name, address) in &report.repointed {
+                println!("{} -> {}", name, address);
+            }
+            println!(
+                "Promoted {} instance(s) in {}, removed {} from {}",
+                report.promoted.len(),
+                to.region,
+                report.removed.len(),
+                from.region
+            );
+        }
+        Action::Api { method, path, data } => {
+            let body = data
+                .map(|data| serde_json::from_str::<serde_json::Value>(&data))
+                .transpose()?;
+            let response = client
+                .request::<serde_json::Value>(method, &path, body.as_ref())
+                .await?;
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        }
+        Action::Verify { json } => {
+            let report = client.verify().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_token_report(&report);
+            }
+            if !report.is_ok() {
+                return Err("the token can't be used for scaling".into());
+            }
+        }
+        Action::Quota => match client.fetch_rate_limit().await? {
+            Some(rate_limit) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                println!(
+                    "{}/{} requests left, window resets in {}s",
+                    rate_limit.remaining,
+                    rate_limit.limit,
+                    rate_limit.reset.saturating_sub(now)
+                );
+            }
+            None => println!("The API did not report a rate limit"),
+        },
+        Action::Region(RegionAction::Evacuate {
+            from,
+            to,
+            tag,
+            domain_id,
+            keep_images,
+        }) => {
+            let from =
+                find_region(&from).ok_or_else(|| format!("Region code '{}' not found.", from))?;
+            let to = find_region(&to).ok_or_else(|| format!("Region code '{}' not found.", to))?;
+            let options = EvacuateOptions {
+                keep_images,
+                ..Default::default()
+            };
+            let locks = vec![
+                acquire_lock(&client, &args_lock, domain_id, &tag, from.code).await?,
+                acquire_lock(&client, &args_lock, domain_id, &tag, to.code).await?,
+            ];
+            let result = client
+                .evacuate_region(domain_id, &tag, from, to, &options)
+                .await;
+            for lock in locks {
+                release_lock(&client, lock).await?;
+            }
+            let evacuated = match result {
+                Ok(evacuated) => evacuated,
+                Err(e) => {
+                    hooks
+                        .failure("evacuate", &tag, from.code, &e.to_string())
+                        .await;
+                    return Err(format!("Evacuation of {} failed: {}", from.code, e).into());
+                }
+            };
+            for evacuation in &evacuated {
+                hooks
+                    .node_event(HookEvent::ScaleDown, &tag, &evacuation.removed)
+                    .await;
+                hooks
+                    .node_event(HookEvent::ScaleUp, &tag, &evacuation.created)
+                    .await;
+                println!(
+                    "{}: {} -> {}",
+                    evacuation.slot, evacuation.removed.label, evacuation.created.label
+                );
+            }
+        }
+        Action::Region(RegionAction::Drain {
+            region,
+            tag,
+            domain_id,
+            replace_in,
+            image_id,
+            instance_type,
+        }) => {
+            let region_info = find_region(&region)
+                .ok_or_else(|| format!("Region code '{}' not found.", region))?;
+            let replacement = match replace_in {
+                Some(name) => Some(DrainReplacement {
+                    region: find_region(&name)
+                        .ok_or_else(|| format!("Region code '{}' not found.", name))?,
+                    image_id: image_id.unwrap_or_default(),
+                    instance_type: instance_type.unwrap_or_default(),
+                    options: ScaleUpOptions::default(),
+                }),
+                None => None,
+            };
+            let mut locks =
+                vec![acquire_lock(&client, &args_lock, domain_id, &tag, region_info.code).await?];
+            if let Some(replacement) = &replacement {
+                locks.push(
+                    acquire_lock(
+                        &client,
+                        &args_lock,
+                        domain_id,
+                        &tag,
+                        replacement.region.code,
+                    )
+                    .await?,
+                );
+            }
+            let result = client
+                .drain_region(domain_id, &tag, region_info, replacement.as_ref())
+                .await;
+            for lock in locks {
+                release_lock(&client, lock).await?;
+            }
+            let report = match result {
+                Ok(report) => report,
+                Err(e) => {
+                    hooks
+                        .failure("drain", &tag, region_info.code, &e.to_string())
+                        .await;
+                    return Err(format!("Failed to drain {}: {}", region_info.code, e).into());
+                }
+            };
+            for node in &report.replacements {
+                hooks.node_event(HookEvent::ScaleUp, &tag, node).await;
+                println!("replacement {} in {}", node.label, node.region);
+            }
+            for (label, slot) in &report.cordoned {
+                println!("cordoned {} from {}", label, slot);
+            }
+        }
+        Action::Regions { capabilities } => list_regions(&client, &capabilities).await,
+        Action::Types {
+            region,
+            min_memory,
+            min_vcpus,
+            class,
+        } => {
+            let region = region
+                .as_deref()
+                .map(|r| find_region(r).map_or(r, |i| i.region));
+            let mut types = client.fetch_types().await?;
+            types.retain(|t| {
+                min_memory.is_none_or(|m| t.memory >= m)
+                    && min_vcpus.is_none_or(|v| t.vcpus >= v)
+                    && class.as_ref().is_none_or(|c| &t.class == c)
+            });
+            let price = |t: &LinodeType| match region {
+                Some(region) => t.price_in(region),
+                None => t.price,
+            };
+            types.sort_by(|a, b| price(a).hourly.total_cmp(&price(b).hourly));
+
+            println!("id\tclass\tvcpus\tmemory\tdisk\tgpus\thourly\tmonthly");
+            for t in &types {
+                let p = price(t);
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{:.4}\t{:.2}",
+                    t.id, t.class, t.vcpus, t.memory, t.disk, t.gpus, p.hourly, p.monthly
+                );
+            }
+        }
+        Action::Firewall(action) => firewall(&client, action).await?,
+        Action::Nb(NbAction::Cert(NbCertAction::Rotate {
+            nb_id,
+            config_id,
+            cert,
+            key,
+            timeout,
+        })) => {
+            let cert =
+                std::fs::read_to_string(&cert).map_err(|e| format!("{}: {}", cert.display(), e))?;
+            let key =
+                std::fs::read_to_string(&key).map_err(|e| format!("{}: {}", key.display(), e))?;
+            let nb = client.fetch_nodebalancer(nb_id).await?;
+            let rotations = client
+                .rotate_nodebalancer_cert(nb_id, config_id, &cert, &key)
+                .await?;
+            print_cert_rotations(&rotations);
+
+            let address = nb.ipv4.ok_or("NodeBalancer has no IPv4 address")?;
+            for rotation in &rotations {
+                let status = check_tcp(&address, rotation.port, Duration::from_secs(timeout)).await;
+                if !status.healthy {
+                    return Err(format!(
+                        "{}:{} is not accepting connections: {}",
+                        address,
+                        rotation.port,
+                        status.error.unwrap_or_default()
+                    )
+                    .into());
+                }
+            }
+        }
+        Action::Images(action) => images(&client, action).await?,
+        Action::Adopt {
+            instance_id,
+            tag,
+            region,
+            domain_id,
+        } => match find_region(&region) {
+            Some(region_info) => {
+                let lock =
+                    acquire_lock(&client, &args_lock, domain_id, &tag, region_info.code).await?;
+                let result = client
+                    .adopt_instance(instance_id, &tag, region_info, domain_id)
+                    .await;
+                release_lock(&client, lock).await?;
+                let report = result?;
+                println!("Adopted instance ID: {}", instance_id);
+                if !report.tags_added.is_empty() {
+                    println!("tags added: {}", report.tags_added.join(","));
+                }
+                if let Some(ip) = &report.vlan_ip {
+                    println!("vlan ip: {}", ip);
+                }
+                if let Some(name) = &report.dns_name {
+                    println!("dns: {}", name);
                 }
             }
+            None => eprintln!("Region code '{}' not found.", region),
         },
+        Action::RenameFleet {
+            from,
+            to,
+            domain_id,
+            relabel_vlan,
+        } => {
+            let report = client
+                .rename_fleet(&from, &to, domain_id, relabel_vlan)
+                .await?;
+            for label in &report.instances {
+                println!("retagged {}", label);
+            }
+            for label in &report.interfaces {
+                println!("relabelled vlan on {}", label);
+            }
+            for (old, new) in &report.records {
+                println!("renamed {} -> {}", old, new);
+            }
+        }
+        Action::Export {
+            tag,
+            domain_id,
+            output,
+        } => {
+            let snapshot = client.export_fleet(&tag, domain_id).await?;
+            snapshot.write_to(&output)?;
+            println!(
+                "Exported {} instance(s) to {}",
+                snapshot.instances.len(),
+                output.display()
+            );
+        }
+        Action::Resume {
+            operation_id: None, ..
+        } => {
+            let dir = args.journal_dir.clone().or_else(journal::default_dir);
+            let dir = dir.ok_or("no journal directory")?;
+            println!("operation_id\tkind\ttag\tregion\tstarted\tsteps");
+            for entry in Journal::open(dir).unfinished()? {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    entry.operation_id,
+                    entry.kind,
+                    entry.tag,
+                    entry.region,
+                    entry.started,
+                    entry.steps.len()
+                );
+            }
+        }
+        Action::Resume {
+            operation_id: Some(operation_id),
+            domain_id,
+            rollback,
+        } => {
+            let domain_id = domain_id.ok_or("resume needs --domain-id")?;
+            let registry = LinodeDnsRegistry::new(&client, domain_id);
+            let entry = client.resume(&registry, &operation_id, rollback).await?;
+            println!(
+                "{} {} {}",
+                if rollback { "rolled back" } else { "finished" },
+                entry.kind,
+                entry.operation_id
+            );
+        }
+        Action::Restore { file, domain_id } => {
+            let snapshot = FleetSnapshot::from_file(file)?;
+            let domain_id = domain_id.unwrap_or(snapshot.domain_id);
+            let report = client.restore_fleet(&snapshot, domain_id).await?;
+            for label in &report.created {
+                println!("created {}", label);
+            }
+            for label in &report.skipped {
+                println!("skipped {} (exists)", label);
+            }
+            for record in &report.records {
+                println!("dns {}", record);
+            }
+        }
+        Action::Diff {
+            file,
+            json,
+            no_color,
+        } => {
+            let spec = FleetSpec::from_file(file)?;
+            let diff = client.diff_fleet(&spec).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff, !no_color && std::io::stdout().is_terminal());
+            }
+        }
+        Action::Status {
+            tag,
+            domain_id,
+            health_port,
+            health_timeout,
+            json,
+        } => {
+            if health_port.is_some() && args.offline {
+                eprintln!("Skipping health checks while offline");
+            }
+            let health = health_port
+                .filter(|_| !args.offline)
+                .map(|port| HealthCheck {
+                    port,
+                    timeout: Duration::from_secs(health_timeout),
+                });
+            let members = client.fleet_status(&tag, domain_id, health).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&members)?);
+            } else {
+                print_fleet(&members);
+            }
+        }
+        Action::Roll(RollAction::Status {
+            tag,
+            domain_id,
+            json,
+        }) => {
+            let status = client.roll_status(&tag, domain_id).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                print_roll_status(&status);
+            }
+        }
+        Action::Schedule(ScheduleAction::Run { file, tag }) => {
+            let spec = FleetSpec::from_file(file)?;
+            let groups = spec
+                .fleets
+                .iter()
+                .filter(|group| group.schedule.is_some())
+                .filter(|group| tag.as_ref().is_none_or(|tag| &group.tag == tag));
+            for group in groups {
+                if client.is_shutting_down() {
+                    break;
+                }
+                let mut locks = Vec::new();
+                for name in group.regions.keys() {
+                    let code = find_region(name).map_or(name.as_str(), |info| info.code);
+                    locks.push(
+                        acquire_lock(&client, &args_lock, spec.domain_id, &group.tag, code).await?,
+                    );
+                }
+                let result = client
+                    .run_schedule(spec.domain_id, group, &ScaleUpOptions::default())
+                    .await;
+                for lock in locks {
+                    release_lock(&client, lock).await?;
+                }
+                let run = match result {
+                    Ok(run) => run,
+                    Err(e) => {
+                        hooks
+                            .failure("schedule", &group.tag, "", &e.to_string())
+                            .await;
+                        return Err(format!("Schedule for {} failed: {}", group.tag, e).into());
+                    }
+                };
+                for node in &run.removed {
+                    hooks.node_event(HookEvent::ScaleDown, &run.tag, node).await;
+                }
+                for node in &run.added {
+                    hooks.node_event(HookEvent::ScaleUp, &run.tag, node).await;
+                }
+                println!(
+                    "{} is {:?}: removed {}, added {}",
+                    run.tag,
+                    run.phase,
+                    run.removed.len(),
+                    run.added.len()
+                );
+            }
+        }
+        Action::Volume(action) => volumes(&client, action).await?,
         Action::Dns(DnsAction::Ls { domain_id }) => {
             if let Ok(records) = client.fetch_records(domain_id).await {
                 for rec in &records {
@@ -135,7 +2887,327 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
                 }
             }
         }
+        Action::Dns(DnsAction::Upsert {
+            domain_id,
+            name,
+            record_type,
+            target,
+            ttl,
+        }) => {
+            let upsert = client
+                .upsert_record(domain_id, &name, &record_type, &target, ttl)
+                .await?;
+            let action = match &upsert {
+                Upsert::Created(_) => "created",
+                Upsert::Updated(_) => "updated",
+                Upsert::Unchanged(_) => "unchanged",
+            };
+            let rec = upsert.record();
+            println!(
+                "{}\t{}\t{} {} -> {}",
+                action, rec.id, rec.record_type, rec.name, rec.target
+            );
+        }
+        Action::Dns(DnsAction::Clone {
+            domain_id,
+            new_domain,
+            map_targets,
+        }) => {
+            let mut targets = HashMap::new();
+            for mapping in &map_targets {
+                let (from, to) = mapping
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid --map-target {}, expected OLD=NEW", mapping))?;
+                targets.insert(from.to_owned(), to.to_owned());
+            }
+            let domain = client
+                .clone_domain_with(domain_id, &new_domain, &targets)
+                .await?;
+            println!(
+                "Cloned domain ID: {} as {} (ID: {})",
+                domain_id, domain.domain, domain.id
+            );
+        }
+        Action::Dns(DnsAction::Import {
+            axfr,
+            zone,
+            soa_email,
+        }) => {
+            let import = client
+                .import_zone(&axfr, &zone, soa_email.as_deref())
+                .await?;
+            for record in &import.skipped {
+                println!("skipped {}", record);
+            }
+            for record in &import.failed {
+                println!("failed {}", record);
+            }
+            println!(
+                "Imported {} records into {} (domain ID: {})",
+                import.created, import.domain.domain, import.domain.id
+            );
+        }
+        Action::Dns(DnsAction::RegionNames { domain_id, tag }) => {
+            sync_region_names(&client, domain_id, &tag, &region_groups, false).await?;
+        }
+        Action::Dns(DnsAction::Settings {
+            domain_id,
+            soa_email,
+            refresh,
+            retry,
+            expire,
+            ttl,
+        }) => {
+            let settings = DomainSettings {
+                soa_email,
+                refresh_sec: refresh,
+                retry_sec: retry,
+                expire_sec: expire,
+                ttl_sec: ttl,
+            };
+            let domain = client.update_domain_settings(domain_id, &settings).await?;
+            println!("domain\t{}", domain.domain);
+            println!("soa_email\t{}", domain.soa_email.as_deref().unwrap_or("-"));
+            println!("refresh\t{}", format_soa_timer(domain.refresh_sec));
+            println!("retry\t{}", format_soa_timer(domain.retry_sec));
+            println!("expire\t{}", format_soa_timer(domain.expire_sec));
+            println!("ttl\t{}", format_soa_timer(domain.ttl_sec));
+        }
+        Action::Dns(DnsAction::Health {
+            domain_id,
+            prefixes,
+            port,
+            timeout,
+        }) => {
+            let health = client
+                .zone_health(domain_id, &prefixes, port, Duration::from_secs(timeout))
+                .await?;
+            match &args.metrics_file {
+                Some(_) => zone_metrics = Some(health.render()),
+                None => println!("{}# EOF", health.render()),
+            }
+        }
+        Action::Dns(DnsAction::Reconcile {
+            domain_id,
+            mirror_domain_id,
+            tag,
+        }) => {
+            let mirrors = config.mirror_domains(&tag, domain_id, &mirror_domain_id);
+            let reconcile = client.reconcile_dns_mirrored(domain_id, &mirrors, &tag);
+            let reports = client
+                .with_fleet_locks(domain_id, &tag, args_lock.fleet_ttl(), reconcile)
+                .await?;
+            for report in &reports {
+                // the primary's lines stay as they were without mirrors
+                let zone = if report.domain == domain_id {
+                    String::new()
+                } else {
+                    format!("{}\t", report.domain)
+                };
+                for name in &report.parked {
+                    println!("{}parked\t{}", zone, name);
+                }
+                for label in &report.unregistered {
+                    println!("{}unregistered\t{}", zone, label);
+                }
+                for name in &report.mirrored {
+                    println!("{}mirrored\t{}", zone, name);
+                }
+            }
+        }
+        Action::History(HistoryAction::Diff { since, until, json }) => {
+            let cache = client
+                .state_cache()
+                .ok_or("history needs a state cache, see --state-cache")?;
+            let since = parse_point(&since)?;
+            let until = until.as_deref().map(parse_point).transpose()?;
+            let Some(diff) = diff_history(&cache.history()?, since, until) else {
+                return Err(format!(
+                    "No history yet in {}, run history snapshot",
+                    cache.history_path().display()
+                )
+                .into());
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                println!("from\t{}", format_time(diff.from));
+                println!("to\t{}", format_time(diff.to));
+                for change in &diff.changes {
+                    let kind = match change.kind {
+                        ChangeKind::Added => "added",
+                        ChangeKind::Removed => "removed",
+                        ChangeKind::Changed => "changed",
+                    };
+                    let name = match change.domain {
+                        Some(domain) => {
+                            format!("{} ({}, domain ID: {})", change.name, change.id, domain)
+                        }
+                        None => format!("{} ({})", change.name, change.id),
+                    };
+                    println!("{}\t{}\t{}", kind, change.object, name);
+                    for field in &change.fields {
+                        println!("\t{}: {} -> {}", field.field, field.before, field.after);
+                    }
+                }
+            }
+        }
+        Action::History(HistoryAction::Snapshot) => {
+            let Some(cache) = client.state_cache() else {
+                return Err("history needs a state cache, see --state-cache".into());
+            };
+            let inventory = client.fetch_inventory().await?;
+            let snapshot = cache.record_snapshot(inventory)?;
+            println!(
+                "{}: {} instances, {} domains",
+                format_time(snapshot.at),
+                snapshot.inventory.instances.len(),
+                snapshot.inventory.records.len()
+            );
+        }
+        Action::Events(EventsAction::Watch {
+            domain_id,
+            tags,
+            interval,
+        }) => {
+            client
+                .watch_events(
+                    domain_id,
+                    &tags,
+                    Duration::from_secs(interval),
+                    args_lock.fleet_ttl(),
+                    |event, report| {
+                        for name in &report.parked {
+                            println!("{}\t{}\tparked\t{}", event.id, event.action, name);
+                        }
+                        for label in &report.unregistered {
+                            println!("{}\t{}\tunregistered\t{}", event.id, event.action, label);
+                        }
+                    },
+                )
+                .await?;
+        }
+        Action::Reap {
+            tag,
+            older_than,
+            images,
+            dry_run,
+        } => {
+            let report = client.reap(&tag, older_than, images, dry_run).await?;
+            print_reap_report(&report, dry_run);
+            if !report.failed.is_empty() {
+                return Err(
+                    format!("{} resources could not be reaped", report.failed.len()).into(),
+                );
+            }
+        }
+        Action::SyncAllowlist {
+            tag,
+            firewall_id,
+            label,
+            protocol,
+            ports,
+            include_vlan,
+        } => {
+            let allowlist = Allowlist {
+                label,
+                protocol,
+                ports,
+                include_vlan,
+                ..Allowlist::new(&tag, firewall_id)
+            };
+            let sync = client.sync_allowlist(&allowlist).await?;
+            if sync.is_unchanged() {
+                println!("{} is up to date", allowlist.rule_label());
+            }
+            print_allowlist_sync(&allowlist, &sync, false);
+        }
+        Action::Backup(BackupAction::Schedule { tag, day, window }) => {
+            let schedule = BackupSchedule {
+                day: Some(day),
+                window: Some(window),
+            };
+            let report = client.set_backup_schedule_for_tag(&tag, &schedule).await?;
+            for label in &report.updated {
+                println!("updated\t{}", label);
+            }
+            for label in &report.unchanged {
+                println!("unchanged\t{}", label);
+            }
+            for label in &report.not_enabled {
+                println!("backups not enabled\t{}", label);
+            }
+        }
+        Action::Fleet(FleetAction::Disks { tag, image, json }) => {
+            let usage = client.fleet_disks(&tag, image.as_deref()).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&usage)?);
+            } else {
+                print_disk_usage(&usage);
+            }
+        }
+    }
+
+    if let Some(path) = &args.metrics_file {
+        let mut metrics = String::new();
+        if !client.step_metrics().is_empty() {
+            metrics.push_str(&client.step_metrics().render());
+        }
+        if !client.api_calls().is_empty() {
+            metrics.push_str(&client.api_calls().render());
+        }
+        if let Some(zone_metrics) = &zone_metrics {
+            metrics.push_str(zone_metrics);
+        }
+        if !metrics.is_empty() {
+            std::fs::write(path, metrics)?;
+        }
+    }
+
+    if let Some(age) = client.state_cache().and_then(|cache| cache.staleness()) {
+        eprintln!(
+            "STALE: offline, answered from state cached up to {} ago",
+            format_age(age)
+        );
     }
 
     Ok(())
 }
+
+fn print_scale_summary(summary: &ScaleSummary) {
+    for node in &summary.succeeded {
+        println!(
+            "added\t{}\t{}\t{}",
+            node.region,
+            node.label,
+            node.name.as_deref().unwrap_or("-")
+        );
+    }
+    for failure in &summary.failed {
+        println!(
+            "failed\t{}\t#{}\t{}",
+            failure.region, failure.iteration, failure.error
+        );
+    }
+    if summary.skipped > 0 {
+        println!("skipped\t{} instance(s) not tried", summary.skipped);
+    }
+    println!("api_calls\t{}", summary.api_calls);
+}
+
+fn format_soa_timer(secs: u32) -> String {
+    match secs {
+        0 => "default".to_owned(),
+        secs => secs.to_string(),
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h{}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d{}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
@@ -0,0 +1,145 @@
+use crate::LinodeError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+pub const NODE_ENV_PATH: &str = "/etc/linode-node.env";
+
+// the API rejects base64 encoded user_data larger than this
+pub const USER_DATA_MAX_BYTES: usize = 16 * 1024;
+
+// Payload for the `metadata` field of instance creation, served to the
+// node by the Linode Metadata service. user_data must be base64 encoded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstanceMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+}
+
+impl InstanceMetadata {
+    pub fn from_user_data(user_data: &[u8]) -> Self {
+        InstanceMetadata {
+            user_data: Some(STANDARD.encode(user_data)),
+        }
+    }
+
+    // base64 encode user_data, gzipping it first if the plain encoding is
+    // over the size limit (cloud-init detects and inflates gzip payloads)
+    pub fn encode_user_data(user_data: &[u8]) -> Result<Self, LinodeError> {
+        let metadata = Self::from_user_data(user_data);
+        if metadata.encoded_len() <= USER_DATA_MAX_BYTES {
+            return Ok(metadata);
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        let compressed = encoder
+            .write_all(user_data)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| LinodeError::UserData(e.to_string()))?;
+
+        let metadata = Self::from_user_data(&compressed);
+        if metadata.encoded_len() > USER_DATA_MAX_BYTES {
+            return Err(LinodeError::UserData(format!(
+                "user_data is {} bytes after gzip and base64 encoding, the limit is {}",
+                metadata.encoded_len(),
+                USER_DATA_MAX_BYTES
+            )));
+        }
+        Ok(metadata)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LinodeError> {
+        let user_data = std::fs::read(path.as_ref())
+            .map_err(|e| LinodeError::UserData(format!("{}: {}", path.as_ref().display(), e)))?;
+        Self::encode_user_data(&user_data)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.user_data.as_ref().map_or(0, String::len)
+    }
+}
+
+// A node already in the fleet, as a new node is told about it at boot so
+// clustered services can join it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Peer {
+    pub label: String,
+    pub public_ip: Option<String>,
+    pub vlan_ip: Option<String>,
+    pub dns_name: Option<String>,
+}
+
+// What a scaled node needs to know about its own role at boot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeIdentity {
+    pub tag: String,
+    pub region: String,
+    pub label: String,
+    pub dns_name: Option<String>,
+    pub vlan_ip: Option<String>,
+    // the scale operation that created the node
+    #[serde(default)]
+    pub operation_id: Option<String>,
+    // the fleet's other nodes when the node was created
+    #[serde(default)]
+    pub peers: Vec<Peer>,
+}
+
+impl NodeIdentity {
+    // shell-sourceable KEY=value lines; peers as comma separated lists,
+    // set but empty on the fleet's first node
+    pub fn to_env(&self) -> String {
+        let peer_vlan_ips = self.peer_list(|peer| peer.vlan_ip.as_deref());
+        let peer_dns_names = self.peer_list(|peer| peer.dns_name.as_deref());
+        let mut vars = vec![
+            ("LINODE_TAG", self.tag.as_str()),
+            ("LINODE_REGION", self.region.as_str()),
+            ("LINODE_LABEL", self.label.as_str()),
+        ];
+        if let Some(dns_name) = &self.dns_name {
+            vars.push(("LINODE_DNS_NAME", dns_name));
+        }
+        if let Some(vlan_ip) = &self.vlan_ip {
+            vars.push(("LINODE_VLAN_IP", vlan_ip));
+        }
+        if let Some(operation_id) = &self.operation_id {
+            vars.push(("LINODE_OPERATION_ID", operation_id));
+        }
+        vars.push(("LINODE_PEER_VLAN_IPS", &peer_vlan_ips));
+        vars.push(("LINODE_PEER_DNS_NAMES", &peer_dns_names));
+
+        vars.iter()
+            .map(|(k, v)| format!("{}={}\n", k, shell_quote(v)))
+            .collect()
+    }
+
+    // cloud-config that writes the identity to NODE_ENV_PATH
+    pub fn to_cloud_config(&self) -> String {
+        let mut config = String::from("#cloud-config\nwrite_files:\n");
+        config.push_str(&format!("  - path: {}\n", NODE_ENV_PATH));
+        config.push_str("    permissions: \"0644\"\n");
+        config.push_str("    content: |\n");
+        for line in self.to_env().lines() {
+            config.push_str(&format!("      {}\n", line));
+        }
+        config
+    }
+
+    pub fn to_metadata(&self) -> InstanceMetadata {
+        InstanceMetadata::from_user_data(self.to_cloud_config().as_bytes())
+    }
+
+    fn peer_list(&self, field: impl Fn(&Peer) -> Option<&str>) -> String {
+        self.peers
+            .iter()
+            .filter_map(field)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
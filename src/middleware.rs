@@ -0,0 +1,49 @@
+use crate::LinodeClient;
+use reqwest::{Request, Response};
+use std::sync::Arc;
+
+// Sees every request LinodeClient sends and what came back, once per
+// attempt, e.g. for logging, custom metrics or extra headers:
+//
+//   struct Tenant(HeaderValue);
+//   impl Middleware for Tenant {
+//       fn on_request(&self, request: &mut Request) {
+//           request.headers_mut().insert("x-tenant", self.0.clone());
+//       }
+//   }
+//   let client = LinodeClient::new(token, pub_key)?.with_middleware(Tenant(id));
+//
+// Requests answered from a cassette replay or an offline state cache are
+// never sent, so never seen. Every hook does nothing by default.
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, _request: &mut Request) {}
+
+    fn on_response(&self, _response: &Response) {}
+
+    // the request failed before any response, e.g. timed out
+    fn on_error(&self, _error: &reqwest::Error) {}
+}
+
+impl LinodeClient {
+    // run middleware around every request, after any added before it
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    // every hook in the order the middleware was added
+    pub(crate) fn intercept_request(&self, request: &mut Request) {
+        for middleware in &self.middleware {
+            middleware.on_request(request);
+        }
+    }
+
+    pub(crate) fn intercept_result(&self, result: &Result<Response, reqwest::Error>) {
+        for middleware in &self.middleware {
+            match result {
+                Ok(response) => middleware.on_response(response),
+                Err(e) => middleware.on_error(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::regions::REGIONS;
+use crate::{LinodeClient, LinodeError};
+
+pub const DEFAULT_TEMPLATE: &str = "{tag}-{region}-{n}";
+
+// How scale slots are named. A template such as "{tag}-{region}-{n}" is
+// split into the fleet prefix, "{tag}-{region}", which registries are
+// handed, and the separator before the sequence number, so a prefix of
+// "api-gb-lon" names slots "api-gb-lon-1", "api-gb-lon-2"... With a width
+// the number is zero padded ("api-gb-lon-01"), and with an environment
+// every prefix starts with it ("prod-api-gb-lon-1"). Names already in a
+// zone are matched by parsing them back, padded or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamingScheme {
+    prefix: String,
+    separator: String,
+    width: usize,
+    environment: Option<String>,
+}
+
+impl Default for NamingScheme {
+    fn default() -> Self {
+        NamingScheme {
+            prefix: "{tag}-{region}".to_owned(),
+            separator: "-".to_owned(),
+            width: 0,
+            environment: None,
+        }
+    }
+}
+
+impl NamingScheme {
+    // template must use {tag} and {region} and end in {n}, e.g.
+    // "{region}-{tag}{n}" for "gb-lon-api1"
+    pub fn new(template: &str) -> Result<Self, LinodeError> {
+        let invalid = |reason: &str| {
+            LinodeError::Spec(format!(
+                "invalid naming template '{}': {}",
+                template, reason
+            ))
+        };
+        let head = template
+            .strip_suffix("{n}")
+            .ok_or_else(|| invalid("it must end in {n}"))?;
+        if !head.contains("{tag}") || !head.contains("{region}") {
+            return Err(invalid("it must use {tag} and {region}"));
+        }
+        let unknown = head
+            .replace("{tag}", "")
+            .replace("{region}", "")
+            .contains(['{', '}']);
+        if unknown {
+            return Err(invalid("only {tag}, {region} and {n} can be used"));
+        }
+        // whatever follows the last placeholder separates the number
+        let split = head.rfind('}').map_or(0, |i| i + 1);
+        let (prefix, separator) = head.split_at(split);
+        Ok(NamingScheme {
+            prefix: prefix.to_owned(),
+            separator: separator.to_owned(),
+            ..Default::default()
+        })
+    }
+
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_environment(mut self, environment: Option<String>) -> Self {
+        self.environment = environment.filter(|env| !env.is_empty());
+        self
+    }
+
+    // the prefix a fleet's slots in a region share
+    pub fn prefix(&self, tag: &str, region: &str) -> String {
+        let prefix = self
+            .prefix
+            .replace("{tag}", tag)
+            .replace("{region}", region);
+        match &self.environment {
+            Some(env) => format!("{}-{}", env, prefix),
+            None => prefix,
+        }
+    }
+
+    // the name of slot n of prefix
+    pub fn slot(&self, prefix: &str, n: i32) -> String {
+        format!(
+            "{}{}{:0width$}",
+            prefix,
+            self.separator,
+            n,
+            width = self.width
+        )
+    }
+
+    // every slot name of prefix as a glob, for display
+    pub fn pattern(&self, prefix: &str) -> String {
+        format!("{}{}*", prefix, self.separator)
+    }
+
+    // the number of a slot name of prefix, None if name isn't one
+    pub fn sequence(&self, prefix: &str, name: &str) -> Option<i32> {
+        let n = name.strip_prefix(prefix)?.strip_prefix(&self.separator)?;
+        if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        n.parse().ok()
+    }
+
+    pub fn is_slot(&self, prefix: &str, name: &str) -> bool {
+        self.sequence(prefix, name).is_some()
+    }
+
+    // the region code and number of a slot name of tag in any region
+    pub fn slot_of(&self, tag: &str, name: &str) -> Option<(&'static str, i32)> {
+        REGIONS.values().find_map(|info| {
+            self.sequence(&self.prefix(tag, info.code), name)
+                .map(|n| (info.code, n))
+        })
+    }
+
+    // which of tags a slot name in region belongs to
+    pub fn fleet_of<'a>(&self, name: &str, tags: &'a [String], region: &str) -> Option<&'a str> {
+        tags.iter()
+            .map(String::as_str)
+            .find(|tag| self.is_slot(&self.prefix(tag, region), name))
+    }
+}
+
+impl LinodeClient {
+    // name scale slots by scheme instead of {tag}-{region}-{n}
+    pub fn with_naming(mut self, scheme: NamingScheme) -> Self {
+        self.naming = scheme;
+        self
+    }
+
+    pub fn naming(&self) -> &NamingScheme {
+        &self.naming
+    }
+}
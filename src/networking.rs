@@ -0,0 +1,198 @@
+use crate::firewall::{Firewall, FirewallDevice, FirewallRules};
+use crate::instances::LinodeInstance;
+use crate::regions::RegionInfo;
+use crate::{LinodeClient, LinodeError, ListOptions};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Interfaces {
+    pub interfaces: Vec<Interface>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interface {
+    pub purpose: String,
+    pub ipam_address: Option<String>,
+    pub label: Option<String>,
+}
+
+// Interface, VLAN and firewall calls, from LinodeClient::networking().
+#[derive(Clone, Copy)]
+pub struct Networking<'a> {
+    client: &'a LinodeClient,
+}
+
+impl LinodeClient {
+    pub fn networking(&self) -> Networking<'_> {
+        Networking { client: self }
+    }
+
+    pub async fn set_interfaces(
+        &self,
+        id: u64,
+        config_id: u64,
+        interfaces: Interfaces,
+    ) -> Result<(), LinodeError> {
+        info!(
+            "Setting interfaces for instance ID: {} with config ID: {}",
+            id, config_id
+        );
+        self.send(
+            self.client
+                .put(format!(
+                    "{}/linode/instances/{}/configs/{}",
+                    self.base_url, id, config_id
+                ))
+                .bearer_auth(self.token.expose())
+                .json(&interfaces),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        info!(
+            "Interfaces set successfully for instance ID: {} with config ID: {}",
+            id, config_id
+        );
+        Ok(())
+    }
+
+    // the next free host number on the fleet's /24 VLAN
+    pub async fn next_vlan_host(&self, tag: &str, region: &RegionInfo) -> Result<u8, LinodeError> {
+        let fleet = self.fleet_vlan_addresses(tag, region).await?;
+        Ok(next_host(&fleet))
+    }
+
+    // the fleet's instances with the addresses they hold on its VLAN,
+    // without the prefix length
+    pub(crate) async fn fleet_vlan_addresses(
+        &self,
+        tag: &str,
+        region: &RegionInfo,
+    ) -> Result<Vec<(LinodeInstance, Vec<String>)>, LinodeError> {
+        let instances = self.get_fleet_instances(tag, region).await?;
+
+        let mut fleet = Vec::new();
+        for instance in instances {
+            let configs = self.get_instance_configurations(instance.id).await?;
+            let addresses = configs
+                .iter()
+                .flat_map(|config| &config.interfaces)
+                .filter(|interface| interface.label.as_deref() == Some(tag))
+                .filter_map(|interface| interface.ipam_address.as_deref())
+                .map(|ipam| ipam.split('/').next().unwrap_or(ipam).to_owned())
+                .collect();
+            fleet.push((instance, addresses));
+        }
+        Ok(fleet)
+    }
+}
+
+// one past the highest host number the fleet holds, 1 for an empty fleet
+pub(crate) fn next_host(fleet: &[(LinodeInstance, Vec<String>)]) -> u8 {
+    let mut cidrs: Vec<u8> = Vec::new();
+    for address in fleet.iter().flat_map(|(_, addresses)| addresses) {
+        match address.rsplit('.').next().unwrap_or_default().parse::<u8>() {
+            Ok(n) => cidrs.push(n),
+            Err(e) => {
+                error!("Parsing error in next_vlan_host: {}", e);
+            }
+        }
+    }
+
+    if let Some(max) = cidrs.iter().max() {
+        max + 1
+    } else {
+        1
+    }
+}
+
+impl Networking<'_> {
+    pub async fn set_interfaces(
+        &self,
+        id: u64,
+        config_id: u64,
+        interfaces: Interfaces,
+    ) -> Result<(), LinodeError> {
+        self.client.set_interfaces(id, config_id, interfaces).await
+    }
+
+    pub async fn next_vlan_host(&self, tag: &str, region: &RegionInfo) -> Result<u8, LinodeError> {
+        self.client.next_vlan_host(tag, region).await
+    }
+
+    pub async fn fetch_firewalls(
+        &self,
+        options: &ListOptions,
+    ) -> Result<Vec<Firewall>, LinodeError> {
+        self.client.fetch_firewalls(options).await
+    }
+
+    pub async fn create_firewall(
+        &self,
+        label: &str,
+        rules: FirewallRules,
+        tags: Vec<String>,
+    ) -> Result<Firewall, LinodeError> {
+        self.client.create_firewall(label, rules, tags).await
+    }
+
+    pub async fn fetch_firewall_rules(&self, id: u64) -> Result<FirewallRules, LinodeError> {
+        self.client.fetch_firewall_rules(id).await
+    }
+
+    pub async fn set_firewall_rules(
+        &self,
+        id: u64,
+        rules: &FirewallRules,
+    ) -> Result<FirewallRules, LinodeError> {
+        self.client.set_firewall_rules(id, rules).await
+    }
+
+    pub async fn fetch_firewall_devices(
+        &self,
+        id: u64,
+    ) -> Result<Vec<FirewallDevice>, LinodeError> {
+        self.client.fetch_firewall_devices(id).await
+    }
+
+    pub async fn attach_firewall(
+        &self,
+        id: u64,
+        linode_id: u64,
+    ) -> Result<FirewallDevice, LinodeError> {
+        self.client.attach_firewall(id, linode_id).await
+    }
+}
+
+// the network part of a /24 VLAN subnet, "10.8.0.0/24" gives "10.8.0"
+pub(crate) fn vlan_network(subnet: Option<&str>) -> Result<String, LinodeError> {
+    let Some(subnet) = subnet else {
+        return Ok("10.0.0".to_owned());
+    };
+    let invalid = || LinodeError::Spec(format!("VLAN subnet {} is not an IPv4 /24", subnet));
+    let (addr, len) = subnet.split_once('/').ok_or_else(invalid)?;
+    let addr = addr.parse::<std::net::Ipv4Addr>().map_err(|_| invalid())?;
+    if len != "24" {
+        return Err(invalid());
+    }
+    let [a, b, c, _] = addr.octets();
+    Ok(format!("{}.{}.{}", a, b, c))
+}
+
+pub(crate) fn public_interface() -> Interface {
+    Interface {
+        purpose: "public".to_string(),
+        ipam_address: None,
+        label: None,
+    }
+}
+
+pub(crate) fn vlan_interface(tag: &str, ipam: String) -> Interface {
+    Interface {
+        label: Some(tag.to_string()),
+        ipam_address: Some(ipam),
+        purpose: "vlan".to_string(),
+    }
+}
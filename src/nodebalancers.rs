@@ -0,0 +1,182 @@
+use crate::{LinodeClient, LinodeError, ListOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeBalancer {
+    pub id: u64,
+    pub label: String,
+    pub region: String,
+    pub hostname: Option<String>,
+    pub ipv4: Option<String>,
+    pub ipv6: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct NodesStatus {
+    pub up: u32,
+    pub down: u32,
+}
+
+// One port of a NodeBalancer. The API never returns the certificate or
+// key, only the common name and fingerprint of the one being served.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeBalancerConfig {
+    pub id: u64,
+    pub port: u16,
+    // http, https or tcp
+    pub protocol: String,
+    pub ssl_commonname: Option<String>,
+    pub ssl_fingerprint: Option<String>,
+    #[serde(default)]
+    pub nodes_status: NodesStatus,
+}
+
+// The certificate an HTTPS config served before and after a rotation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertRotation {
+    pub config_id: u64,
+    pub port: u16,
+    pub previous_fingerprint: Option<String>,
+    pub fingerprint: String,
+    pub common_name: Option<String>,
+}
+
+impl CertRotation {
+    // the config was already serving the uploaded certificate
+    pub fn is_unchanged(&self) -> bool {
+        self.previous_fingerprint.as_deref() == Some(self.fingerprint.as_str())
+    }
+}
+
+impl LinodeClient {
+    pub async fn fetch_nodebalancer(&self, id: u64) -> Result<NodeBalancer, LinodeError> {
+        info!("Fetching NodeBalancer ID: {}", id);
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}/nodebalancers/{}", self.base_url, id))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+
+        response.json::<NodeBalancer>().await
+    }
+
+    pub async fn fetch_nodebalancer_configs(
+        &self,
+        id: u64,
+    ) -> Result<Vec<NodeBalancerConfig>, LinodeError> {
+        info!("Fetching configs for NodeBalancer ID: {}", id);
+        let configs = self
+            .fetch_all::<NodeBalancerConfig>(
+                &format!("nodebalancers/{}/configs", id),
+                &ListOptions::default(),
+            )
+            .await?;
+        info!(
+            "Fetched {} configs for NodeBalancer ID: {}",
+            configs.len(),
+            id
+        );
+
+        Ok(configs)
+    }
+
+    pub async fn fetch_nodebalancer_config(
+        &self,
+        id: u64,
+        config_id: u64,
+    ) -> Result<NodeBalancerConfig, LinodeError> {
+        let response = self
+            .send(
+                self.client
+                    .get(format!(
+                        "{}/nodebalancers/{}/configs/{}",
+                        self.base_url, id, config_id
+                    ))
+                    .bearer_auth(self.token.expose()),
+            )
+            .await?;
+
+        response.json::<NodeBalancerConfig>().await
+    }
+
+    // Upload a PEM certificate (chain) and key to the NodeBalancer's HTTPS
+    // configs, or only config_id, then re-read each config to confirm the
+    // API reports a certificate in service. Fails if there's no HTTPS
+    // config to rotate.
+    pub async fn rotate_nodebalancer_cert(
+        &self,
+        id: u64,
+        config_id: Option<u64>,
+        cert: &str,
+        key: &str,
+    ) -> Result<Vec<CertRotation>, LinodeError> {
+        let configs = self
+            .fetch_nodebalancer_configs(id)
+            .await?
+            .into_iter()
+            .filter(|config| config.protocol == "https")
+            .filter(|config| config_id.is_none_or(|config_id| config.id == config_id))
+            .collect::<Vec<_>>();
+        if configs.is_empty() {
+            return Err(LinodeError::NodeBalancer(format!(
+                "NodeBalancer ID: {} has no HTTPS config{}",
+                id,
+                config_id
+                    .map(|config_id| format!(" with ID: {}", config_id))
+                    .unwrap_or_default()
+            )));
+        }
+
+        let mut rotations = Vec::new();
+        for config in configs {
+            info!(
+                "Uploading certificate to NodeBalancer ID: {} config ID: {} (port {})",
+                id, config.id, config.port
+            );
+            self.send(
+                self.client
+                    .put(format!(
+                        "{}/nodebalancers/{}/configs/{}",
+                        self.base_url, id, config.id
+                    ))
+                    .bearer_auth(self.token.expose())
+                    .json(&json!({ "ssl_cert": cert, "ssl_key": key })),
+            )
+            .await?
+            .error_for_status()
+            .await?;
+
+            let served = self.fetch_nodebalancer_config(id, config.id).await?;
+            let fingerprint = served
+                .ssl_fingerprint
+                .filter(|f| !f.is_empty())
+                .ok_or_else(|| {
+                    LinodeError::NodeBalancer(format!(
+                        "config ID: {} reports no certificate after the upload",
+                        config.id
+                    ))
+                })?;
+            info!(
+                "NodeBalancer ID: {} port {} is serving {} ({})",
+                id,
+                config.port,
+                served.ssl_commonname.as_deref().unwrap_or("-"),
+                fingerprint
+            );
+            rotations.push(CertRotation {
+                config_id: config.id,
+                port: config.port,
+                previous_fingerprint: config.ssl_fingerprint,
+                fingerprint,
+                common_name: served.ssl_commonname,
+            });
+        }
+        Ok(rotations)
+    }
+}
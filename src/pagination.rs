@@ -0,0 +1,117 @@
+use crate::{LinodeClient, LinodeError, ListOptions};
+use futures::{stream, Stream, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use tracing::info;
+
+// One page of a list endpoint, the envelope every /v4 list call returns.
+// page counts from 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub page: u32,
+    pub pages: u32,
+    pub results: u32,
+}
+
+impl<T> Page<T> {
+    pub fn is_last(&self) -> bool {
+        self.page >= self.pages
+    }
+}
+
+// Walks a list endpoint one page at a time, starting at options.page or
+// the first page. Works for any path, so endpoints this crate doesn't
+// wrap yet can be listed with a caller-defined T.
+pub struct Paginator<'a, T> {
+    client: &'a LinodeClient,
+    path: String,
+    options: ListOptions,
+    next: Option<u32>,
+    item: PhantomData<T>,
+}
+
+impl<'a, T: DeserializeOwned + 'a> Paginator<'a, T> {
+    // None once the last page has been returned
+    pub async fn next_page(&mut self) -> Result<Option<Page<T>>, LinodeError> {
+        let Some(page) = self.next else {
+            return Ok(None);
+        };
+        let options = ListOptions {
+            page: Some(page),
+            ..self.options.clone()
+        };
+        let page = self.client.fetch_page::<T>(&self.path, &options).await?;
+        self.next = (!page.is_last()).then_some(page.page + 1);
+        Ok(Some(page))
+    }
+
+    // every remaining item, fetching pages until the last
+    pub async fn collect_all(mut self) -> Result<Vec<T>, LinodeError> {
+        let mut items = Vec::new();
+        while let Some(page) = self.next_page().await? {
+            items.extend(page.data);
+        }
+        Ok(items)
+    }
+
+    // Every remaining item as a stream. A page is only fetched once the
+    // items before it have been taken, so at most one page is held.
+    pub fn into_stream(self) -> impl Stream<Item = Result<T, LinodeError>> + 'a {
+        stream::try_unfold(self, |mut pages| async move {
+            let page = pages.next_page().await?;
+            let items = page.map(|page| stream::iter(page.data.into_iter().map(Ok)));
+            Ok::<_, LinodeError>(items.map(|items| (items, pages)))
+        })
+        .try_flatten()
+    }
+}
+
+impl LinodeClient {
+    // a single page of path, e.g. "linode/instances"
+    pub async fn fetch_page<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        options: &ListOptions,
+    ) -> Result<Page<T>, LinodeError> {
+        let page = self
+            .send(self.list_request(path, options))
+            .await?
+            .json::<Page<T>>()
+            .await?;
+        info!(
+            "Fetched page {}/{} of {} ({} results)",
+            page.page, page.pages, path, page.results
+        );
+        Ok(page)
+    }
+
+    // Every item of path, following page/pages until the last page. When
+    // options.page is set only that page is fetched, so callers can still
+    // ask for one page explicitly.
+    pub async fn fetch_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        options: &ListOptions,
+    ) -> Result<Vec<T>, LinodeError> {
+        if options.page.is_some() {
+            return Ok(self.fetch_page::<T>(path, options).await?.data);
+        }
+        self.paginate::<T>(path, options).collect_all().await
+    }
+
+    pub fn paginate<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        options: &ListOptions,
+    ) -> Paginator<'_, T> {
+        Paginator {
+            client: self,
+            path: path.to_owned(),
+            options: options.clone(),
+            next: Some(options.page.unwrap_or(1)),
+            item: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::regions::{find_region, RegionInfo};
+use crate::{LinodeClient, LinodeError};
+use rand::Rng;
+use std::collections::BTreeMap;
+use tracing::info;
+
+// capabilities every scaled-up region needs
+const REQUIRED_CAPABILITIES: &[&str] = &["linodes", "vlans"];
+
+// The API answers a create it has no room for with a 400 naming the
+// region or plan as unavailable or sold out.
+pub fn is_capacity_error(e: &LinodeError) -> bool {
+    match e {
+        LinodeError::Validation { reasons, .. } => reasons.iter().any(|reason| {
+            let reason = reason.reason.to_lowercase();
+            reason.contains("capacity")
+                || reason.contains("sold out")
+                || reason.contains("not available")
+        }),
+        _ => false,
+    }
+}
+
+// Regions to place instances in, each picked with probability
+// proportional to its weight. Regions that fail with a capacity error
+// are excluded for the rest of the run.
+#[derive(Debug, Clone)]
+pub struct RegionPicker {
+    candidates: Vec<(&'static RegionInfo, u32)>,
+}
+
+impl RegionPicker {
+    // weights keyed by region name or code; zero weights are left out
+    pub fn new(weights: &BTreeMap<String, u32>) -> Result<Self, LinodeError> {
+        let mut candidates = Vec::new();
+        for (name, weight) in weights {
+            let info = find_region(name)
+                .ok_or_else(|| LinodeError::Spec(format!("unknown region {} in weights", name)))?;
+            if *weight > 0 {
+                candidates.push((info, *weight));
+            }
+        }
+        Ok(RegionPicker { candidates })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    pub fn pick(&self) -> Option<&'static RegionInfo> {
+        let total = self.candidates.iter().map(|(_, w)| *w).sum::<u32>();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rand::thread_rng().gen_range(0..total);
+        for (info, weight) in &self.candidates {
+            if roll < *weight {
+                return Some(info);
+            }
+            roll -= weight;
+        }
+        None
+    }
+
+    pub fn exclude(&mut self, region: &str) {
+        self.candidates.retain(|(info, _)| info.region != region);
+    }
+}
+
+impl LinodeClient {
+    // A picker over weights, leaving out regions the live /regions
+    // endpoint reports as down or without Linodes and VLANs.
+    pub async fn region_picker(
+        &self,
+        weights: &BTreeMap<String, u32>,
+    ) -> Result<RegionPicker, LinodeError> {
+        let mut picker = RegionPicker::new(weights)?;
+        let live = self.fetch_regions().await?;
+        picker.candidates.retain(|(info, _)| {
+            let usable = live.iter().any(|region| {
+                region.id == info.region
+                    && region.status == "ok"
+                    && REQUIRED_CAPABILITIES
+                        .iter()
+                        .all(|capability| region.has_capability(capability))
+            });
+            if !usable {
+                info!(
+                    "Leaving out region: {}, unavailable or missing capabilities",
+                    info.region
+                );
+            }
+            usable
+        });
+        Ok(picker)
+    }
+}
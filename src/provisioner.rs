@@ -0,0 +1,177 @@
+use crate::metadata::NodeIdentity;
+use crate::{LinodeError, LinodeInstance};
+use async_trait::async_trait;
+use serde_json::json;
+use std::fmt;
+use tracing::info;
+
+#[cfg(feature = "native")]
+pub use self::ssh::SshProvisioner;
+
+// Post-create step run once a scaled instance is running and before it is
+// registered, so a node that fails to bootstrap never receives traffic.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Provisioner: fmt::Debug + Send + Sync {
+    async fn provision(
+        &self,
+        instance: &LinodeInstance,
+        identity: &NodeIdentity,
+    ) -> Result<(), LinodeError>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NoopProvisioner;
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Provisioner for NoopProvisioner {
+    async fn provision(
+        &self,
+        _instance: &LinodeInstance,
+        _identity: &NodeIdentity,
+    ) -> Result<(), LinodeError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "native")]
+mod ssh {
+    use super::*;
+    use crate::STATUS_POLL_INTERVAL;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tokio::process::Command;
+    use web_time::Instant;
+
+    // Runs a command over ssh on the instance's public address, retrying until
+    // sshd accepts connections or the timeout passes.
+    #[derive(Debug, Clone)]
+    pub struct SshProvisioner {
+        pub user: String,
+        pub command: String,
+        pub identity_file: Option<PathBuf>,
+        pub timeout: Duration,
+    }
+
+    impl SshProvisioner {
+        pub fn new(command: &str) -> Self {
+            SshProvisioner {
+                user: "root".to_owned(),
+                command: command.to_owned(),
+                identity_file: None,
+                timeout: Duration::from_secs(300),
+            }
+        }
+
+        fn ssh(&self, host: &str, identity: &NodeIdentity) -> Command {
+            let mut cmd = Command::new("ssh");
+            cmd.args(["-o", "BatchMode=yes"])
+                .args(["-o", "StrictHostKeyChecking=accept-new"])
+                .args(["-o", "ConnectTimeout=10"]);
+            if let Some(path) = &self.identity_file {
+                cmd.arg("-i").arg(path);
+            }
+            // the node identity is prepended as shell variables
+            cmd.arg(format!("{}@{}", self.user, host)).arg(format!(
+                "{} {}",
+                identity.to_env().lines().collect::<Vec<_>>().join(" "),
+                self.command
+            ));
+            cmd
+        }
+    }
+
+    #[async_trait]
+    impl Provisioner for SshProvisioner {
+        async fn provision(
+            &self,
+            instance: &LinodeInstance,
+            identity: &NodeIdentity,
+        ) -> Result<(), LinodeError> {
+            let host = &instance.ipv4[0];
+            info!(
+                "Provisioning instance ID: {} over ssh at {}",
+                instance.id, host
+            );
+            let start = Instant::now();
+            loop {
+                let output = self
+                    .ssh(host, identity)
+                    .output()
+                    .await
+                    .map_err(|e| LinodeError::Provision(format!("failed to run ssh: {}", e)))?;
+
+                match output.status.code() {
+                    Some(0) => {
+                        info!("Provisioned instance ID: {}", instance.id);
+                        return Ok(());
+                    }
+                    // 255 is ssh itself failing, most likely sshd isn't up yet
+                    Some(255) if start.elapsed() < self.timeout => {
+                        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+                    }
+                    code => {
+                        return Err(LinodeError::Provision(format!(
+                            "ssh command on instance ID: {} exited with {:?}: {}",
+                            instance.id,
+                            code,
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        )))
+                    }
+                }
+            }
+        }
+    }
+}
+
+// POSTs the instance ID, address and node identity as JSON to a URL and
+// expects a 2xx response.
+#[derive(Debug, Clone)]
+pub struct HttpCallbackProvisioner {
+    pub url: String,
+    client: reqwest::Client,
+}
+
+impl HttpCallbackProvisioner {
+    pub fn new(url: &str) -> Self {
+        HttpCallbackProvisioner {
+            url: url.to_owned(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Provisioner for HttpCallbackProvisioner {
+    async fn provision(
+        &self,
+        instance: &LinodeInstance,
+        identity: &NodeIdentity,
+    ) -> Result<(), LinodeError> {
+        info!(
+            "Calling provisioning hook: {} for instance ID: {}",
+            self.url, instance.id
+        );
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&json!({
+                "id": instance.id,
+                "ipv4": instance.ipv4,
+                "identity": identity,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(LinodeError::Provision(format!(
+                "callback {} returned {}",
+                self.url,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
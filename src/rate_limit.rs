@@ -0,0 +1,107 @@
+use crate::{LinodeClient, LinodeError};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, info};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+const LIMIT_HEADER: &str = "X-RateLimit-Limit";
+const REMAINING_HEADER: &str = "X-RateLimit-Remaining";
+const RESET_HEADER: &str = "X-RateLimit-Reset";
+
+// once less than this percent of a window's budget is left, the rest is
+// spread evenly over the time until it resets
+const PACE_BELOW_PERCENT: u64 = 10;
+
+// The API's request budget as of the last response that reported it.
+// reset is the unix time the window refills.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset: u64,
+}
+
+impl RateLimit {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        Some(RateLimit {
+            limit: header(LIMIT_HEADER)?,
+            remaining: header(REMAINING_HEADER)?,
+            reset: header(RESET_HEADER)?,
+        })
+    }
+
+    // how long to hold the next request at unix time now so the budget
+    // left lasts until the window resets
+    pub fn pacing_delay(&self, now: u64) -> Duration {
+        if now >= self.reset {
+            return Duration::ZERO;
+        }
+        let until_reset = Duration::from_secs(self.reset - now);
+        if self.remaining == 0 {
+            return until_reset;
+        }
+        if self.remaining * 100 >= self.limit * PACE_BELOW_PERCENT {
+            return Duration::ZERO;
+        }
+        until_reset / (self.remaining as u32 + 1)
+    }
+}
+
+impl LinodeClient {
+    // None until a response has carried the rate limit headers
+    pub fn last_rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    // wait out the pacing delay of the last known rate limit
+    pub(crate) async fn pace(&self) {
+        if !self.rate_limit_pacing {
+            return;
+        }
+        let Some(rate_limit) = self.last_rate_limit() else {
+            return;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let delay = rate_limit.pacing_delay(now);
+        if !delay.is_zero() {
+            info!(
+                "Rate limit low ({}/{} left), waiting {:?} before the next request",
+                rate_limit.remaining, rate_limit.limit, delay
+            );
+            self.pause(delay).await;
+        }
+    }
+
+    pub(crate) fn note_rate_limit(&self, headers: &HeaderMap) {
+        if let Some(rate_limit) = RateLimit::from_headers(headers) {
+            debug!(
+                "Rate limit: {}/{} left, resets at {}",
+                rate_limit.remaining, rate_limit.limit, rate_limit.reset
+            );
+            *self.rate_limit.lock().unwrap() = Some(rate_limit);
+        }
+    }
+
+    // make a cheap call to learn the current budget
+    pub async fn fetch_rate_limit(&self) -> Result<Option<RateLimit>, LinodeError> {
+        self.send(
+            self.client
+                .get(format!("{}/profile", self.base_url))
+                .bearer_auth(self.token.expose()),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+        Ok(self.last_rate_limit())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::zone_health::unix_seconds;
+use crate::{LinodeClient, LinodeError, ListOptions};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::info;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+// "90s", "30m", "24h", "7d" or "2w"; a bare number is seconds
+pub fn parse_age(age: &str) -> Result<Duration, LinodeError> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (count, unit) = age.split_at(split);
+    let count = count
+        .parse::<u64>()
+        .map_err(|_| LinodeError::Spec(format!("invalid age: {}", age)))?;
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(LinodeError::Spec(format!("invalid age unit: {}", age))),
+    };
+    Ok(Duration::from_secs(count * seconds))
+}
+
+// An instance or image old enough to be reaped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reaped {
+    pub id: String,
+    pub label: String,
+    pub created: String,
+    pub age_seconds: u64,
+}
+
+// What a reap removed, or would have with dry_run; failed holds the
+// labels that couldn't be removed and why.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReapReport {
+    pub instances: Vec<Reaped>,
+    pub images: Vec<Reaped>,
+    pub failed: Vec<(String, String)>,
+}
+
+// age of an API timestamp at now, None when it can't be parsed
+fn age_of(created: &str, now: u64) -> Option<u64> {
+    unix_seconds(created).map(|created| now.saturating_sub(created))
+}
+
+impl LinodeClient {
+    // Destroy instances tagged tag that were created more than older_than
+    // ago, e.g. forgotten image builders or warm pool nodes. Their disks
+    // go with them. With images, private images carrying the tag that no
+    // instance uses any more are deleted too. Nothing is removed with
+    // dry_run, the report lists what would be.
+    pub async fn reap(
+        &self,
+        tag: &str,
+        older_than: Duration,
+        images: bool,
+        dry_run: bool,
+    ) -> Result<ReapReport, LinodeError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let threshold = older_than.as_secs();
+        let mut report = ReapReport::default();
+
+        info!(
+            "Reaping instances tagged: {} older than {:?}",
+            tag, older_than
+        );
+        for instance in self.get_instances_by_tag(vec![tag]).await? {
+            let Some(age_seconds) = age_of(&instance.created, now) else {
+                info!(
+                    "Skipping instance ID: {}, unreadable created time: {}",
+                    instance.id, instance.created
+                );
+                continue;
+            };
+            if age_seconds < threshold {
+                continue;
+            }
+            if !dry_run {
+                if let Err(e) = self.destroy_instance(instance.id).await {
+                    report.failed.push((instance.label, e.to_string()));
+                    continue;
+                }
+            }
+            report.instances.push(Reaped {
+                id: instance.id.to_string(),
+                label: instance.label,
+                created: instance.created,
+                age_seconds,
+            });
+        }
+
+        if images {
+            // the reaped instances are gone (or will be), so they don't
+            // count as users
+            let in_use = self
+                .fetch_instances()
+                .await?
+                .into_iter()
+                .filter(|i| {
+                    !report
+                        .instances
+                        .iter()
+                        .any(|reaped| reaped.id == i.id.to_string())
+                })
+                .filter_map(|i| i.image)
+                .collect::<Vec<_>>();
+            for image in self.fetch_images(&ListOptions::default()).await? {
+                if image.is_public || !image.tags.iter().any(|t| t == tag) {
+                    continue;
+                }
+                let Some(age_seconds) = age_of(&image.created, now) else {
+                    continue;
+                };
+                if age_seconds < threshold || in_use.contains(&image.id) {
+                    continue;
+                }
+                if !dry_run {
+                    if let Err(e) = self.delete_image(&image.id).await {
+                        report.failed.push((image.label, e.to_string()));
+                        continue;
+                    }
+                }
+                report.images.push(Reaped {
+                    id: image.id,
+                    label: image.label,
+                    created: image.created,
+                    age_seconds,
+                });
+            }
+        }
+
+        info!(
+            "Reaped {} instances and {} images tagged: {}{}",
+            report.instances.len(),
+            report.images.len(),
+            tag,
+            if dry_run { " (dry run)" } else { "" }
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::cordon::cordoned_slot;
+use crate::{LinodeClient, LinodeError, A_RECORD, LOCALHOST};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::info;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    pub tag: String,
+    #[serde(default)]
+    pub domain: u64,
+    // slots that pointed at no live instance and were parked
+    pub parked: Vec<String>,
+    // live, uncordoned instances without a slot, left for an operator
+    pub unregistered: Vec<String>,
+    // slots of a mirror domain brought in line with the primary's
+    #[serde(default)]
+    pub mirrored: Vec<String>,
+}
+
+impl LinodeClient {
+    // Bring a tag's DNS slots back in line with its instances after
+    // changes made outside this tool, e.g. an instance deleted in the
+    // Cloud Manager: slots pointing at addresses no tagged instance holds
+    // are parked. Instances are never created or destroyed here.
+    pub async fn reconcile_dns(
+        &self,
+        domain: u64,
+        tag: &str,
+    ) -> Result<ReconcileReport, LinodeError> {
+        info!("Reconciling DNS for tag: {} in domain ID: {}", tag, domain);
+        self.invalidate_record_cache(domain);
+        let records = self.fetch_records(domain).await?;
+        let instances = self.get_instances_by_tag(vec![tag]).await?;
+        let live = instances
+            .iter()
+            .flat_map(|instance| instance.ipv4.iter())
+            .collect::<HashSet<_>>();
+
+        let mut report = ReconcileReport {
+            tag: tag.to_owned(),
+            domain,
+            ..Default::default()
+        };
+        let slots = records
+            .iter()
+            .filter(|rec| {
+                rec.record_type == A_RECORD && self.naming().slot_of(tag, &rec.name).is_some()
+            })
+            .collect::<Vec<_>>();
+        for slot in &slots {
+            if slot.target != LOCALHOST && !live.contains(&slot.target) {
+                info!(
+                    "Slot: {} points at {}, which no instance with tag: {} holds",
+                    slot.name, slot.target, tag
+                );
+                self.remove_instance_record(domain, &slot.name).await?;
+                self.update_record_target(domain, slot.id, LOCALHOST)
+                    .await?;
+                report.parked.push(slot.name.clone());
+            }
+        }
+        for instance in &instances {
+            let registered = slots
+                .iter()
+                .any(|slot| instance.ipv4.first() == Some(&slot.target));
+            if !registered && cordoned_slot(instance).is_none() {
+                report.unregistered.push(instance.label.clone());
+            }
+        }
+
+        info!(
+            "Reconciled tag: {}, parked {} slots, {} instances unregistered",
+            tag,
+            report.parked.len(),
+            report.unregistered.len()
+        );
+        Ok(report)
+    }
+
+    // Reconcile tag in domain and in each mirror domain, then point every
+    // mirror's records for the primary's slots where the primary does, so
+    // all the zones agree. One report per domain, the primary's first.
+    pub async fn reconcile_dns_mirrored(
+        &self,
+        domain: u64,
+        mirrors: &[u64],
+        tag: &str,
+    ) -> Result<Vec<ReconcileReport>, LinodeError> {
+        let mut reports = vec![self.reconcile_dns(domain, tag).await?];
+        let slots = self
+            .fetch_records(domain)
+            .await?
+            .into_iter()
+            .filter(|rec| {
+                rec.record_type == A_RECORD && self.naming().slot_of(tag, &rec.name).is_some()
+            })
+            .collect::<Vec<_>>();
+        for mirror in mirrors.iter().filter(|mirror| **mirror != domain) {
+            let mut report = self.reconcile_dns(*mirror, tag).await?;
+            for slot in &slots {
+                if self
+                    .mirror_record(*mirror, &slot.name, &slot.target)
+                    .await?
+                {
+                    report.mirrored.push(slot.name.clone());
+                }
+            }
+            info!(
+                "Mirrored {} slots of tag: {} into domain ID: {}",
+                report.mirrored.len(),
+                tag,
+                mirror
+            );
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+}
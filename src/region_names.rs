@@ -0,0 +1,84 @@
+use crate::regions::RegionGroups;
+use crate::{DomainRecordOptions, LinodeClient, LinodeError, A_RECORD, LOCALHOST};
+use std::collections::BTreeSet;
+use tracing::info;
+
+// Changes made to one group's round-robin name.
+#[derive(Debug, Clone)]
+pub struct RegionNameSync {
+    pub name: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+// {tag}.{group}, e.g. api.eu for every slot of api in gb-lon and fr-par
+pub fn region_name(tag: &str, group: &str) -> String {
+    format!("{}.{}", tag, group.trim_start_matches('@'))
+}
+
+impl LinodeClient {
+    // Point {tag}.{group} at every claimed slot of the tag in the group's
+    // regions, one A record per target, so clients can resolve a
+    // continent-level name. Groups without live slots end up with no
+    // records. Run after each scale event to keep the names current.
+    pub async fn sync_region_names(
+        &self,
+        domain: u64,
+        tag: &str,
+        groups: &RegionGroups,
+    ) -> Result<Vec<RegionNameSync>, LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        let mut synced = Vec::new();
+
+        for group in groups.names() {
+            let codes = groups
+                .resolve(&format!("@{}", group))
+                .map_err(LinodeError::Spec)?;
+            let name = region_name(tag, group);
+            let wanted = records
+                .iter()
+                .filter(|rec| rec.record_type == A_RECORD && rec.target != LOCALHOST)
+                .filter(|rec| {
+                    codes.iter().any(|info| {
+                        self.naming()
+                            .is_slot(&self.naming().prefix(tag, info.code), &rec.name)
+                    })
+                })
+                .map(|rec| rec.target.clone())
+                .collect::<BTreeSet<_>>();
+            let current = records
+                .iter()
+                .filter(|rec| rec.record_type == A_RECORD && rec.name == name)
+                .collect::<Vec<_>>();
+
+            let mut removed = Vec::new();
+            for rec in &current {
+                if !wanted.contains(&rec.target) {
+                    self.delete_record(domain, rec.id).await?;
+                    removed.push(rec.target.clone());
+                }
+            }
+            let mut added = Vec::new();
+            for target in &wanted {
+                if !current.iter().any(|rec| &rec.target == target) {
+                    self.post_record(domain, &DomainRecordOptions::a(&name, target))
+                        .await?;
+                    added.push(target.clone());
+                }
+            }
+
+            if !added.is_empty() || !removed.is_empty() {
+                info!(
+                    "Region name {} in domain ID: {}: added {:?}, removed {:?}",
+                    name, domain, added, removed
+                );
+            }
+            synced.push(RegionNameSync {
+                name,
+                added,
+                removed,
+            });
+        }
+        Ok(synced)
+    }
+}
@@ -1,6 +1,35 @@
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+// A region as reported by the live /regions endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Region {
+    pub id: String,
+    pub label: String,
+    pub country: String,
+    pub capabilities: Vec<String>,
+    pub status: String,
+}
+
+impl Region {
+    pub fn has_capability(&self, capability: &str) -> bool {
+        let wanted = normalize_capability(capability);
+        self.capabilities
+            .iter()
+            .any(|c| normalize_capability(c) == wanted)
+    }
+}
+
+// "Block Storage" -> "block-storage", "VLANs" -> "vlan"
+pub fn normalize_capability(capability: &str) -> String {
+    let normalized = capability.trim().to_lowercase().replace([' ', '_'], "-");
+    normalized
+        .strip_suffix('s')
+        .map(str::to_owned)
+        .unwrap_or(normalized)
+}
+
 #[derive(Debug, Clone)]
 pub struct RegionInfo {
     pub code: &'static str,
@@ -145,3 +174,95 @@ lazy_static! {
         m
     };
 }
+
+// Named groups of regions, referenced as "@name" wherever a region is
+// accepted. Users can add or replace groups via RegionGroups overrides.
+lazy_static! {
+    pub static ref REGION_GROUPS: HashMap<&'static str, Vec<&'static str>> = {
+        let mut m = HashMap::new();
+        m.insert("us", vec!["us-iad", "us-ord", "us-lax", "us-mia", "us-sea"]);
+        m.insert("eu", vec!["gb-lon", "fr-par", "it-mil", "se-sto"]);
+        m
+    };
+}
+
+// look up a region by its API name or by its IATA suffixed code
+pub fn find_region(name: &str) -> Option<&'static RegionInfo> {
+    REGIONS
+        .get(name)
+        .or_else(|| REGIONS.values().find(|info| info.code == name))
+}
+
+#[derive(Debug, Clone)]
+pub struct RegionGroups {
+    groups: HashMap<String, Vec<String>>,
+}
+
+impl Default for RegionGroups {
+    fn default() -> Self {
+        let groups = REGION_GROUPS
+            .iter()
+            .map(|(name, regions)| {
+                let regions = regions.iter().map(|r| r.to_string()).collect();
+                (name.to_string(), regions)
+            })
+            .collect();
+        RegionGroups { groups }
+    }
+}
+
+impl RegionGroups {
+    pub fn insert(&mut self, name: &str, regions: Vec<String>) {
+        self.groups
+            .insert(name.trim_start_matches('@').to_owned(), regions);
+    }
+
+    // parse an override of the form "name=region,region,..."
+    pub fn insert_override(&mut self, spec: &str) -> Result<(), String> {
+        let (name, regions) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("invalid region group '{}', expected name=r1,r2", spec))?;
+        let regions = regions
+            .split(',')
+            .map(|r| r.trim().to_owned())
+            .filter(|r| !r.is_empty())
+            .collect();
+        self.insert(name.trim(), regions);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Vec<String>> {
+        self.groups.get(name.trim_start_matches('@'))
+    }
+
+    // group names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.groups.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    // resolve a comma separated list of region names, codes and @groups
+    // into region info, preserving order and dropping duplicates
+    pub fn resolve(&self, spec: &str) -> Result<Vec<&'static RegionInfo>, String> {
+        let mut resolved: Vec<&'static RegionInfo> = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let names = if let Some(group) = part.strip_prefix('@') {
+                self.get(group)
+                    .ok_or_else(|| format!("Region group '@{}' not found.", group))?
+                    .clone()
+            } else {
+                vec![part.to_owned()]
+            };
+
+            for name in names {
+                let info = find_region(&name)
+                    .ok_or_else(|| format!("Region code '{}' not found.", name))?;
+                if !resolved.iter().any(|r| r.region == info.region) {
+                    resolved.push(info);
+                }
+            }
+        }
+        Ok(resolved)
+    }
+}
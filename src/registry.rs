@@ -0,0 +1,188 @@
+use crate::dns::predict_slot_name;
+use crate::instance_record::InstanceRecord;
+use crate::srv::SrvService;
+use crate::{LinodeClient, LinodeError, LinodeInstance, A_RECORD, LOCALHOST};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Registration {
+    pub name: String,
+    pub address: String,
+}
+
+// Where scaled nodes are published for discovery. The scale paths only
+// talk to this trait; LinodeDnsRegistry is the default, but anything
+// that can map a fleet prefix ({tag}-{region} by default, see
+// NamingScheme) to addresses will do. In the browser (wasm32) reqwest
+// futures aren't Send, so neither are these.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait ServiceRegistry: Send + Sync {
+    // name the next node will most likely be registered under, handed to
+    // the node at boot; registries that can't predict it return None
+    async fn peek(&self, _prefix: &str) -> Result<Option<String>, LinodeError> {
+        Ok(None)
+    }
+
+    // publish a running instance, returning the name it was registered as
+    async fn register(
+        &self,
+        prefix: &str,
+        instance: &LinodeInstance,
+    ) -> Result<String, LinodeError>;
+
+    // withdraw an instance, returning the name it was registered as or
+    // None if it wasn't registered
+    async fn deregister(
+        &self,
+        prefix: &str,
+        instance: &LinodeInstance,
+    ) -> Result<Option<String>, LinodeError>;
+
+    async fn list(&self, prefix: &str) -> Result<Vec<Registration>, LinodeError>;
+}
+
+// Sequential A records named by the client's NamingScheme in a Linode
+// managed domain, with the optional SRV and instance TXT records
+// alongside.
+pub struct LinodeDnsRegistry<'a> {
+    client: &'a LinodeClient,
+    domain: u64,
+    srv: Option<(String, SrvService)>,
+    instance_records: bool,
+    mirrors: Vec<u64>,
+}
+
+impl<'a> LinodeDnsRegistry<'a> {
+    pub fn new(client: &'a LinodeClient, domain: u64) -> Self {
+        LinodeDnsRegistry {
+            client,
+            domain,
+            srv: None,
+            instance_records: false,
+            mirrors: Vec::new(),
+        }
+    }
+
+    // also publish _service._proto.{tag} for every registered node
+    pub fn with_srv(mut self, tag: &str, srv: Option<SrvService>) -> Self {
+        self.srv = srv.map(|srv| (tag.to_owned(), srv));
+        self
+    }
+
+    pub fn with_instance_records(mut self, enabled: bool) -> Self {
+        self.instance_records = enabled;
+        self
+    }
+
+    // keep a same-named A record for every slot in each of domains too,
+    // e.g. the zones of other environments
+    pub fn with_mirrors(mut self, domains: &[u64]) -> Self {
+        self.mirrors = domains
+            .iter()
+            .copied()
+            .filter(|domain| *domain != self.domain)
+            .collect();
+        self
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ServiceRegistry for LinodeDnsRegistry<'_> {
+    async fn peek(&self, prefix: &str) -> Result<Option<String>, LinodeError> {
+        let zone = self.client.fetch_domain(self.domain).await?;
+        let records = self.client.fetch_records(self.domain).await?;
+        let slot = predict_slot_name(&records, self.client.naming(), prefix);
+        Ok(Some(format!("{}.{}", slot, zone.domain)))
+    }
+
+    async fn register(
+        &self,
+        prefix: &str,
+        instance: &LinodeInstance,
+    ) -> Result<String, LinodeError> {
+        let name = self
+            .client
+            .register_slot(self.domain, prefix, &instance.ipv4[0])
+            .await?;
+        for domain in &self.mirrors {
+            self.client
+                .mirror_record(*domain, &name, &instance.ipv4[0])
+                .await?;
+        }
+
+        if self.instance_records {
+            self.client
+                .write_instance_record(self.domain, &name, &InstanceRecord::from_instance(instance))
+                .await?;
+        }
+
+        if let Some((tag, srv)) = &self.srv {
+            let zone = self.client.fetch_domain(self.domain).await?;
+            self.client
+                .publish_srv(self.domain, srv, tag, &format!("{}.{}", name, zone.domain))
+                .await?;
+        }
+
+        Ok(name)
+    }
+
+    async fn deregister(
+        &self,
+        prefix: &str,
+        instance: &LinodeInstance,
+    ) -> Result<Option<String>, LinodeError> {
+        let records = self.client.fetch_records(self.domain).await?;
+        // only a slot of the fleet, not a round-robin or hand-made record
+        // that happens to point at the instance
+        let Some(slot) = records.iter().find(|rec| {
+            rec.record_type == A_RECORD
+                && rec.target == instance.ipv4[0]
+                && self.client.naming().is_slot(prefix, &rec.name)
+        }) else {
+            return Ok(None);
+        };
+
+        // the slot is about to be parked, drop anything advertising it
+        let zone = self.client.fetch_domain(self.domain).await?;
+        self.client
+            .remove_srv_targets(self.domain, &format!("{}.{}", slot.name, zone.domain))
+            .await?;
+        self.client
+            .remove_instance_record(self.domain, &slot.name)
+            .await?;
+        self.client
+            .update_record_target(self.domain, slot.id, LOCALHOST)
+            .await?;
+        for domain in &self.mirrors {
+            self.client
+                .mirror_record(*domain, &slot.name, LOCALHOST)
+                .await?;
+        }
+
+        info!(
+            "Deregistered instance ID: {} from slot: {}",
+            instance.id, slot.name
+        );
+        Ok(Some(slot.name.clone()))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<Registration>, LinodeError> {
+        let records = self.client.fetch_records(self.domain).await?;
+        Ok(records
+            .into_iter()
+            .filter(|rec| {
+                rec.record_type == A_RECORD
+                    && self.client.naming().is_slot(prefix, &rec.name)
+                    && rec.target != LOCALHOST
+            })
+            .map(|rec| Registration {
+                name: rec.name,
+                address: rec.target,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Interfaces, LinodeClient, LinodeError, A_RECORD, TXT_RECORD};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenameReport {
+    pub instances: Vec<String>,
+    // (old name, new name)
+    pub records: Vec<(String, String)>,
+    pub interfaces: Vec<String>,
+}
+
+impl LinodeClient {
+    pub async fn rename_record(&self, domain: u64, id: u64, name: &str) -> Result<(), LinodeError> {
+        info!(
+            "Renaming record ID: {} in domain ID: {} to {}",
+            id, domain, name
+        );
+        self.send(
+            self.client
+                .put(format!(
+                    "{}/domains/{}/records/{}",
+                    self.base_url, domain, id
+                ))
+                .bearer_auth(self.token.expose())
+                .json(&json!({ "name": name })),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        self.cache_records(domain, |records| {
+            if let Some(rec) = records.iter_mut().find(|rec| rec.id == id) {
+                rec.name = name.to_owned();
+            }
+        });
+        Ok(())
+    }
+
+    // move a fleet from one tag to another: instance tags, the
+    // {tag}-{region}-N records and, optionally, the VLAN interface labels
+    // (which needs a reboot of every instance)
+    pub async fn rename_fleet(
+        &self,
+        from: &str,
+        to: &str,
+        domain: u64,
+        relabel_vlan: bool,
+    ) -> Result<RenameReport, LinodeError> {
+        info!("Renaming fleet tag: {} to {}", from, to);
+        let mut report = RenameReport::default();
+        let instances = self.get_instances_by_tag(vec![from]).await?;
+
+        for instance in &instances {
+            let tags = instance
+                .tags
+                .iter()
+                .map(|t| if t == from { to.to_owned() } else { t.clone() })
+                .collect();
+            self.update_instance_tags(instance.id, tags).await?;
+            report.instances.push(instance.label.clone());
+
+            if relabel_vlan {
+                for config in self.get_instance_configurations(instance.id).await? {
+                    if !config
+                        .interfaces
+                        .iter()
+                        .any(|i| i.label.as_deref() == Some(from))
+                    {
+                        continue;
+                    }
+                    let mut interfaces = config.interfaces.clone();
+                    for interface in interfaces.iter_mut() {
+                        if interface.label.as_deref() == Some(from) {
+                            interface.label = Some(to.to_owned());
+                        }
+                    }
+                    self.set_interfaces(instance.id, config.id, Interfaces { interfaces })
+                        .await?;
+                    self.reboot_instance_with_config(instance.id, Some(config.id))
+                        .await?;
+                    report.interfaces.push(instance.label.clone());
+                }
+            }
+        }
+
+        for rec in self.fetch_records(domain).await? {
+            // A records and the instance TXT records alongside them
+            if rec.record_type != A_RECORD && rec.record_type != TXT_RECORD {
+                continue;
+            }
+            // only slots of from, not e.g. {from}-canary-... records
+            let Some((code, n)) = self.naming().slot_of(from, &rec.name) else {
+                continue;
+            };
+            let new_name = self.naming().slot(&self.naming().prefix(to, code), n);
+            self.rename_record(domain, rec.id, &new_name).await?;
+            report.records.push((rec.name.clone(), new_name));
+        }
+
+        info!(
+            "Renamed {} instances and {} records from {} to {}",
+            report.instances.len(),
+            report.records.len(),
+            from,
+            to
+        );
+        Ok(report)
+    }
+}
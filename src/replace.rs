@@ -0,0 +1,125 @@
+use crate::evacuate::SlotRegistry;
+use crate::journal::{JournalEntry, JournalStep};
+use crate::regions::find_region;
+use crate::{LinodeClient, LinodeError, ScaleUpOptions, ScaledNode, A_RECORD};
+use serde::{Deserialize, Serialize};
+use svix_ksuid::{Ksuid, KsuidLike};
+use tracing::info;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replacement {
+    // the fleet the instance belongs to
+    pub tag: String,
+    pub slot: String,
+    pub image_id: String,
+    pub removed: ScaledNode,
+    pub created: ScaledNode,
+}
+
+impl LinodeClient {
+    // Swap one fleet instance for a fresh one of the same type in the same
+    // region and fleet, booted from image or else the image the original
+    // was built from. The replacement is scaled up with options, so it is
+    // provisioned and health checked before the slot's A record is pointed
+    // at it in a single update; the original keeps serving until then and
+    // is destroyed after. The fleet is the tag the slot is named after.
+    pub async fn replace_instance(
+        &self,
+        domain: u64,
+        label: &str,
+        image: Option<&str>,
+        options: &ScaleUpOptions,
+    ) -> Result<Replacement, LinodeError> {
+        let instance = self.fetch_instance_by_label(label).await?;
+        let region = find_region(&instance.region).ok_or_else(|| {
+            LinodeError::Spec(format!(
+                "instance {} is in unknown region: {}",
+                label, instance.region
+            ))
+        })?;
+        let image_id = image
+            .map(str::to_owned)
+            .or_else(|| instance.image.clone())
+            .ok_or_else(|| {
+                LinodeError::Spec(format!("instance {} has no image to rebuild from", label))
+            })?;
+
+        let records = self.fetch_records(domain).await?;
+        let (tag, slot) = records
+            .iter()
+            .filter(|rec| rec.record_type == A_RECORD)
+            .filter(|rec| instance.ipv4.first() == Some(&rec.target))
+            .find_map(|rec| {
+                let tag = self
+                    .naming()
+                    .fleet_of(&rec.name, &instance.tags, region.code)?;
+                Some((tag.to_owned(), rec.clone()))
+            })
+            .ok_or_else(|| {
+                LinodeError::Spec(format!(
+                    "instance {} has no DNS slot in domain ID: {}",
+                    label, domain
+                ))
+            })?;
+        info!(
+            "Replacing {} in slot: {} from image: {}",
+            label, slot.name, image_id
+        );
+
+        let operation_id = Ksuid::new(None, None).to_string();
+        self.journal_begin(JournalEntry {
+            domain: Some(domain),
+            ..JournalEntry::new(&operation_id, "replace", &tag, region.code)
+        });
+        let registry = SlotRegistry {
+            client: self,
+            domain,
+            slot: slot.clone(),
+        };
+        let created = self
+            .scale_up_one_in(
+                &registry,
+                &image_id,
+                &instance.instance_type,
+                region,
+                &tag,
+                options,
+            )
+            .await?;
+        // registering the replacement moved the slot off the original
+        self.journal_step(
+            &operation_id,
+            JournalStep::Deregistered {
+                instance_id: instance.id,
+                name: slot.name.clone(),
+            },
+        );
+        self.journal_step(
+            &operation_id,
+            JournalStep::Replaced {
+                instance_id: instance.id,
+                replacement_id: created.id,
+            },
+        );
+
+        self.destroy_instance(instance.id).await?;
+        self.journal_step(
+            &operation_id,
+            JournalStep::Destroyed {
+                instance_id: instance.id,
+            },
+        );
+        self.journal_finish(&operation_id);
+        info!(
+            "Replaced {} with {} in slot: {}",
+            label, created.label, slot.name
+        );
+        Ok(Replacement {
+            removed: ScaledNode::new(&instance, Some(slot.name.clone()), &operation_id),
+            tag,
+            slot: slot.name,
+            image_id,
+            created,
+        })
+    }
+}
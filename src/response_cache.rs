@@ -0,0 +1,115 @@
+use crate::{LinodeClient, LinodeError, ListOptions};
+use reqwest::Url;
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+use web_time::Instant;
+
+struct Entry {
+    fetched: Instant,
+    value: Box<dyn Any + Send + Sync>,
+}
+
+// Listings fetched in the last ttl, by URL and list options, so a scale
+// loop doesn't list the same instances, records and configs again for
+// every node. Any write the client sends drops the listings it could
+// change: those of the collection it writes into and those under the
+// object it writes to.
+pub(crate) struct ResponseCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<(String, String), Entry>>,
+}
+
+impl ResponseCache {
+    fn new(ttl: Duration) -> Self {
+        ResponseCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get<T: Clone + 'static>(&self, key: &(String, String)) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+        if entry.fetched.elapsed() >= self.ttl {
+            entries.remove(key);
+            return None;
+        }
+        entry.value.downcast_ref::<T>().cloned()
+    }
+
+    fn insert<T: Send + Sync + 'static>(&self, key: (String, String), value: T) {
+        self.entries.lock().unwrap().insert(
+            key,
+            Entry {
+                fetched: Instant::now(),
+                value: Box::new(value),
+            },
+        );
+    }
+
+    // after a write to url
+    pub(crate) fn invalidate(&self, url: &Url) {
+        let written = segments(url.as_str());
+        self.entries.lock().unwrap().retain(|(listed, _), _| {
+            let listed = segments(listed);
+            !(written.starts_with(&listed) || listed.starts_with(&written))
+        });
+    }
+}
+
+fn segments(url: &str) -> Vec<&str> {
+    url.split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+impl LinodeClient {
+    // reuse instance, record and config listings for up to ttl; off by
+    // default
+    pub fn with_response_cache(mut self, ttl: Duration) -> Self {
+        self.response_cache = Some(ResponseCache::new(ttl));
+        self
+    }
+
+    // forget every cached listing, e.g. after changes made elsewhere
+    pub fn clear_response_cache(&self) {
+        if let Some(cache) = &self.response_cache {
+            cache.entries.lock().unwrap().clear();
+        }
+    }
+
+    // the listing of path under options from the response cache, or from
+    // fetch, which is then cached
+    pub(crate) async fn cached_list<T, F>(
+        &self,
+        path: &str,
+        options: &ListOptions,
+        fetch: F,
+    ) -> Result<Vec<T>, LinodeError>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Future<Output = Result<Vec<T>, LinodeError>>,
+    {
+        let Some(cache) = &self.response_cache else {
+            return fetch.await;
+        };
+        let key = (
+            format!("{}{}", self.base_url, path),
+            format!("{:?}", options),
+        );
+        if let Some(listing) = cache.get::<Vec<T>>(&key) {
+            info!("Using cached listing of {}", path);
+            return Ok(listing);
+        }
+        let listing = fetch.await?;
+        cache.insert(key, listing.clone());
+        Ok(listing)
+    }
+}
@@ -0,0 +1,117 @@
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+// When and how often a failed API call is repeated. The client keeps one
+// policy for reads, updates and deletes, which are safe to repeat, and a
+// stricter one for creates, where retrying after a 5xx can leave a
+// duplicate behind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    // total tries including the first, 1 disables retries
+    pub max_attempts: u32,
+    pub retryable_statuses: Vec<u16>,
+    // retry requests that never got a response (connect errors, timeouts)
+    pub retry_transport_errors: bool,
+    // wall-clock limit for all attempts of one call
+    pub budget: Option<Duration>,
+    // wait before the first retry, doubling for each one after up to
+    // max_delay
+    pub delay: Duration,
+    pub max_delay: Duration,
+    // randomize each wait between half and all of it, so clients that
+    // failed together don't retry together
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            retryable_statuses: vec![429, 500, 502, 503, 504],
+            retry_transport_errors: true,
+            budget: Some(Duration::from_secs(60)),
+            delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    // only retry what the API rejected before acting on it
+    pub fn creates() -> Self {
+        RetryPolicy {
+            retryable_statuses: vec![429],
+            retry_transport_errors: false,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_budget(mut self, budget: Option<Duration>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn with_delay(mut self, delay: Duration, max_delay: Duration) -> Self {
+        self.delay = delay;
+        self.max_delay = max_delay.max(delay);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    // how long to wait after the attempt'th try failed, counting from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay = self.delay.saturating_mul(1 << exponent).min(self.max_delay);
+        if !self.jitter || delay.is_zero() {
+            return delay;
+        }
+        let half = delay / 2;
+        half + half.mul_f64(rand::thread_rng().gen::<f64>())
+    }
+
+    // a 429 or 503 may say when to come back; that wins over the backoff
+    // when it is longer, capped at max_delay
+    pub fn backoff_for(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        let backoff = self.backoff(attempt);
+        let retry_after = headers
+            .and_then(retry_after)
+            .map(|wait| wait.min(self.max_delay));
+        retry_after.map_or(backoff, |retry_after| retry_after.max(backoff))
+    }
+
+    pub fn retries_status(&self, status: StatusCode) -> bool {
+        self.retryable_statuses.contains(&status.as_u16())
+    }
+
+    pub fn retries_error(&self, e: &reqwest::Error) -> bool {
+        self.retry_transport_errors && (e.is_connect() || e.is_timeout())
+    }
+}
+
+// the wait a Retry-After header in seconds asks for
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
@@ -0,0 +1,108 @@
+use crate::instance_record::InstanceRecord;
+use crate::regions::find_region;
+use crate::{LinodeClient, LinodeError, A_RECORD, TXT_RECORD};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::info;
+
+// The instances of a region running one image, and the slots they hold.
+// image is None when neither the instance nor its slot's instance record
+// says what it was built from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageRollout {
+    pub image: Option<String>,
+    pub instance_ids: Vec<u64>,
+    pub slots: Vec<String>,
+}
+
+// A region's instances by image, most common first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegionRollout {
+    pub region: String,
+    pub images: Vec<ImageRollout>,
+}
+
+impl RegionRollout {
+    // more than one image, e.g. a rolling upgrade stopped partway
+    pub fn is_mixed(&self) -> bool {
+        self.images.len() > 1
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollStatus {
+    pub tag: String,
+    pub regions: Vec<RegionRollout>,
+}
+
+impl RollStatus {
+    pub fn is_complete(&self) -> bool {
+        !self.regions.iter().any(RegionRollout::is_mixed)
+    }
+}
+
+impl LinodeClient {
+    // Which image each of a fleet's instances runs, per region, with the
+    // slots they hold in domain. The image is the instance's own, or else
+    // the one its slot's instance record names.
+    pub async fn roll_status(&self, tag: &str, domain: u64) -> Result<RollStatus, LinodeError> {
+        info!("Building roll status for tag: {}", tag);
+        let instances = self.get_instances_by_tag(vec![tag]).await?;
+        let records = self.fetch_records(domain).await?;
+
+        let mut regions: BTreeMap<String, BTreeMap<Option<String>, ImageRollout>> = BTreeMap::new();
+        for instance in instances {
+            let code = find_region(&instance.region).map_or(instance.region.as_str(), |r| r.code);
+            let prefix = self.naming().prefix(tag, code);
+            let slots = records
+                .iter()
+                .filter(|rec| rec.record_type == A_RECORD)
+                .filter(|rec| instance.ipv4.first() == Some(&rec.target))
+                .filter(|rec| self.naming().is_slot(&prefix, &rec.name))
+                .map(|rec| rec.name.clone())
+                .collect::<Vec<_>>();
+            let image = instance.image.clone().or_else(|| {
+                records
+                    .iter()
+                    .filter(|rec| rec.record_type == TXT_RECORD)
+                    .filter_map(|rec| InstanceRecord::parse(&rec.target))
+                    .find(|record| record.instance_id == instance.id)
+                    .and_then(|record| record.image)
+            });
+
+            let rollout = regions
+                .entry(instance.region.clone())
+                .or_default()
+                .entry(image.clone())
+                .or_insert_with(|| ImageRollout {
+                    image,
+                    instance_ids: Vec::new(),
+                    slots: Vec::new(),
+                });
+            rollout.instance_ids.push(instance.id);
+            rollout.slots.extend(slots);
+        }
+
+        let regions = regions
+            .into_iter()
+            .map(|(region, images)| {
+                let mut images = images.into_values().collect::<Vec<_>>();
+                images.sort_by_key(|rollout| std::cmp::Reverse(rollout.instance_ids.len()));
+                for rollout in &mut images {
+                    rollout.slots.sort();
+                }
+                RegionRollout { region, images }
+            })
+            .collect::<Vec<_>>();
+        info!(
+            "Fleet {} is in {} regions, {} of them on more than one image",
+            tag,
+            regions.len(),
+            regions.iter().filter(|r| r.is_mixed()).count()
+        );
+        Ok(RollStatus {
+            tag: tag.to_owned(),
+            regions,
+        })
+    }
+}
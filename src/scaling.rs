@@ -0,0 +1,557 @@
+use crate::fleet::HealthCheck;
+use crate::instances::{CreateInstanceOptions, LinodeInstance};
+use crate::journal::{JournalEntry, JournalStep};
+use crate::metadata::{InstanceMetadata, NodeIdentity, Peer};
+use crate::networking::{next_host, public_interface, vlan_interface, vlan_network, Interfaces};
+use crate::provisioner::Provisioner;
+use crate::regions::RegionInfo;
+use crate::registry::{LinodeDnsRegistry, ServiceRegistry};
+use crate::srv::SrvService;
+use crate::template::{TemplateVars, UserDataTemplate};
+use crate::timings::{StepClock, StepTiming};
+use crate::{LinodeClient, LinodeError, BOOT_TIMEOUT, STATUS_POLL_INTERVAL, STATUS_RUNNING};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use svix_ksuid::*;
+use tracing::{info, info_span, Instrument};
+
+#[derive(Debug)]
+pub enum UserData {
+    // rendered per instance with TemplateVars
+    Template(UserDataTemplate),
+    // submitted as-is to every instance
+    Static(InstanceMetadata),
+}
+
+#[derive(Debug, Default)]
+pub struct ScaleUpOptions {
+    pub user_data: Option<UserData>,
+    pub swap_size: Option<u32>,
+    // shutdown and boot instead of a soft reboot after setting interfaces
+    pub power_cycle: bool,
+    // how long to wait for the instance to be running, per boot
+    pub boot_timeout: Option<Duration>,
+    // publish _service._proto.{tag} pointing at the new node
+    pub srv: Option<SrvService>,
+    // write a TXT record with instance ID, image and creation time
+    pub instance_record: bool,
+    // run once the instance is up, before it is registered
+    pub provisioner: Option<Arc<dyn Provisioner>>,
+    // refuse to add the instance if the tag would then cost more than
+    // this per month at list prices
+    pub monthly_budget: Option<f64>,
+    // the fleet's VLAN, a /24 such as "10.8.0.0/24"; 10.0.0.0/24 if unset
+    pub vlan_subnet: Option<String>,
+    // firewall the instance is attached to before it is booted into service
+    pub firewall_id: Option<u64>,
+    // port that must accept connections before the node is registered,
+    // waiting up to the boot timeout
+    pub health: Option<HealthCheck>,
+    // other domains the node's slot is mirrored into under the same name
+    pub mirror_domains: Vec<u64>,
+}
+
+// An instance added or removed by a scale operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaledNode {
+    pub id: u64,
+    pub label: String,
+    pub region: String,
+    pub ipv4: Option<String>,
+    // name the node is registered as, e.g. its slot record
+    pub name: Option<String>,
+    // shared by every span and log line of the operation
+    pub operation_id: String,
+    // how long each step of adding or removing the node took
+    #[serde(default)]
+    pub timings: Vec<StepTiming>,
+    // API calls made while adding or removing the node
+    #[serde(default)]
+    pub api_calls: u64,
+}
+
+impl ScaledNode {
+    pub(crate) fn new(instance: &LinodeInstance, name: Option<String>, operation_id: &str) -> Self {
+        ScaledNode {
+            id: instance.id,
+            label: instance.label.clone(),
+            region: instance.region.clone(),
+            ipv4: instance.ipv4.first().cloned(),
+            name,
+            operation_id: operation_id.to_owned(),
+            timings: Vec::new(),
+            api_calls: 0,
+        }
+    }
+}
+
+// An iteration of a looped scale-up that failed. iteration counts from 1
+// within the region; 0 is a failure before the first instance, e.g. while
+// reserving slots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleFailure {
+    pub region: String,
+    pub iteration: u32,
+    pub error: String,
+}
+
+// Everything a looped scale operation did, including what failed and
+// what was never tried after a failure.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScaleSummary {
+    pub succeeded: Vec<ScaledNode>,
+    pub failed: Vec<ScaleFailure>,
+    pub skipped: u32,
+    // API calls the whole operation made, failures included
+    #[serde(default)]
+    pub api_calls: u64,
+}
+
+impl ScaleSummary {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && self.skipped == 0
+    }
+}
+
+// Fleet scale up and down, from LinodeClient::scaling().
+#[derive(Clone, Copy)]
+pub struct Scaling<'a> {
+    client: &'a LinodeClient,
+}
+
+impl LinodeClient {
+    pub fn scaling(&self) -> Scaling<'_> {
+        Scaling { client: self }
+    }
+
+    // remove an instance that has a particular tag
+    pub async fn scale_down(
+        &self,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+        n: usize,
+    ) -> Result<usize, LinodeError> {
+        self.scale_down_in(&LinodeDnsRegistry::new(self, domain), region, tag, n)
+            .await
+            .map(|removed| removed.len())
+    }
+
+    pub async fn scale_down_in(
+        &self,
+        registry: &dyn ServiceRegistry,
+        region: &RegionInfo,
+        tag: &str,
+        n: usize,
+    ) -> Result<Vec<ScaledNode>, LinodeError> {
+        let operation_id = Ksuid::new(None, None).to_string();
+        let span = info_span!(
+            "scale_down",
+            operation_id = %operation_id,
+            tag = %tag,
+            region = %region.code
+        );
+        async move {
+            info!(
+                "Scaling down an instance in region: {} with tag: {}",
+                region.code, tag
+            );
+            let instances = self.get_fleet_instances(tag, region).await?;
+            let prefix = self.naming.prefix(tag, region.code);
+            self.journal_begin(JournalEntry::new(
+                &operation_id,
+                "scale_down",
+                tag,
+                region.code,
+            ));
+
+            let mut done = Vec::new();
+            for instance in &instances {
+                if done.len() >= n || self.is_shutting_down() {
+                    break;
+                }
+                let mut clock = StepClock::start(self, "scale_down");
+                if let Some(name) = registry.deregister(&prefix, instance).await? {
+                    clock.lap("dns");
+                    self.journal_step(
+                        &operation_id,
+                        JournalStep::Deregistered {
+                            instance_id: instance.id,
+                            name: name.clone(),
+                        },
+                    );
+                    self.destroy_instance(instance.id).await?;
+                    clock.lap("destroy");
+                    self.journal_step(
+                        &operation_id,
+                        JournalStep::Destroyed {
+                            instance_id: instance.id,
+                        },
+                    );
+
+                    info!(
+                        "Scaled down instance ID: {} with label: {} in region: {}",
+                        instance.id, instance.label, region.code
+                    );
+
+                    done.push(ScaledNode {
+                        api_calls: clock.api_calls(),
+                        timings: clock.finish(),
+                        ..ScaledNode::new(instance, Some(name), &operation_id)
+                    });
+                }
+            }
+
+            self.journal_finish(&operation_id);
+            Ok(done)
+        }
+        .instrument(span)
+        .await
+    }
+
+    // add an instance to the same VLAN as other linodes in a region
+    // assigns instance to a sequential subdomain
+    pub async fn scale_up_one(
+        &self,
+        image_id: &str,
+        instance_type: &str,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+    ) -> Result<ScaledNode, LinodeError> {
+        self.scale_up_one_with(
+            image_id,
+            instance_type,
+            domain,
+            region,
+            tag,
+            &ScaleUpOptions::default(),
+        )
+        .await
+    }
+
+    // The fleet's nodes as a node scaled up into it is told about them:
+    // VLAN addresses from their interfaces, names from registry.
+    pub async fn fleet_peers(
+        &self,
+        registry: &dyn ServiceRegistry,
+        region: &RegionInfo,
+        tag: &str,
+    ) -> Result<Vec<Peer>, LinodeError> {
+        let fleet = self.fleet_vlan_addresses(tag, region).await?;
+        let prefix = self.naming.prefix(tag, region.code);
+        let dns_name = registry.peek(&prefix).await?;
+        self.peers_of(registry, &prefix, dns_name.as_deref(), fleet)
+            .await
+    }
+
+    // Registries may list bare slot names; they are qualified with the
+    // zone of dns_name, the name the new node expects, when it has one.
+    async fn peers_of(
+        &self,
+        registry: &dyn ServiceRegistry,
+        prefix: &str,
+        dns_name: Option<&str>,
+        fleet: Vec<(LinodeInstance, Vec<String>)>,
+    ) -> Result<Vec<Peer>, LinodeError> {
+        let zone = dns_name
+            .and_then(|name| name.split_once('.'))
+            .map(|(_, zone)| zone);
+        let registrations = registry.list(prefix).await?;
+        Ok(fleet
+            .into_iter()
+            .map(|(instance, addresses)| {
+                let public_ip = instance.ipv4.first().cloned();
+                let dns_name = registrations
+                    .iter()
+                    .find(|reg| Some(&reg.address) == public_ip.as_ref())
+                    .map(|reg| match zone {
+                        Some(zone) if !reg.name.contains('.') => format!("{}.{}", reg.name, zone),
+                        _ => reg.name.clone(),
+                    });
+                Peer {
+                    label: instance.label,
+                    public_ip,
+                    vlan_ip: addresses.into_iter().next(),
+                    dns_name,
+                }
+            })
+            .collect())
+    }
+
+    pub async fn scale_up_one_with(
+        &self,
+        image_id: &str,
+        instance_type: &str,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+        options: &ScaleUpOptions,
+    ) -> Result<ScaledNode, LinodeError> {
+        let registry = LinodeDnsRegistry::new(self, domain)
+            .with_srv(tag, options.srv.clone())
+            .with_instance_records(options.instance_record)
+            .with_mirrors(&options.mirror_domains);
+        self.scale_up_one_in(&registry, image_id, instance_type, region, tag, options)
+            .await
+    }
+
+    pub async fn scale_up_one_in(
+        &self,
+        registry: &dyn ServiceRegistry,
+        image_id: &str,
+        instance_type: &str,
+        region: &RegionInfo,
+        tag: &str,
+        options: &ScaleUpOptions,
+    ) -> Result<ScaledNode, LinodeError> {
+        let operation_id = Ksuid::new(None, None).to_string();
+        let span = info_span!(
+            "scale_up",
+            operation_id = %operation_id,
+            tag = %tag,
+            region = %region.code
+        );
+        async move {
+            info!(
+                "Scaling up an instance in region: {} with tag: {}",
+                region.code, tag
+            );
+            let mut clock = StepClock::start(self, "scale_up");
+            if let Some(cap) = options.monthly_budget {
+                self.check_budget(tag, instance_type, region, cap).await?;
+            }
+            let vlan = vlan_network(options.vlan_subnet.as_deref())?;
+            let fleet = self.fleet_vlan_addresses(tag, region).await?;
+            let cidr = next_host(&fleet);
+
+            let kid = Ksuid::new(None, None);
+            let label = format!("{}-{}", region.code, kid);
+
+            let ipam = format!("{}.{}/24", vlan, cidr);
+
+            // let the node discover its role at boot via the metadata service
+            let prefix = self.naming.prefix(tag, region.code);
+            let dns_name = registry.peek(&prefix).await?;
+            let peers = self
+                .peers_of(registry, &prefix, dns_name.as_deref(), fleet)
+                .await?;
+            info!("Handing {} peers to the new node", peers.len());
+            let identity = NodeIdentity {
+                tag: tag.to_string(),
+                region: region.code.to_string(),
+                label: label.clone(),
+                dns_name: dns_name.clone(),
+                vlan_ip: Some(format!("{}.{}", vlan, cidr)),
+                operation_id: Some(operation_id.clone()),
+                peers: peers.clone(),
+            };
+
+            // a user-data template replaces the default identity cloud-config;
+            // render it up front so a bad template fails before provisioning
+            let metadata = match &options.user_data {
+                Some(UserData::Static(metadata)) => metadata.clone(),
+                Some(UserData::Template(template)) => {
+                    let vars = TemplateVars {
+                        tag: tag.to_string(),
+                        region: region.code.to_string(),
+                        label: label.clone(),
+                        dns_name: dns_name.clone().unwrap_or_default(),
+                        vlan_ip: format!("{}.{}", vlan, cidr),
+                        index: dns_name
+                            .as_deref()
+                            .and_then(|name| self.naming.sequence(&prefix, name.split('.').next()?))
+                            .unwrap_or(0),
+                        peers,
+                    };
+                    InstanceMetadata::encode_user_data(template.render(&vars)?.as_bytes())?
+                }
+                None => identity.to_metadata(),
+            };
+            clock.lap("prepare");
+
+            self.journal_begin(JournalEntry {
+                label: Some(label.clone()),
+                ipam: Some(ipam.clone()),
+                ..JournalEntry::new(&operation_id, "scale_up", tag, region.code)
+            });
+            let instance = self
+                .create_instance(CreateInstanceOptions {
+                    image: image_id.to_string(),
+                    tags: vec![tag.to_string(), region.code.to_string()],
+                    label: label.clone(),
+                    region: region.region.to_string(),
+                    instance_type: instance_type.to_string(),
+                    metadata: Some(metadata),
+                    swap_size: options.swap_size,
+                    ..Default::default()
+                })
+                .await?;
+            clock.lap("create");
+            self.journal_step(
+                &operation_id,
+                JournalStep::Created {
+                    instance_id: instance.id,
+                },
+            );
+
+            if let Some(firewall_id) = options.firewall_id {
+                self.attach_firewall(firewall_id, instance.id).await?;
+                clock.lap("firewall");
+            }
+
+            let configs = self.get_instance_configurations(instance.id).await?;
+            let config_id = configs[0].id;
+            clock.lap("config_fetch");
+
+            let new_interfaces = Interfaces {
+                interfaces: vec![public_interface(), vlan_interface(tag, ipam)],
+            };
+
+            self.set_interfaces(instance.id, config_id, new_interfaces)
+                .await?;
+            clock.lap("interfaces");
+            self.journal_step(
+                &operation_id,
+                JournalStep::Configured {
+                    instance_id: instance.id,
+                    config_id,
+                },
+            );
+
+            // the instance must finish provisioning before it accepts a reboot
+            let boot_timeout = options.boot_timeout.unwrap_or(BOOT_TIMEOUT);
+            self.wait_for_status(instance.id, STATUS_RUNNING, boot_timeout)
+                .await?;
+            clock.lap("boot_wait");
+
+            if options.power_cycle {
+                self.power_cycle_instance(instance.id, Some(config_id))
+                    .await?;
+            } else {
+                info!("Rebooting the newly created instance ID: {}", instance.id);
+                self.reboot_instance_with_config(instance.id, Some(config_id))
+                    .await?;
+            }
+
+            // give the API a moment to leave the running state before polling
+            self.pause(STATUS_POLL_INTERVAL).await;
+            self.wait_for_status(instance.id, STATUS_RUNNING, boot_timeout)
+                .await?;
+            clock.lap("reboot_wait");
+            self.journal_step(
+                &operation_id,
+                JournalStep::Booted {
+                    instance_id: instance.id,
+                },
+            );
+
+            if let Some(provisioner) = &options.provisioner {
+                provisioner.provision(&instance, &identity).await?;
+                clock.lap("provision");
+            }
+
+            if let Some(check) = options.health {
+                let node = ScaledNode::new(&instance, None, &operation_id);
+                self.wait_for_healthy(&[node], check, boot_timeout).await?;
+                clock.lap("health");
+            }
+
+            let name = registry.register(&prefix, &instance).await?;
+            clock.lap("dns");
+            self.journal_step(
+                &operation_id,
+                JournalStep::Registered {
+                    instance_id: instance.id,
+                    name: name.clone(),
+                },
+            );
+            self.journal_finish(&operation_id);
+
+            info!(
+                "Scaled up instance ID: {} with label: {} in region: {}",
+                instance.id, label, region.code
+            );
+            Ok(ScaledNode {
+                api_calls: clock.api_calls(),
+                timings: clock.finish(),
+                ..ScaledNode::new(&instance, Some(name), &operation_id)
+            })
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+impl Scaling<'_> {
+    pub async fn scale_down(
+        &self,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+        n: usize,
+    ) -> Result<usize, LinodeError> {
+        self.client.scale_down(domain, region, tag, n).await
+    }
+
+    pub async fn scale_down_in(
+        &self,
+        registry: &dyn ServiceRegistry,
+        region: &RegionInfo,
+        tag: &str,
+        n: usize,
+    ) -> Result<Vec<ScaledNode>, LinodeError> {
+        self.client.scale_down_in(registry, region, tag, n).await
+    }
+
+    pub async fn scale_up_one(
+        &self,
+        image_id: &str,
+        instance_type: &str,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+    ) -> Result<ScaledNode, LinodeError> {
+        self.client
+            .scale_up_one(image_id, instance_type, domain, region, tag)
+            .await
+    }
+
+    pub async fn scale_up_one_with(
+        &self,
+        image_id: &str,
+        instance_type: &str,
+        domain: u64,
+        region: &RegionInfo,
+        tag: &str,
+        options: &ScaleUpOptions,
+    ) -> Result<ScaledNode, LinodeError> {
+        self.client
+            .scale_up_one_with(image_id, instance_type, domain, region, tag, options)
+            .await
+    }
+
+    pub async fn scale_up_one_in(
+        &self,
+        registry: &dyn ServiceRegistry,
+        image_id: &str,
+        instance_type: &str,
+        region: &RegionInfo,
+        tag: &str,
+        options: &ScaleUpOptions,
+    ) -> Result<ScaledNode, LinodeError> {
+        self.client
+            .scale_up_one_in(registry, image_id, instance_type, region, tag, options)
+            .await
+    }
+
+    pub async fn fleet_peers(
+        &self,
+        registry: &dyn ServiceRegistry,
+        region: &RegionInfo,
+        tag: &str,
+    ) -> Result<Vec<Peer>, LinodeError> {
+        self.client.fleet_peers(registry, region, tag).await
+    }
+}
@@ -0,0 +1,201 @@
+use crate::regions::find_region;
+use crate::registry::{LinodeDnsRegistry, ServiceRegistry};
+use crate::spec::FleetGroupSpec;
+use crate::{LinodeClient, LinodeError, ScaleUpOptions, ScaledNode};
+use serde::{Deserialize, Serialize};
+use svix_ksuid::{Ksuid, KsuidLike};
+use tracing::info;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_PROTECT_TAG: &str = "protected";
+
+// Off-hours for a dev or staging fleet, set per fleet in the spec file:
+//
+//   [fleet.schedule]
+//   down = "20:00"
+//   up = "07:00"
+//   protect_tag = "keep"
+//
+// Times are UTC. Between down and up the fleet's instances are destroyed,
+// except those carrying protect_tag ("protected" by default); from up
+// until down again it is rebuilt to its configured counts, reusing the
+// freed DNS slots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub down: String,
+    pub up: String,
+    pub protect_tag: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulePhase {
+    Up,
+    Down,
+}
+
+// minutes past midnight for "HH:MM"
+fn parse_time(time: &str) -> Result<u32, LinodeError> {
+    let invalid = || LinodeError::Spec(format!("invalid schedule time '{}', expected HH:MM", time));
+    let (hours, minutes) = time.split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.trim().parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.trim().parse().map_err(|_| invalid())?;
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+impl Schedule {
+    pub fn protect_tag(&self) -> &str {
+        self.protect_tag.as_deref().unwrap_or(DEFAULT_PROTECT_TAG)
+    }
+
+    // which phase the fleet should be in at unix time now
+    pub fn phase_at(&self, now: u64) -> Result<SchedulePhase, LinodeError> {
+        let down = parse_time(&self.down)?;
+        let up = parse_time(&self.up)?;
+        let minute = ((now % 86400) / 60) as u32;
+        // the down window usually wraps past midnight
+        let in_down = if down <= up {
+            minute >= down && minute < up
+        } else {
+            minute >= down || minute < up
+        };
+        Ok(if in_down {
+            SchedulePhase::Down
+        } else {
+            SchedulePhase::Up
+        })
+    }
+
+    pub fn phase_now(&self) -> Result<SchedulePhase, LinodeError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.phase_at(now)
+    }
+}
+
+// What one pass of a schedule did to a fleet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub tag: String,
+    pub phase: SchedulePhase,
+    pub removed: Vec<ScaledNode>,
+    pub added: Vec<ScaledNode>,
+}
+
+impl LinodeClient {
+    // Bring a scheduled fleet in line with the phase it should be in now.
+    // Safe to run repeatedly, e.g. from cron every few minutes: a fleet
+    // that's already torn down or rebuilt is left alone.
+    pub async fn run_schedule(
+        &self,
+        domain: u64,
+        group: &FleetGroupSpec,
+        options: &ScaleUpOptions,
+    ) -> Result<ScheduleRun, LinodeError> {
+        let schedule = group
+            .schedule
+            .as_ref()
+            .ok_or_else(|| LinodeError::Spec(format!("fleet {} has no schedule", group.tag)))?;
+        let phase = schedule.phase_now()?;
+        info!("Fleet with tag: {} is scheduled {:?}", group.tag, phase);
+        let mut run = ScheduleRun {
+            tag: group.tag.clone(),
+            phase,
+            removed: Vec::new(),
+            added: Vec::new(),
+        };
+        match phase {
+            SchedulePhase::Down => {
+                run.removed = self
+                    .teardown_fleet(domain, group, schedule.protect_tag())
+                    .await?
+            }
+            SchedulePhase::Up => run.added = self.rebuild_fleet(domain, group, options).await?,
+        }
+        Ok(run)
+    }
+
+    // destroy every instance of the fleet not tagged protect_tag, freeing
+    // its DNS slot for the rebuild; a shutdown stops between instances
+    pub async fn teardown_fleet(
+        &self,
+        domain: u64,
+        group: &FleetGroupSpec,
+        protect_tag: &str,
+    ) -> Result<Vec<ScaledNode>, LinodeError> {
+        let operation_id = Ksuid::new(None, None).to_string();
+        let registry = LinodeDnsRegistry::new(self, domain);
+        let mut removed = Vec::new();
+        for name in group.regions.keys() {
+            let region = find_region(name)
+                .ok_or_else(|| LinodeError::Spec(format!("unknown region '{}'", name)))?;
+            let prefix = self.naming().prefix(&group.tag, region.code);
+            for instance in self.get_fleet_instances(&group.tag, region).await? {
+                if self.is_shutting_down() {
+                    break;
+                }
+                if instance.tags.iter().any(|t| t == protect_tag) {
+                    info!("Keeping protected instance ID: {}", instance.id);
+                    continue;
+                }
+                let slot = registry.deregister(&prefix, &instance).await?;
+                self.destroy_instance(instance.id).await?;
+                removed.push(ScaledNode::new(&instance, slot, &operation_id));
+            }
+        }
+        info!(
+            "Tore down {} instances with tag: {}",
+            removed.len(),
+            group.tag
+        );
+        Ok(removed)
+    }
+
+    // scale each region of the fleet back up to its configured count; a
+    // shutdown stops between instances
+    pub async fn rebuild_fleet(
+        &self,
+        domain: u64,
+        group: &FleetGroupSpec,
+        options: &ScaleUpOptions,
+    ) -> Result<Vec<ScaledNode>, LinodeError> {
+        let missing = |field: &str| {
+            LinodeError::Spec(format!("fleet {} needs {} to rebuild", group.tag, field))
+        };
+        let image = group.image.as_deref().ok_or_else(|| missing("image"))?;
+        let instance_type = group
+            .instance_type
+            .as_deref()
+            .ok_or_else(|| missing("type"))?;
+
+        let mut added = Vec::new();
+        for (name, count) in &group.regions {
+            let region = find_region(name)
+                .ok_or_else(|| LinodeError::Spec(format!("unknown region '{}'", name)))?;
+            let live = self.get_fleet_instances(&group.tag, region).await?.len();
+            for _ in live..*count {
+                if self.is_shutting_down() {
+                    break;
+                }
+                added.push(
+                    self.scale_up_one_with(
+                        image,
+                        instance_type,
+                        domain,
+                        region,
+                        &group.tag,
+                        options,
+                    )
+                    .await?,
+                );
+            }
+        }
+        info!("Rebuilt {} instances with tag: {}", added.len(), group.tag);
+        Ok(added)
+    }
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::RwLock;
+use tracing_subscriber::fmt::MakeWriter;
+
+const REDACTED: &str = "<redacted>";
+
+// shorter values, e.g. an empty placeholder token, would redact ordinary
+// text
+const MIN_SECRET_LEN: usize = 8;
+
+// every secret seen so far, scrubbed from output by RedactingWriter
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// A token, password or key that must not reach a log. Debug prints
+// <redacted> and there's no Display, so it can't be formatted into a
+// message or a header by accident; expose gives the value where it is
+// really needed. Serialize writes the value, for request bodies and
+// config files. The value is also registered for RedactingWriter, in
+// case it is exposed into a log line anyway.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new<S: Into<String>>(value: S) -> Self {
+        let value = value.into();
+        register(&value);
+        SecretString(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        SecretString::new(value)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(SecretString::new)
+    }
+}
+
+// have RedactingWriter scrub secret from now on, e.g. a token read from
+// somewhere other than a SecretString
+pub fn register(secret: &str) {
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap();
+    if !secrets.iter().any(|known| known == secret) {
+        secrets.push(secret.to_owned());
+    }
+}
+
+// text with every registered secret replaced by <redacted>
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let secrets = SECRETS.read().unwrap();
+    let mut text = Cow::Borrowed(text);
+    for secret in secrets.iter() {
+        if text.contains(secret.as_str()) {
+            text = Cow::Owned(text.replace(secret.as_str(), REDACTED));
+        }
+    }
+    text
+}
+
+// Wraps a tracing-subscriber writer so registered secrets are redacted
+// from every line before it's written:
+//
+//   tracing_subscriber::fmt::layer().with_writer(RedactingWriter(std::io::stderr))
+pub struct RedactingWriter<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingWriter<M> {
+    type Writer = Redacting<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Redacting(self.0.make_writer())
+    }
+}
+
+pub struct Redacting<W>(W);
+
+impl<W: Write> Write for Redacting<W> {
+    // the fmt layer writes each event in one call, so a secret isn't split
+    // across writes
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match std::str::from_utf8(buf) {
+            Ok(text) => self.0.write_all(redact(text).as_bytes())?,
+            Err(_) => self.0.write_all(buf)?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
@@ -0,0 +1,63 @@
+use crate::LinodeClient;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::info;
+
+// Asks a client's long-running operations to stop, from anywhere, e.g. a
+// signal handler task. Each finishes the instance it is adding or
+// removing, so its slot is claimed or parked and its journal entry
+// closed, and returns early with what it has done; watch_events returns
+// at its next poll.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle(Arc<AtomicBool>);
+
+impl ShutdownHandle {
+    pub fn request(&self) {
+        if !self.0.swap(true, Ordering::SeqCst) {
+            info!("Shutdown requested, finishing the operation in flight");
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl LinodeClient {
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    pub fn request_shutdown(&self) {
+        self.shutdown.request()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_requested()
+    }
+
+    // operations journaled by this client that haven't finished
+    pub fn in_flight(&self) -> Vec<String> {
+        self.journal
+            .as_ref()
+            .map(|journal| journal.in_flight())
+            .unwrap_or_default()
+    }
+}
+
+// Resolves on SIGINT or, on unix, SIGTERM, e.g. a pod being stopped.
+#[cfg(feature = "native")]
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
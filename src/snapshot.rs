@@ -0,0 +1,185 @@
+use crate::{
+    Configuration, CreateInstanceOptions, Interfaces, LinodeClient, LinodeError, A_RECORD,
+    BOOT_TIMEOUT, STATUS_RUNNING,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+// Versionable description of a tagged fleet: instances, their configs
+// and interfaces, and the DNS records that point at them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetSnapshot {
+    pub version: u32,
+    pub tag: String,
+    pub domain_id: u64,
+    // unix timestamp
+    pub created: u64,
+    pub instances: Vec<SnapshotInstance>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInstance {
+    pub label: String,
+    pub region: String,
+    pub instance_type: String,
+    pub image: Option<String>,
+    pub tags: Vec<String>,
+    pub public_ip: Option<String>,
+    pub configs: Vec<Configuration>,
+    pub dns_records: Vec<SnapshotRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotRecord {
+    pub name: String,
+    pub record_type: String,
+    pub target: String,
+}
+
+impl FleetSnapshot {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LinodeError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| LinodeError::Spec(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&source)
+            .map_err(|e| LinodeError::Spec(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> Result<(), LinodeError> {
+        let path = path.as_ref();
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| LinodeError::Spec(e.to_string()))?;
+        std::fs::write(path, json)
+            .map_err(|e| LinodeError::Spec(format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub created: Vec<String>,
+    pub skipped: Vec<String>,
+    pub records: Vec<String>,
+}
+
+impl LinodeClient {
+    pub async fn export_fleet(&self, tag: &str, domain: u64) -> Result<FleetSnapshot, LinodeError> {
+        info!("Exporting fleet for tag: {}", tag);
+        let records = self.fetch_records(domain).await?;
+        let mut instances = Vec::new();
+
+        for instance in self.get_instances_by_tag(vec![tag]).await? {
+            let public_ip = instance.ipv4.first().cloned();
+            let dns_records = records
+                .iter()
+                .filter(|rec| Some(&rec.target) == public_ip.as_ref())
+                .map(|rec| SnapshotRecord {
+                    name: rec.name.clone(),
+                    record_type: rec.record_type.clone(),
+                    target: rec.target.clone(),
+                })
+                .collect();
+            let configs = self.get_instance_configurations(instance.id).await?;
+
+            instances.push(SnapshotInstance {
+                label: instance.label,
+                region: instance.region,
+                instance_type: instance.instance_type,
+                image: instance.image,
+                tags: instance.tags,
+                public_ip,
+                configs,
+                dns_records,
+            });
+        }
+
+        info!("Exported {} instances for tag: {}", instances.len(), tag);
+        Ok(FleetSnapshot {
+            version: SNAPSHOT_VERSION,
+            tag: tag.to_owned(),
+            domain_id: domain,
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            instances,
+        })
+    }
+
+    // recreate instances missing from the account (matched by label) with
+    // their interfaces, then point their DNS records at the new addresses
+    pub async fn restore_fleet(
+        &self,
+        snapshot: &FleetSnapshot,
+        domain: u64,
+    ) -> Result<RestoreReport, LinodeError> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(LinodeError::Spec(format!(
+                "unsupported snapshot version {}",
+                snapshot.version
+            )));
+        }
+
+        let mut report = RestoreReport::default();
+        let existing = self.get_instances_by_tag(vec![&snapshot.tag]).await?;
+
+        for wanted in &snapshot.instances {
+            if existing.iter().any(|i| i.label == wanted.label) {
+                info!("Instance: {} exists, skipping", wanted.label);
+                report.skipped.push(wanted.label.clone());
+                continue;
+            }
+            let image = wanted.image.clone().ok_or_else(|| {
+                LinodeError::Spec(format!(
+                    "instance {} has no image to restore from",
+                    wanted.label
+                ))
+            })?;
+
+            let instance = self
+                .create_instance(CreateInstanceOptions {
+                    image,
+                    tags: wanted.tags.clone(),
+                    label: wanted.label.clone(),
+                    region: wanted.region.clone(),
+                    instance_type: wanted.instance_type.clone(),
+                    ..Default::default()
+                })
+                .await?;
+            report.created.push(wanted.label.clone());
+
+            let configs = self.get_instance_configurations(instance.id).await?;
+            let interfaces = wanted
+                .configs
+                .first()
+                .map(|c| c.interfaces.clone())
+                .unwrap_or_default();
+            if let (Some(config), false) = (configs.first(), interfaces.is_empty()) {
+                self.set_interfaces(instance.id, config.id, Interfaces { interfaces })
+                    .await?;
+                self.wait_for_status(instance.id, STATUS_RUNNING, BOOT_TIMEOUT)
+                    .await?;
+                self.reboot_instance_with_config(instance.id, Some(config.id))
+                    .await?;
+            }
+
+            let ip = &instance.ipv4[0];
+            for wanted_rec in wanted
+                .dns_records
+                .iter()
+                .filter(|r| r.record_type == A_RECORD)
+            {
+                self.upsert_record(domain, &wanted_rec.name, A_RECORD, ip, 30)
+                    .await?;
+                report
+                    .records
+                    .push(format!("{} -> {}", wanted_rec.name, ip));
+            }
+        }
+
+        Ok(report)
+    }
+}
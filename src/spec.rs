@@ -0,0 +1,259 @@
+use crate::regions::{find_region, RegionInfo};
+use crate::schedule::Schedule;
+use crate::{DomainRecord, LinodeClient, LinodeError, LinodeInstance, A_RECORD, LOCALHOST};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::info;
+
+// Desired state of one or more tagged fleets, usually read from
+// fleet.toml:
+//
+//   domain_id = 2958920
+//
+//   [[fleet]]
+//   tag = "api"
+//   image = "linode/arch"
+//   type = "g6-dedicated-2"
+//   regions = { gb-lon = 3, fr-par = 2 }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetSpec {
+    pub domain_id: u64,
+    #[serde(rename = "fleet", default)]
+    pub fleets: Vec<FleetGroupSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetGroupSpec {
+    pub tag: String,
+    pub image: Option<String>,
+    #[serde(rename = "type")]
+    pub instance_type: Option<String>,
+    // region name or code -> instance count
+    pub regions: BTreeMap<String, usize>,
+    // nightly teardown and morning rebuild, see schedule.rs
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+}
+
+impl FleetSpec {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LinodeError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| LinodeError::Spec(format!("{}: {}", path.display(), e)))?;
+        toml::from_str(&source).map_err(|e| LinodeError::Spec(format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Add,
+    Remove,
+    Change,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Instance,
+    Dns,
+    Interface,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffEntry {
+    pub op: DiffOp,
+    pub kind: DiffKind,
+    pub tag: String,
+    pub region: String,
+    pub subject: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FleetDiff {
+    pub entries: Vec<DiffEntry>,
+}
+
+impl FleetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn push(
+        &mut self,
+        op: DiffOp,
+        kind: DiffKind,
+        (tag, region): (&str, &str),
+        subject: String,
+        detail: String,
+    ) {
+        self.entries.push(DiffEntry {
+            op,
+            kind,
+            tag: tag.to_owned(),
+            region: region.to_owned(),
+            subject,
+            detail,
+        });
+    }
+}
+
+fn region_code_of(instance: &LinodeInstance) -> &str {
+    find_region(&instance.region).map_or(instance.region.as_str(), |info| info.code)
+}
+
+impl LinodeClient {
+    // compare the live fleet against spec without changing anything
+    pub async fn diff_fleet(&self, spec: &FleetSpec) -> Result<FleetDiff, LinodeError> {
+        let mut diff = FleetDiff::default();
+        let records = self.fetch_records(spec.domain_id).await?;
+
+        for group in &spec.fleets {
+            info!("Diffing fleet for tag: {}", group.tag);
+            let mut regions: Vec<(&'static RegionInfo, usize)> = Vec::new();
+            for (name, count) in &group.regions {
+                let info = find_region(name)
+                    .ok_or_else(|| LinodeError::Spec(format!("unknown region '{}'", name)))?;
+                regions.push((info, *count));
+            }
+
+            // tagged instances in regions the spec doesn't mention
+            for instance in self.get_instances_by_tag(vec![&group.tag]).await? {
+                let code = region_code_of(&instance);
+                if !regions.iter().any(|(info, _)| info.code == code) {
+                    diff.push(
+                        DiffOp::Remove,
+                        DiffKind::Instance,
+                        (&group.tag, code),
+                        instance.label.clone(),
+                        "region not in spec".to_owned(),
+                    );
+                }
+            }
+
+            for (region, desired) in regions {
+                self.diff_region(&mut diff, group, region, desired, &records)
+                    .await?;
+            }
+        }
+
+        info!("Fleet diff has {} entries", diff.entries.len());
+        Ok(diff)
+    }
+
+    async fn diff_region(
+        &self,
+        diff: &mut FleetDiff,
+        group: &FleetGroupSpec,
+        region: &RegionInfo,
+        desired: usize,
+        records: &[DomainRecord],
+    ) -> Result<(), LinodeError> {
+        let at = (group.tag.as_str(), region.code);
+        let instances = self.get_fleet_instances(&group.tag, region).await?;
+        let keep = instances.len().min(desired);
+        let (kept, removed) = instances.split_at(keep);
+
+        for n in instances.len()..desired {
+            diff.push(
+                DiffOp::Add,
+                DiffKind::Instance,
+                at,
+                format!("new instance {}", n + 1),
+                format!(
+                    "{} from {}",
+                    group.instance_type.as_deref().unwrap_or("?"),
+                    group.image.as_deref().unwrap_or("?")
+                ),
+            );
+            diff.push(
+                DiffOp::Add,
+                DiffKind::Dns,
+                at,
+                self.naming()
+                    .pattern(&self.naming().prefix(&group.tag, region.code)),
+                "claim a free slot or the next sequential name".to_owned(),
+            );
+        }
+
+        for instance in removed {
+            diff.push(
+                DiffOp::Remove,
+                DiffKind::Instance,
+                at,
+                instance.label.clone(),
+                format!("over desired count of {}", desired),
+            );
+        }
+
+        for instance in kept {
+            if let (Some(want), Some(have)) = (&group.image, &instance.image) {
+                if want != have {
+                    diff.push(
+                        DiffOp::Change,
+                        DiffKind::Instance,
+                        at,
+                        instance.label.clone(),
+                        format!("image {} -> {}", have, want),
+                    );
+                }
+            }
+            if let Some(want) = &group.instance_type {
+                if want != &instance.instance_type {
+                    diff.push(
+                        DiffOp::Change,
+                        DiffKind::Instance,
+                        at,
+                        instance.label.clone(),
+                        format!("type {} -> {}", instance.instance_type, want),
+                    );
+                }
+            }
+            if self.get_vlan_ip(instance.id, &group.tag).await?.is_none() {
+                diff.push(
+                    DiffOp::Add,
+                    DiffKind::Interface,
+                    at,
+                    instance.label.clone(),
+                    format!("vlan interface labelled {}", group.tag),
+                );
+            }
+        }
+
+        // DNS slots: kept instances need a record, everything else that
+        // isn't free should be released
+        let prefix = self.naming().prefix(&group.tag, region.code);
+        let slots = records
+            .iter()
+            .filter(|rec| rec.record_type == A_RECORD && self.naming().is_slot(&prefix, &rec.name))
+            .collect::<Vec<_>>();
+        for instance in kept {
+            let ip = instance.ipv4.first().map(String::as_str).unwrap_or("");
+            if !slots.iter().any(|rec| rec.target == ip) {
+                diff.push(
+                    DiffOp::Add,
+                    DiffKind::Dns,
+                    at,
+                    self.naming().pattern(&prefix),
+                    format!("-> {} ({})", ip, instance.label),
+                );
+            }
+        }
+        for rec in slots {
+            let owned = kept.iter().any(|i| i.ipv4.first() == Some(&rec.target));
+            if rec.target != LOCALHOST && !owned {
+                diff.push(
+                    DiffOp::Change,
+                    DiffKind::Dns,
+                    at,
+                    rec.name.clone(),
+                    format!("{} -> {}", rec.target, LOCALHOST),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
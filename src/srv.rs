@@ -0,0 +1,87 @@
+use crate::dns::verify_record_field;
+use crate::{DomainRecord, DomainRecordOptions, LinodeClient, LinodeError};
+use tracing::info;
+
+const SRV_RECORD: &str = "SRV";
+
+// An SRV record advertised for every node in a fleet, published as
+// _{service}._{protocol}.{tag} in the managed domain. The API adds the
+// leading underscores itself.
+#[derive(Debug, Clone)]
+pub struct SrvService {
+    pub service: String,
+    pub protocol: String,
+    pub port: u16,
+    pub priority: i32,
+    pub weight: i32,
+}
+
+impl SrvService {
+    pub fn new(service: &str, port: u16) -> Self {
+        SrvService {
+            service: service.trim_start_matches('_').to_owned(),
+            protocol: "tcp".to_owned(),
+            port,
+            priority: 10,
+            weight: 10,
+        }
+    }
+}
+
+impl LinodeClient {
+    pub async fn publish_srv(
+        &self,
+        domain: u64,
+        srv: &SrvService,
+        tag: &str,
+        target: &str,
+    ) -> Result<DomainRecord, LinodeError> {
+        info!(
+            "Publishing SRV record _{}._{}.{} -> {}:{} in domain ID: {}",
+            srv.service, srv.protocol, tag, target, srv.port, domain
+        );
+        let options = DomainRecordOptions {
+            record_type: SRV_RECORD.to_owned(),
+            name: tag.to_owned(),
+            target: target.to_owned(),
+            ttl_sec: 30,
+            service: Some(srv.service.clone()),
+            protocol: Some(srv.protocol.clone()),
+            priority: Some(srv.priority),
+            weight: Some(srv.weight),
+            port: Some(i32::from(srv.port)),
+        };
+        let record = self.post_record(domain, &options).await?;
+
+        if self.verify_writes {
+            let stored = self.fetch_record(domain, record.id).await?;
+            verify_record_field(domain, stored.id, "type", SRV_RECORD, &stored.record_type)?;
+            verify_record_field(domain, stored.id, "target", target, &stored.target)?;
+            return Ok(stored);
+        }
+
+        Ok(record)
+    }
+
+    // delete every SRV record pointing at target, returning how many went
+    pub async fn remove_srv_targets(
+        &self,
+        domain: u64,
+        target: &str,
+    ) -> Result<usize, LinodeError> {
+        let records = self.fetch_records(domain).await?;
+        let stale = records
+            .iter()
+            .filter(|rec| {
+                rec.record_type == SRV_RECORD && rec.target.trim_end_matches('.') == target
+            })
+            .map(|rec| rec.id)
+            .collect::<Vec<_>>();
+
+        for id in &stale {
+            info!("Removing SRV record ID: {} for target: {}", id, target);
+            self.delete_record(domain, *id).await?;
+        }
+        Ok(stale.len())
+    }
+}
@@ -0,0 +1,323 @@
+use crate::history::{Inventory, InventoryUpdate, Snapshot};
+use crate::vcr::{json_response, path_and_query};
+use crate::{LinodeError, X_FILTER};
+use reqwest::{Method, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{error, info};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+// snapshots kept in the history file; older ones are dropped as new ones
+// are appended
+const HISTORY_LIMIT: usize = 500;
+
+// One successful GET response and when it was fetched (unix time).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    fetched_at: u64,
+    body: String,
+}
+
+// Last known answer for every GET the client has made, keyed by path and
+// query and kept on disk between runs. Online the cache is only written,
+// to the file once, by flush or on drop; offline it answers reads and
+// refuses everything else, so listings and diffs still work during API
+// outages, just stale.
+//
+// Online, every unfiltered instance or domain record listing that changes
+// the inventory, once all its pages are in, also appends a Snapshot to a
+// history file beside the cache (state.history.jsonl for state.json), to
+// diff points in time. The history keeps the last HISTORY_LIMIT.
+#[derive(Debug)]
+pub struct StateCache {
+    path: PathBuf,
+    offline: bool,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    // entries changed since the file was last written
+    dirty: AtomicBool,
+    // fetch time of the oldest entry served offline
+    oldest_served: Mutex<Option<u64>>,
+    // the latest snapshot's, read from the history on first use
+    inventory: Mutex<Option<Inventory>>,
+    // listings partway through their pages, by path: the last page seen
+    // and the pages so far
+    pending: Mutex<HashMap<String, (u32, InventoryUpdate)>>,
+}
+
+impl StateCache {
+    // a missing or unreadable file starts an empty cache
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let entries = match std::fs::read_to_string(path) {
+            Ok(source) => serde_json::from_str(&source).unwrap_or_else(|e| {
+                error!("Ignoring unreadable state cache {}: {}", path.display(), e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        StateCache {
+            path: path.to_path_buf(),
+            offline: false,
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+            oldest_served: Mutex::new(None),
+            inventory: Mutex::new(None),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // serve from the cache only, which must exist
+    pub fn offline<P: AsRef<Path>>(path: P) -> Result<Self, LinodeError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(LinodeError::Offline(format!(
+                "no cached state at {}",
+                path.display()
+            )));
+        }
+        let mut cache = StateCache::open(path);
+        cache.offline = true;
+        info!(
+            "Offline: answering from {} cached responses in {}",
+            cache.entries.lock().unwrap().len(),
+            path.display()
+        );
+        Ok(cache)
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    // how old the stalest answer served so far is, None if nothing came
+    // from the cache
+    pub fn staleness(&self) -> Option<Duration> {
+        let oldest = (*self.oldest_served.lock().unwrap())?;
+        Some(Duration::from_secs(now().saturating_sub(oldest)))
+    }
+
+    // unix time the stalest answer served so far was fetched
+    pub fn oldest_served(&self) -> Option<u64> {
+        *self.oldest_served.lock().unwrap()
+    }
+
+    pub(crate) fn lookup(&self, request: &Request) -> Result<Response, LinodeError> {
+        let key = path_and_query(request);
+        if request.method() != Method::GET {
+            return Err(LinodeError::Offline(format!(
+                "{} {} needs the API",
+                request.method(),
+                key
+            )));
+        }
+        let entries = self.entries.lock().unwrap();
+        let cached = entries
+            .get(&key)
+            .ok_or_else(|| LinodeError::Offline(format!("nothing cached for GET {}", key)))?;
+        let mut oldest = self.oldest_served.lock().unwrap();
+        *oldest = Some(oldest.map_or(cached.fetched_at, |t| t.min(cached.fetched_at)));
+        json_response(200, cached.body.clone())
+    }
+
+    // keep successful GETs and hand back an equivalent response, since
+    // reading the body consumes it
+    pub(crate) async fn store(
+        &self,
+        request: &Request,
+        response: Response,
+    ) -> Result<Response, reqwest::Error> {
+        if request.method() != Method::GET || !response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        let rebuilt = json_response(status, body.clone()).expect("status is valid");
+        if request.headers().get(X_FILTER).is_none() {
+            let path = request.url().path();
+            if let Some((update, page, pages)) = InventoryUpdate::parse(path, &body) {
+                self.note_page(path, update, page, pages);
+            }
+        }
+
+        self.entries.lock().unwrap().insert(
+            path_and_query(request),
+            CachedResponse {
+                fetched_at: now(),
+                body,
+            },
+        );
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(rebuilt)
+    }
+
+    // write the responses stored since the last flush to the file
+    pub fn flush(&self) -> std::io::Result<()> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let entries = self.entries.lock().unwrap();
+        self.save(&entries)
+            .inspect_err(|_| self.dirty.store(true, Ordering::Relaxed))
+    }
+
+    pub fn history_path(&self) -> PathBuf {
+        self.path.with_extension("history.jsonl")
+    }
+
+    // every snapshot in the history, oldest first; unreadable lines are
+    // skipped
+    pub fn history(&self) -> Result<Vec<Snapshot>, LinodeError> {
+        let path = self.history_path();
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(LinodeError::Offline(format!("{}: {}", path.display(), e))),
+        };
+        Ok(source
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str::<Snapshot>(line) {
+                Ok(snapshot) => Some(snapshot),
+                Err(e) => {
+                    error!("Skipping unreadable snapshot in {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    // Append inventory to the history as it is now, e.g. what
+    // fetch_inventory returned, whether or not it changed.
+    pub fn record_snapshot(&self, inventory: Inventory) -> Result<Snapshot, LinodeError> {
+        let mut latest = self.inventory.lock().unwrap();
+        let snapshot = Snapshot {
+            at: now(),
+            inventory,
+        };
+        self.append_history(&snapshot).map_err(|e| {
+            LinodeError::Offline(format!("{}: {}", self.history_path().display(), e))
+        })?;
+        *latest = Some(snapshot.inventory.clone());
+        Ok(snapshot)
+    }
+
+    // Hold a listing's pages until the last one is in; a page out of
+    // order (a restarted or abandoned listing) drops what was held.
+    fn note_page(&self, listing: &str, update: InventoryUpdate, page: u32, pages: u32) {
+        let mut pending = self.pending.lock().unwrap();
+        let held = pending.remove(listing);
+        let update = if page <= 1 {
+            update
+        } else {
+            match held {
+                Some((last, mut so_far)) if last + 1 == page => {
+                    so_far.extend(update);
+                    so_far
+                }
+                _ => return,
+            }
+        };
+        if page < pages {
+            pending.insert(listing.to_owned(), (page.max(1), update));
+            return;
+        }
+        drop(pending);
+        self.note_inventory(update);
+    }
+
+    fn note_inventory(&self, update: InventoryUpdate) {
+        let mut latest = self.inventory.lock().unwrap();
+        let current = latest.get_or_insert_with(|| {
+            self.history()
+                .ok()
+                .and_then(|snapshots| snapshots.into_iter().last())
+                .map(|snapshot| snapshot.inventory)
+                .unwrap_or_default()
+        });
+        let mut next = current.clone();
+        update.apply(&mut next);
+        if next == *current {
+            return;
+        }
+        let snapshot = Snapshot {
+            at: now(),
+            inventory: next,
+        };
+        if let Err(e) = self.append_history(&snapshot) {
+            error!(
+                "Failed to write history {}: {}",
+                self.history_path().display(),
+                e
+            );
+        }
+        *current = snapshot.inventory;
+    }
+
+    // append snapshot, rewriting the file with only the latest
+    // HISTORY_LIMIT once it would hold more
+    fn append_history(&self, snapshot: &Snapshot) -> std::io::Result<()> {
+        let path = self.history_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let line = serde_json::to_string(snapshot)?;
+        let existing = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let lines = existing
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>();
+        if lines.len() < HISTORY_LIMIT {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            return writeln!(file, "{}", line);
+        }
+        info!(
+            "Dropping {} old snapshots from {}",
+            lines.len() + 1 - HISTORY_LIMIT,
+            path.display()
+        );
+        let mut kept = lines[lines.len() + 1 - HISTORY_LIMIT..].join("\n");
+        kept.push('\n');
+        kept.push_str(&line);
+        kept.push('\n');
+        std::fs::write(path, kept)
+    }
+
+    fn save(&self, entries: &HashMap<String, CachedResponse>) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string(entries)?)
+    }
+}
+
+impl Drop for StateCache {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            error!("Failed to write state cache {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+pub fn default_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("linode").join("state.json"))
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
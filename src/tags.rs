@@ -0,0 +1,169 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Boolean expression over instance tags, e.g. "prod & !canary | staging".
+// `!` binds tighter than `&`, which binds tighter than `|`; parentheses
+// can be used for grouping.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagExpr {
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagExprError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TagExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid tag expression at position {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for TagExprError {}
+
+impl TagExpr {
+    pub fn parse(input: &str) -> Result<Self, TagExprError> {
+        let mut parser = Parser {
+            chars: input.char_indices().collect(),
+            pos: 0,
+        };
+        let expr = parser.parse_or()?;
+        parser.skip_whitespace();
+        if let Some(&(i, c)) = parser.chars.get(parser.pos) {
+            return Err(TagExprError {
+                position: i,
+                message: format!("unexpected '{}'", c),
+            });
+        }
+        Ok(expr)
+    }
+
+    pub fn matches<S: AsRef<str>>(&self, tags: &[S]) -> bool {
+        match self {
+            TagExpr::Tag(tag) => tags.iter().any(|t| t.as_ref() == tag),
+            TagExpr::Not(expr) => !expr.matches(tags),
+            TagExpr::And(a, b) => a.matches(tags) && b.matches(tags),
+            TagExpr::Or(a, b) => a.matches(tags) || b.matches(tags),
+        }
+    }
+}
+
+impl FromStr for TagExpr {
+    type Err = TagExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TagExpr::parse(s)
+    }
+}
+
+impl fmt::Display for TagExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagExpr::Tag(tag) => write!(f, "{}", tag),
+            TagExpr::Not(expr) => write!(f, "!{}", expr),
+            TagExpr::And(a, b) => write!(f, "({} & {})", a, b),
+            TagExpr::Or(a, b) => write!(f, "({} | {})", a, b),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<(usize, char)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while let Some((_, c)) = self.chars.get(self.pos) {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+
+    fn offset(&self) -> usize {
+        self.chars
+            .get(self.pos)
+            .map(|&(i, _)| i)
+            .unwrap_or_else(|| self.chars.last().map_or(0, |&(i, c)| i + c.len_utf8()))
+    }
+
+    fn parse_or(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            expr = TagExpr::Or(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some('&') {
+            self.pos += 1;
+            let rhs = self.parse_unary()?;
+            expr = TagExpr::And(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<TagExpr, TagExprError> {
+        match self.peek() {
+            Some('!') => {
+                self.pos += 1;
+                Ok(TagExpr::Not(Box::new(self.parse_unary()?)))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.parse_or()?;
+                if self.peek() != Some(')') {
+                    return Err(TagExprError {
+                        position: self.offset(),
+                        message: "expected ')'".to_owned(),
+                    });
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            _ => self.parse_tag(),
+        }
+    }
+
+    fn parse_tag(&mut self) -> Result<TagExpr, TagExprError> {
+        let start = self.pos;
+        while let Some(&(_, c)) = self.chars.get(self.pos) {
+            if c.is_whitespace() || "&|!()".contains(c) {
+                break;
+            }
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(TagExprError {
+                position: self.offset(),
+                message: "expected a tag".to_owned(),
+            });
+        }
+        let tag = self.chars[start..self.pos]
+            .iter()
+            .map(|&(_, c)| c)
+            .collect();
+        Ok(TagExpr::Tag(tag))
+    }
+}
@@ -0,0 +1,68 @@
+use crate::metadata::Peer;
+use crate::LinodeError;
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+use std::path::Path;
+
+const TEMPLATE_NAME: &str = "user_data";
+const CLOUD_CONFIG_HEADER: &str = "#cloud-config";
+
+// Variables available to user-data templates, e.g. {{ dns_name }}.
+// index is the sequence number of the DNS slot the node is expected
+// to claim. peers are the fleet's other nodes, e.g.
+// {% for peer in peers %}{{ peer.vlan_ip }}{% endfor %}; their
+// fields may be none.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateVars {
+    pub tag: String,
+    pub region: String,
+    pub label: String,
+    pub dns_name: String,
+    pub vlan_ip: String,
+    pub index: i32,
+    pub peers: Vec<Peer>,
+}
+
+// A minijinja user-data template, syntax checked on construction and
+// rendered with strict undefined handling so typos in variable names
+// fail before anything is submitted to the API.
+#[derive(Debug)]
+pub struct UserDataTemplate {
+    env: Environment<'static>,
+}
+
+impl UserDataTemplate {
+    pub fn new(source: String) -> Result<Self, LinodeError> {
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        env.add_template_owned(TEMPLATE_NAME, source)
+            .map_err(|e| LinodeError::Template(e.to_string()))?;
+        Ok(UserDataTemplate { env })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LinodeError> {
+        let source = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| LinodeError::Template(format!("{}: {}", path.as_ref().display(), e)))?;
+        Self::new(source)
+    }
+
+    pub fn render(&self, vars: &TemplateVars) -> Result<String, LinodeError> {
+        let rendered = self
+            .env
+            .get_template(TEMPLATE_NAME)
+            .and_then(|t| t.render(vars))
+            .map_err(|e| LinodeError::Template(e.to_string()))?;
+        validate_user_data(&rendered)?;
+        Ok(rendered)
+    }
+}
+
+// cloud-config documents must parse as YAML; scripts and other cloud-init
+// formats are passed through untouched
+pub fn validate_user_data(user_data: &str) -> Result<(), LinodeError> {
+    if user_data.starts_with(CLOUD_CONFIG_HEADER) {
+        serde_yaml::from_str::<serde_yaml::Value>(user_data)
+            .map_err(|e| LinodeError::Template(format!("invalid cloud-config: {}", e)))?;
+    }
+    Ok(())
+}
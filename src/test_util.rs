@@ -0,0 +1,131 @@
+use crate::LinodeClient;
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+// Canned API responses for a two-node "api" fleet in gb-lon with
+// sequential slot records in example.com (domain ID 2958920). IDs line up
+// across fixtures: instance 1001 is 192.0.2.11 / 10.0.0.1 / api-gb-lon-1.
+pub mod fixtures {
+    pub const INSTANCES: &str = include_str!("../fixtures/instances.json");
+    pub const INSTANCE: &str = include_str!("../fixtures/instance.json");
+    pub const CONFIGS: &str = include_str!("../fixtures/configs.json");
+    pub const RECORDS: &str = include_str!("../fixtures/records.json");
+    pub const RECORD: &str = include_str!("../fixtures/record.json");
+    pub const DOMAIN: &str = include_str!("../fixtures/domain.json");
+
+    pub const DOMAIN_ID: u64 = 2958920;
+    pub const INSTANCE_ID: u64 = 1001;
+}
+
+// base64 of a throwaway ed25519 public key, accepted by LinodeClient::new
+pub const TEST_PUB_KEY: &str = "c3NoLWVkMjU1MTkgQUFBQUMzTnphQzFsWkRJMU5URTVBQUFBSUdQeTNGRXR1Q3g0dVk4Z3JVQ3JWSm5QYzhUYmZtWWhJRkRjMDZ2VlJzTHYgdGVzdA==";
+
+fn json(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.to_owned(), "application/json")
+}
+
+// paths are matched with any number of slashes after /v4, the client
+// joins its base URL and paths as "{base}/{path}"
+fn api_path(path: &str) -> String {
+    format!(r"^/v4/+{}$", path)
+}
+
+// mount the read-only fixture endpoints on server
+pub async fn mount_fixtures(server: &MockServer) {
+    let routes = [
+        (r"linode/instances", fixtures::INSTANCES),
+        (r"linode/instances/\d+", fixtures::INSTANCE),
+        (r"linode/instances/\d+/configs", fixtures::CONFIGS),
+        (r"domains/\d+", fixtures::DOMAIN),
+        (r"domains/\d+/records", fixtures::RECORDS),
+    ];
+    for (path, body) in routes {
+        Mock::given(method("GET"))
+            .and(path_regex(api_path(path)))
+            .respond_with(json(body))
+            .mount(server)
+            .await;
+    }
+}
+
+// mount accepting answers for the writes a scale up or down makes:
+// creating an instance (answered with the INSTANCE fixture), setting its
+// interfaces, rebooting, destroying, and creating (answered with RECORD),
+// retargeting and deleting records
+pub async fn mount_writes(server: &MockServer) {
+    let routes = [
+        ("POST", r"linode/instances", fixtures::INSTANCE),
+        ("POST", r"linode/instances/\d+/(boot|reboot|shutdown)", "{}"),
+        ("PUT", r"linode/instances/\d+/configs/\d+", "{}"),
+        ("DELETE", r"linode/instances/\d+", "{}"),
+        ("POST", r"domains/\d+/records", fixtures::RECORD),
+        ("PUT", r"domains/\d+/records/\d+", "{}"),
+        ("DELETE", r"domains/\d+/records/\d+", "{}"),
+    ];
+    for (verb, path, body) in routes {
+        Mock::given(method(verb))
+            .and(path_regex(api_path(path)))
+            .respond_with(json(body))
+            .mount(server)
+            .await;
+    }
+}
+
+// a mock server with every fixture mounted; add Mocks for the writes a
+// test expects on top, or mount_writes for the usual ones
+pub async fn mock_server() -> MockServer {
+    let server = MockServer::start().await;
+    mount_fixtures(&server).await;
+    server
+}
+
+pub fn client_for(server: &MockServer) -> LinodeClient {
+    LinodeClient::new("test-token".to_owned(), TEST_PUB_KEY.to_owned())
+        .expect("test key decodes")
+        .with_base_url(&format!("{}/v4/", server.uri()))
+}
+
+// the requests server received for verb and a path under /v4, e.g.
+// ("DELETE", r"linode/instances/\d+")
+pub async fn hits(server: &MockServer, verb: &str, path: &str) -> Vec<Request> {
+    let path = regex::Regex::new(&api_path(path)).expect("path is a valid regex");
+    server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|request| request.method.as_str() == verb && path.is_match(request.url.path()))
+        .collect()
+}
+
+// every request server received, as "METHOD /path" lines for assertion
+// messages
+pub async fn received(server: &MockServer) -> String {
+    server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|request| format!("{} {}", request.method, request.url.path()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// panics unless server received exactly times requests for verb and path
+pub async fn assert_hit(server: &MockServer, verb: &str, path: &str, times: usize) {
+    let hits = hits(server, verb, path).await.len();
+    assert_eq!(
+        hits,
+        times,
+        "expected {} {} {} times, got {}; received:\n{}",
+        verb,
+        path,
+        times,
+        hits,
+        received(server).await
+    );
+}
+
+pub async fn assert_not_hit(server: &MockServer, verb: &str, path: &str) {
+    assert_hit(server, verb, path, 0).await;
+}
@@ -0,0 +1,147 @@
+use crate::api_calls::ApiCalls;
+use crate::LinodeClient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+use web_time::Instant;
+
+const METRIC_NAME: &str = "linode_scale_step_duration_seconds";
+
+// histogram bucket upper bounds in seconds, out to the default boot timeout
+const BUCKETS: &[f64] = &[0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+// How long one step of a scale operation took.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepTiming {
+    pub step: String,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    // cumulative, one per BUCKETS entry
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+// Duration histograms of every scale step the client has timed, keyed by
+// operation (scale_up, scale_down) and step.
+#[derive(Debug, Default)]
+pub struct StepMetrics {
+    histograms: Mutex<BTreeMap<(String, String), Histogram>>,
+}
+
+impl StepMetrics {
+    pub fn observe(&self, operation: &str, step: &str, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let mut histograms = self.histograms.lock().unwrap();
+        let histogram = histograms
+            .entry((operation.to_owned(), step.to_owned()))
+            .or_insert_with(|| Histogram {
+                buckets: vec![0; BUCKETS.len()],
+                ..Default::default()
+            });
+        for (bucket, le) in histogram.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= *le {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histograms.lock().unwrap().is_empty()
+    }
+
+    // the histograms in the Prometheus text exposition format, e.g. for
+    // node_exporter's textfile collector
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP {} Time spent in each step of a scale operation.",
+            METRIC_NAME
+        );
+        let _ = writeln!(out, "# TYPE {} histogram", METRIC_NAME);
+        for ((operation, step), histogram) in self.histograms.lock().unwrap().iter() {
+            let labels = format!("operation=\"{}\",step=\"{}\"", operation, step);
+            for (bucket, le) in histogram.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{{},le=\"{}\"}} {}",
+                    METRIC_NAME, labels, le, bucket
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"+Inf\"}} {}",
+                METRIC_NAME, labels, histogram.count
+            );
+            let _ = writeln!(out, "{}_sum{{{}}} {}", METRIC_NAME, labels, histogram.sum);
+            let _ = writeln!(
+                out,
+                "{}_count{{{}}} {}",
+                METRIC_NAME, labels, histogram.count
+            );
+        }
+        out
+    }
+}
+
+// Times the consecutive steps of one operation: each lap is the time since
+// the previous one, recorded in the client's metrics and kept for the node.
+pub(crate) struct StepClock<'a> {
+    metrics: &'a StepMetrics,
+    operation: &'static str,
+    last: Instant,
+    timings: Vec<StepTiming>,
+    // the client's call count when the clock started
+    calls: &'a ApiCalls,
+    calls_at_start: u64,
+}
+
+impl<'a> StepClock<'a> {
+    pub(crate) fn start(client: &'a LinodeClient, operation: &'static str) -> Self {
+        StepClock {
+            metrics: &client.step_metrics,
+            operation,
+            last: Instant::now(),
+            timings: Vec::new(),
+            calls: &client.api_calls,
+            calls_at_start: client.api_calls.total(),
+        }
+    }
+
+    pub(crate) fn lap(&mut self, step: &str) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last);
+        self.last = now;
+        info!("Step {} of {} took {:?}", step, self.operation, elapsed);
+        self.metrics.observe(self.operation, step, elapsed);
+        self.timings.push(StepTiming {
+            step: step.to_owned(),
+            seconds: elapsed.as_secs_f64(),
+        });
+    }
+
+    // API calls the client has made since the clock started, counting
+    // those of operations running alongside this one
+    pub(crate) fn api_calls(&self) -> u64 {
+        self.calls.total() - self.calls_at_start
+    }
+
+    pub(crate) fn finish(self) -> Vec<StepTiming> {
+        self.timings
+    }
+}
+
+impl LinodeClient {
+    pub fn step_metrics(&self) -> &StepMetrics {
+        &self.step_metrics
+    }
+}
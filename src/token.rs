@@ -0,0 +1,123 @@
+use crate::{LinodeClient, LinodeError, ListOptions, Method};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+// what scaling needs: creating and destroying instances and writing
+// their DNS records
+pub const REQUIRED_SCOPES: &[&str] = &["linodes:read_write", "domains:read_write"];
+
+#[derive(Debug, Deserialize)]
+struct Profile {
+    username: String,
+    #[serde(default)]
+    restricted: bool,
+}
+
+// A personal access token as /profile/tokens lists it; token is only
+// its first 16 characters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalAccessToken {
+    pub id: u64,
+    pub label: String,
+    // space separated, e.g. "linodes:read_write domains:read_only", or "*"
+    pub scopes: String,
+    pub token: String,
+    pub created: String,
+    pub expiry: Option<String>,
+}
+
+// What verify found out about the client's token. scopes is None when
+// they couldn't be read, e.g. for an OAuth token /profile/tokens doesn't
+// list, in which case nothing is reported missing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenReport {
+    pub valid: bool,
+    pub username: Option<String>,
+    // a restricted user's grants can narrow what the scopes allow
+    pub restricted: bool,
+    pub label: Option<String>,
+    pub scopes: Option<Vec<String>>,
+    pub expiry: Option<String>,
+    pub missing: Vec<String>,
+}
+
+impl TokenReport {
+    pub fn is_ok(&self) -> bool {
+        self.valid && self.missing.is_empty()
+    }
+}
+
+// whether scopes include wanted; read_write covers read_only
+fn grants(scopes: &[String], wanted: &str) -> bool {
+    let area = wanted.split(':').next().unwrap_or(wanted);
+    scopes.iter().any(|scope| {
+        scope == "*"
+            || scope == wanted
+            || (wanted.ends_with(":read_only") && *scope == format!("{}:read_write", area))
+    })
+}
+
+impl LinodeClient {
+    // Check the token works and carries REQUIRED_SCOPES before anything
+    // is changed, instead of failing halfway through a scale. A rejected
+    // token is reported as invalid rather than returned as an error.
+    pub async fn verify(&self) -> Result<TokenReport, LinodeError> {
+        info!("Verifying the API token");
+        let profile = match self.request::<Profile>(Method::GET, "profile", None).await {
+            Ok(profile) => profile,
+            // a token source that can't produce a token is as good as
+            // a rejected one
+            Err(LinodeError::Auth { .. } | LinodeError::Token(_)) => {
+                return Ok(TokenReport {
+                    missing: REQUIRED_SCOPES.iter().map(|s| s.to_string()).collect(),
+                    ..Default::default()
+                })
+            }
+            Err(e) => return Err(e),
+        };
+        let mut report = TokenReport {
+            valid: true,
+            username: Some(profile.username),
+            restricted: profile.restricted,
+            ..Default::default()
+        };
+
+        // listing tokens needs a scope of its own; without it the scopes
+        // stay unknown
+        let tokens = match self
+            .fetch_all::<PersonalAccessToken>("profile/tokens", &ListOptions::default())
+            .await
+        {
+            Ok(tokens) => tokens,
+            Err(LinodeError::Auth { .. } | LinodeError::Token(_)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let current = self.current_token().await?;
+        if let Some(token) = tokens
+            .into_iter()
+            .find(|t| !t.token.is_empty() && current.starts_with(&t.token))
+        {
+            let scopes = token
+                .scopes
+                .split([' ', ','])
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+            report.missing = REQUIRED_SCOPES
+                .iter()
+                .filter(|wanted| !grants(&scopes, wanted))
+                .map(|s| s.to_string())
+                .collect();
+            report.label = Some(token.label);
+            report.expiry = token.expiry;
+            report.scopes = Some(scopes);
+        }
+
+        info!(
+            "Token for {} is valid, missing scopes: {:?}",
+            report.username.as_deref().unwrap_or("-"),
+            report.missing
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,261 @@
+use crate::secret;
+use crate::{LinodeClient, LinodeError};
+use async_trait::async_trait;
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::Request;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+use web_time::Instant;
+
+#[cfg(feature = "native")]
+pub use self::keychain::KeychainToken;
+
+// how long a token from a TokenSource is used before it's read again; a
+// 401 re-reads it straight away
+pub const TOKEN_REFRESH: Duration = Duration::from_secs(300);
+
+// Where the client gets its API token from, instead of a fixed string,
+// so a long-running process picks up a rotated token without restarting:
+//
+//   let client = LinodeClient::from_token_source(
+//       FileToken::new("/run/secrets/linode-token"),
+//       pub_key,
+//   )?;
+//
+// The token is read on first use, again every TOKEN_REFRESH (see
+// with_token_refresh), and whenever the API rejects it.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait TokenSource: Send + Sync {
+    async fn token(&self) -> Result<String, LinodeError>;
+}
+
+// The same token every time.
+#[derive(Debug, Clone)]
+pub struct StaticToken(pub String);
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TokenSource for StaticToken {
+    async fn token(&self) -> Result<String, LinodeError> {
+        Ok(self.0.clone())
+    }
+}
+
+// An environment variable, e.g. LINODE_API_TOKEN.
+#[derive(Debug, Clone)]
+pub struct EnvToken {
+    pub var: String,
+}
+
+impl EnvToken {
+    pub fn new(var: &str) -> Self {
+        EnvToken {
+            var: var.to_owned(),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TokenSource for EnvToken {
+    async fn token(&self) -> Result<String, LinodeError> {
+        std::env::var(&self.var).map_err(|e| LinodeError::Token(format!("{}: {}", self.var, e)))
+    }
+}
+
+// A file holding nothing but the token, e.g. a mounted secret that is
+// rewritten when the token rotates. Surrounding whitespace is ignored.
+#[derive(Debug, Clone)]
+pub struct FileToken {
+    pub path: PathBuf,
+}
+
+impl FileToken {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileToken { path: path.into() }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TokenSource for FileToken {
+    async fn token(&self) -> Result<String, LinodeError> {
+        std::fs::read_to_string(&self.path)
+            .map_err(|e| LinodeError::Token(format!("{}: {}", self.path.display(), e)))
+    }
+}
+
+// Whatever the function returns, e.g. a token fetched from a vault.
+pub struct FnTokenSource<F>(pub F);
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<F> TokenSource for FnTokenSource<F>
+where
+    F: Fn() -> Result<String, LinodeError> + Send + Sync,
+{
+    async fn token(&self) -> Result<String, LinodeError> {
+        (self.0)()
+    }
+}
+
+#[cfg(feature = "native")]
+mod keychain {
+    use super::*;
+    use tokio::process::Command;
+
+    // A generic password in the OS keychain: the login keychain via
+    // `security` on macOS, the Secret Service via `secret-tool` elsewhere.
+    // Store it with e.g.
+    //
+    //   security add-generic-password -s linode -a api-token -w
+    //   secret-tool store --label=linode service linode account api-token
+    #[derive(Debug, Clone)]
+    pub struct KeychainToken {
+        pub service: String,
+        pub account: String,
+    }
+
+    impl KeychainToken {
+        pub fn new(service: &str, account: &str) -> Self {
+            KeychainToken {
+                service: service.to_owned(),
+                account: account.to_owned(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TokenSource for KeychainToken {
+        async fn token(&self) -> Result<String, LinodeError> {
+            let mut cmd = if cfg!(target_os = "macos") {
+                let mut cmd = Command::new("security");
+                cmd.args(["find-generic-password", "-w", "-s"])
+                    .arg(&self.service)
+                    .arg("-a")
+                    .arg(&self.account);
+                cmd
+            } else {
+                let mut cmd = Command::new("secret-tool");
+                cmd.args(["lookup", "service"])
+                    .arg(&self.service)
+                    .arg("account")
+                    .arg(&self.account);
+                cmd
+            };
+            let output = cmd
+                .output()
+                .await
+                .map_err(|e| LinodeError::Token(format!("failed to read the keychain: {}", e)))?;
+            if !output.status.success() {
+                return Err(LinodeError::Token(format!(
+                    "no keychain entry for service {} account {}",
+                    self.service, self.account
+                )));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+    }
+}
+
+impl LinodeClient {
+    // a client whose token is read from source, see TokenSource
+    pub fn from_token_source<S: TokenSource + 'static>(
+        source: S,
+        pub_key: String,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        LinodeClient::builder(String::new(), pub_key)
+            .token_source(source)
+            .build()
+    }
+
+    // read the token from source from here on instead of the one the
+    // client was made with
+    pub fn with_token_source<S: TokenSource + 'static>(mut self, source: S) -> Self {
+        self.token_source = Some(Arc::new(source));
+        self.current_token = Default::default();
+        self
+    }
+
+    // how long a token from the source is used before it's read again
+    pub fn with_token_refresh(mut self, every: Duration) -> Self {
+        self.token_refresh = every;
+        self
+    }
+
+    // The token requests are sent with. If the source fails once a token
+    // has been read, the old one is kept until the next refresh rather
+    // than failing the call.
+    pub(crate) async fn current_token(&self) -> Result<String, LinodeError> {
+        let Some(source) = &self.token_source else {
+            return Ok(self.token.expose().to_owned());
+        };
+        let cached = self.current_token.lock().unwrap().clone();
+        if let Some((token, read)) = &cached {
+            if read.elapsed() < self.token_refresh {
+                return Ok(token.clone());
+            }
+        }
+        match read_token(source.as_ref()).await {
+            Ok(token) => {
+                if cached.as_ref().is_some_and(|(old, _)| *old != token) {
+                    info!("Picked up a new API token from the token source");
+                }
+                *self.current_token.lock().unwrap() = Some((token.clone(), Instant::now()));
+                Ok(token)
+            }
+            Err(e) => match cached {
+                Some((token, _)) => {
+                    error!(
+                        "Failed to refresh the API token, keeping the old one: {}",
+                        e
+                    );
+                    *self.current_token.lock().unwrap() = Some((token.clone(), Instant::now()));
+                    Ok(token)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    // After stale was rejected, read the source again; the new token if
+    // it differs, so the request is worth sending again.
+    pub(crate) async fn refreshed_token(&self, stale: &str) -> Option<String> {
+        let source = self.token_source.as_ref()?;
+        match read_token(source.as_ref()).await {
+            Ok(token) => {
+                *self.current_token.lock().unwrap() = Some((token.clone(), Instant::now()));
+                (token != stale).then(|| {
+                    info!("API token was rejected, retrying with a new one");
+                    token
+                })
+            }
+            Err(e) => {
+                error!("Failed to refresh the rejected API token: {}", e);
+                None
+            }
+        }
+    }
+}
+
+async fn read_token(source: &dyn TokenSource) -> Result<String, LinodeError> {
+    let token = source.token().await?.trim().to_owned();
+    if token.is_empty() {
+        return Err(LinodeError::Token(
+            "the token source gave an empty token".to_owned(),
+        ));
+    }
+    secret::register(&token);
+    Ok(token)
+}
+
+pub(crate) fn authorize(request: &mut Request, token: &str) -> Result<(), LinodeError> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| LinodeError::Token("the token isn't a valid header value".to_owned()))?;
+    value.set_sensitive(true);
+    request.headers_mut().insert(AUTHORIZATION, value);
+    Ok(())
+}
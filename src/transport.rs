@@ -0,0 +1,49 @@
+use crate::vcr;
+use async_trait::async_trait;
+use reqwest::{Client, Request, Response};
+
+// Sends a request for LinodeClient, below retries, pacing and recording.
+// reqwest::Client is the default; tests can swap in one that answers
+// from memory so scaling logic runs without the API:
+//
+//   let client = LinodeClient::builder(token, pub_key)
+//       .transport(FnTransport(|request: &Request| {
+//           match (request.method().as_str(), request.url().path()) {
+//               ("GET", "/v4/linode/instances") => json_response(200, INSTANCES),
+//               _ => json_response(404, r#"{"errors":[]}"#),
+//           }
+//       }))
+//       .build()?;
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait LinodeTransport: Send + Sync {
+    async fn execute(&self, request: Request) -> Result<Response, reqwest::Error>;
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl LinodeTransport for Client {
+    async fn execute(&self, request: Request) -> Result<Response, reqwest::Error> {
+        Client::execute(self, request).await
+    }
+}
+
+// Answers every request with what the function returns for it.
+pub struct FnTransport<F>(pub F);
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<F> LinodeTransport for FnTransport<F>
+where
+    F: Fn(&Request) -> Response + Send + Sync,
+{
+    async fn execute(&self, request: Request) -> Result<Response, reqwest::Error> {
+        Ok((self.0)(&request))
+    }
+}
+
+// a JSON response for transports that answer from memory; panics if
+// status isn't a valid HTTP status
+pub fn json_response(status: u16, body: &str) -> Response {
+    vcr::json_response(status, body.to_owned()).expect("status is valid")
+}
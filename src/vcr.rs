@@ -0,0 +1,192 @@
+use crate::LinodeError;
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{error, info};
+
+// request body fields that never make it into a cassette
+const REDACTED_FIELDS: &[&str] = &[
+    "root_pass",
+    "authorized_keys",
+    "password",
+    "token",
+    "ssl_key",
+];
+const REDACTED: &str = "<redacted>";
+
+// One API call as stored in a cassette. Headers aren't kept at all, so
+// the bearer token can't leak into a recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    // path and query, without the host
+    pub path: String,
+    pub request_body: Option<Value>,
+    pub status: u16,
+    pub response_body: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+// Record/replay for whole client sessions. Recording passes every call
+// through to the API and appends it to the cassette file; replaying serves
+// calls from the file in recorded order and never touches the network, so
+// multi-step operations like scale_up_one can run offline.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    interactions: Mutex<Vec<Interaction>>,
+    // replay: which interactions have been served
+    played: Mutex<Vec<bool>>,
+}
+
+impl Cassette {
+    // start a new recording, replacing whatever is at path
+    pub fn record<P: AsRef<Path>>(path: P) -> Self {
+        Cassette {
+            path: path.as_ref().to_path_buf(),
+            mode: CassetteMode::Record,
+            interactions: Mutex::new(Vec::new()),
+            played: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn replay<P: AsRef<Path>>(path: P) -> Result<Self, LinodeError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| LinodeError::Cassette(format!("{}: {}", path.display(), e)))?;
+        let file: CassetteFile = serde_json::from_str(&source)
+            .map_err(|e| LinodeError::Cassette(format!("{}: {}", path.display(), e)))?;
+        info!(
+            "Replaying {} interactions from {}",
+            file.interactions.len(),
+            path.display()
+        );
+        Ok(Cassette {
+            path: path.to_path_buf(),
+            mode: CassetteMode::Replay,
+            played: Mutex::new(vec![false; file.interactions.len()]),
+            interactions: Mutex::new(file.interactions),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.mode == CassetteMode::Replay
+    }
+
+    // the first unplayed interaction with the same method and path
+    pub(crate) fn play(&self, request: &Request) -> Result<Response, LinodeError> {
+        let method = request.method().to_string();
+        let path = path_and_query(request);
+        let interactions = self.interactions.lock().unwrap();
+        let mut played = self.played.lock().unwrap();
+
+        let (i, interaction) = interactions
+            .iter()
+            .enumerate()
+            .find(|(i, it)| !played[*i] && it.method == method && it.path == path)
+            .ok_or_else(|| {
+                LinodeError::Cassette(format!(
+                    "no recorded interaction left for {} {} in {}",
+                    method,
+                    path,
+                    self.path.display()
+                ))
+            })?;
+        played[i] = true;
+        to_response(interaction)
+    }
+
+    // store the response and hand back an equivalent one, since reading
+    // the body consumes it
+    pub(crate) async fn capture(
+        &self,
+        request: &Request,
+        response: Response,
+    ) -> Result<Response, reqwest::Error> {
+        let status = response.status().as_u16();
+        let response_body = response.text().await?;
+        let interaction = Interaction {
+            method: request.method().to_string(),
+            path: path_and_query(request),
+            request_body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .and_then(|bytes| serde_json::from_slice(bytes).ok())
+                .map(|mut body| {
+                    sanitize(&mut body);
+                    body
+                }),
+            status,
+            response_body,
+        };
+
+        let rebuilt = to_response(&interaction).expect("recorded status is valid");
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(interaction);
+        if let Err(e) = self.save(&interactions) {
+            error!("Failed to write cassette {}: {}", self.path.display(), e);
+        }
+        Ok(rebuilt)
+    }
+
+    fn save(&self, interactions: &[Interaction]) -> std::io::Result<()> {
+        let file = CassetteFile {
+            interactions: interactions.to_vec(),
+        };
+        std::fs::write(&self.path, serde_json::to_string_pretty(&file)?)
+    }
+}
+
+pub(crate) fn path_and_query(request: &Request) -> String {
+    let url = request.url();
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_owned(),
+    }
+}
+
+fn to_response(interaction: &Interaction) -> Result<Response, LinodeError> {
+    json_response(interaction.status, interaction.response_body.clone())
+}
+
+pub(crate) fn json_response(status: u16, body: String) -> Result<Response, LinodeError> {
+    let response = http::Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(body)
+        .map_err(|e| LinodeError::Cassette(e.to_string()))?;
+    Ok(Response::from(response))
+}
+
+fn sanitize(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if REDACTED_FIELDS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.to_owned());
+                } else {
+                    sanitize(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(sanitize),
+        _ => {}
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{LinodeClient, LinodeError, ListOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Volume {
+    pub id: u64,
+    pub label: String,
+    pub status: String,
+    pub size: u64,
+    pub region: String,
+    pub linode_id: Option<u64>,
+    pub linode_label: Option<String>,
+    pub filesystem_path: String,
+    pub tags: Vec<String>,
+    pub created: String,
+    pub updated: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VolumeCreateOptions {
+    pub label: String,
+    // size in GB
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub linode_id: Option<u64>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VolumeAttachOptions {
+    linode_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_id: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VolumeResizeOptions {
+    size: u64,
+}
+
+// X-Filter matching the --tag/--region flags used by the instance tooling
+pub fn volume_filter(tag: Option<&str>, region: Option<&str>) -> ListOptions {
+    let mut filters = Vec::new();
+    if let Some(tag) = tag {
+        filters.push(json!({ "tags": tag }));
+    }
+    if let Some(region) = region {
+        filters.push(json!({ "region": region }));
+    }
+    ListOptions {
+        filter: (!filters.is_empty()).then(|| json!({ "+and": filters })),
+        ..Default::default()
+    }
+}
+
+impl LinodeClient {
+    pub async fn fetch_volumes(&self, options: &ListOptions) -> Result<Vec<Volume>, LinodeError> {
+        info!("Fetching volumes");
+        let volumes = self.fetch_all::<Volume>("volumes", options).await?;
+        info!("Fetched {} volumes", volumes.len());
+
+        Ok(volumes)
+    }
+
+    pub async fn create_volume(
+        &self,
+        options: &VolumeCreateOptions,
+    ) -> Result<Volume, LinodeError> {
+        info!(
+            "Creating volume with label: {} and size: {}GB",
+            options.label, options.size
+        );
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/volumes", self.base_url))
+                    .bearer_auth(self.token.expose())
+                    .json(options),
+            )
+            .await?;
+
+        let volume = response.json::<Volume>().await?;
+        info!(
+            "Created volume ID: {} with label: {}",
+            volume.id, volume.label
+        );
+        Ok(volume)
+    }
+
+    pub async fn attach_volume(
+        &self,
+        id: u64,
+        linode_id: u64,
+        config_id: Option<u64>,
+    ) -> Result<Volume, LinodeError> {
+        info!("Attaching volume ID: {} to instance ID: {}", id, linode_id);
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/volumes/{}/attach", self.base_url, id))
+                    .bearer_auth(self.token.expose())
+                    .json(&VolumeAttachOptions {
+                        linode_id,
+                        config_id,
+                    }),
+            )
+            .await?;
+
+        info!("Volume ID: {} attached successfully", id);
+        response.json::<Volume>().await
+    }
+
+    pub async fn detach_volume(&self, id: u64) -> Result<(), LinodeError> {
+        info!("Detaching volume ID: {}", id);
+        self.send(
+            self.client
+                .post(format!("{}/volumes/{}/detach", self.base_url, id))
+                .bearer_auth(self.token.expose()),
+        )
+        .await?
+        .error_for_status()
+        .await?;
+
+        info!("Volume ID: {} detached successfully", id);
+        Ok(())
+    }
+
+    // volumes can only grow
+    pub async fn resize_volume(&self, id: u64, size: u64) -> Result<Volume, LinodeError> {
+        info!("Resizing volume ID: {} to {}GB", id, size);
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}/volumes/{}/resize", self.base_url, id))
+                    .bearer_auth(self.token.expose())
+                    .json(&VolumeResizeOptions { size }),
+            )
+            .await?;
+
+        info!("Volume ID: {} resized successfully", id);
+        response.json::<Volume>().await
+    }
+}
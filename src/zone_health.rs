@@ -0,0 +1,150 @@
+use crate::health::check_tcp;
+use crate::{LinodeClient, LinodeError, A_RECORD, LOCALHOST};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::time::Duration;
+use tracing::info;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+// The state of one managed A record: whether its target answers, its TTL,
+// and how long ago the record last changed by the API's updated time.
+// Parked slots point at the sentinel target and aren't probed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordHealth {
+    pub name: String,
+    pub target: String,
+    pub ttl_sec: i32,
+    pub parked: bool,
+    pub reachable: Option<bool>,
+    pub age_seconds: Option<u64>,
+}
+
+// Per-record health of a zone's managed prefixes, rendered as OpenMetrics
+// gauges for the --metrics-file exporter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneHealth {
+    pub domain: String,
+    pub records: Vec<RecordHealth>,
+}
+
+// a gauge's value for one record, None to leave the record out
+type GaugeValue = fn(&RecordHealth) -> Option<f64>;
+
+impl ZoneHealth {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let families: [(&str, &str, GaugeValue); 4] = [
+            (
+                "linode_dns_record_reachable",
+                "Whether the record's target accepted a TCP connection.",
+                |rec| rec.reachable.map(|up| if up { 1.0 } else { 0.0 }),
+            ),
+            (
+                "linode_dns_record_parked",
+                "Whether the record points at the parked sentinel target.",
+                |rec| Some(if rec.parked { 1.0 } else { 0.0 }),
+            ),
+            (
+                "linode_dns_record_ttl_seconds",
+                "TTL of the record.",
+                |rec| Some(f64::from(rec.ttl_sec)),
+            ),
+            (
+                "linode_dns_record_last_change_age_seconds",
+                "Seconds since the record was last changed.",
+                |rec| rec.age_seconds.map(|age| age as f64),
+            ),
+        ];
+        for (metric, help, value) in families {
+            let _ = writeln!(out, "# HELP {} {}", metric, help);
+            let _ = writeln!(out, "# TYPE {} gauge", metric);
+            for rec in &self.records {
+                if let Some(value) = value(rec) {
+                    let _ = writeln!(
+                        out,
+                        "{}{{domain=\"{}\",name=\"{}\",target=\"{}\"}} {}",
+                        metric, self.domain, rec.name, rec.target, value
+                    );
+                }
+            }
+        }
+        out
+    }
+}
+
+// seconds since the epoch for the API's "2024-05-01T12:00:00" UTC times
+pub(crate) fn unix_seconds(timestamp: &str) -> Option<u64> {
+    let (date, time) = timestamp.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time
+        .trim_end_matches('Z')
+        .splitn(3, ':')
+        .map(|part| part.split('.').next().unwrap_or(part).parse::<i64>());
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    // days from civil, counting years from March so leap days come last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    u64::try_from(days * 86_400 + hour * 3_600 + minute * 60 + second).ok()
+}
+
+impl LinodeClient {
+    // Health of the A records named {prefix}-... in domain, e.g. prefix
+    // "api" or "api-us-iad". Targets are probed on port when given.
+    pub async fn zone_health(
+        &self,
+        domain: u64,
+        prefixes: &[String],
+        port: Option<u16>,
+        timeout: Duration,
+    ) -> Result<ZoneHealth, LinodeError> {
+        let zone = self.fetch_domain(domain).await?;
+        let records = self.fetch_records(domain).await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        let mut health = Vec::new();
+        for rec in records.iter().filter(|rec| {
+            rec.record_type == A_RECORD
+                && prefixes
+                    .iter()
+                    .any(|prefix| rec.name.starts_with(&format!("{}-", prefix)))
+        }) {
+            let parked = rec.target == LOCALHOST;
+            let reachable = match port {
+                Some(port) if !parked => Some(check_tcp(&rec.target, port, timeout).await.healthy),
+                _ => None,
+            };
+            let age_seconds = rec
+                .updated
+                .as_deref()
+                .and_then(unix_seconds)
+                .map(|updated| now.saturating_sub(updated));
+            health.push(RecordHealth {
+                name: rec.name.clone(),
+                target: rec.target.clone(),
+                ttl_sec: rec.ttl_sec,
+                parked,
+                reachable,
+                age_seconds,
+            });
+        }
+
+        info!(
+            "Checked {} managed records in domain ID: {}",
+            health.len(),
+            domain
+        );
+        Ok(ZoneHealth {
+            domain: zone.domain,
+            records: health,
+        })
+    }
+}